@@ -4,8 +4,15 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use bilateral_exchange::contract_info::ContractInfo;
-use bilateral_exchange::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use bilateral_exchange::state::{AskOrder, BidOrder};
+use bilateral_exchange::msg::{
+    CancelResponse, ExecuteMsg, InstantiateMsg, OraclePriceResponse, OracleQueryMsg,
+    PairStatsResponse, QueryMsg, SimulateMatchResponse, SolvencyReport, TriggerOrdersResponse,
+    TwapResponse,
+};
+use bilateral_exchange::state::{
+    AskOrder, Auction, BidOrder, ConditionalOrder, Config, Htlc, Market, OperatorApproval, Rfq,
+    Swap, Trade, TradingPair,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -14,9 +21,27 @@ fn main() {
     remove_schemas(&out_dir).unwrap();
 
     export_schema(&schema_for!(AskOrder), &out_dir);
+    export_schema(&schema_for!(Auction), &out_dir);
     export_schema(&schema_for!(BidOrder), &out_dir);
+    export_schema(&schema_for!(CancelResponse), &out_dir);
+    export_schema(&schema_for!(ConditionalOrder), &out_dir);
+    export_schema(&schema_for!(Config), &out_dir);
     export_schema(&schema_for!(ContractInfo), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(Htlc), &out_dir);
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(Market), &out_dir);
+    export_schema(&schema_for!(OperatorApproval), &out_dir);
+    export_schema(&schema_for!(OraclePriceResponse), &out_dir);
+    export_schema(&schema_for!(OracleQueryMsg), &out_dir);
+    export_schema(&schema_for!(PairStatsResponse), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(Rfq), &out_dir);
+    export_schema(&schema_for!(SimulateMatchResponse), &out_dir);
+    export_schema(&schema_for!(SolvencyReport), &out_dir);
+    export_schema(&schema_for!(Swap), &out_dir);
+    export_schema(&schema_for!(Trade), &out_dir);
+    export_schema(&schema_for!(TradingPair), &out_dir);
+    export_schema(&schema_for!(TriggerOrdersResponse), &out_dir);
+    export_schema(&schema_for!(TwapResponse), &out_dir);
 }
@@ -0,0 +1,678 @@
+use cosmwasm_std::{
+    coin, to_binary, BankMsg, Coin, DepsMut, Env, MessageInfo, Response, Timestamp, Uint128,
+};
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+
+use crate::config::check_allowed_denoms;
+use crate::error::ContractError;
+use crate::escrow::{deposit_escrow, release_escrow};
+use crate::events::{
+    EventAttributes, ASKER_KEY, BASE_KEY, BIDDER_KEY, ORDER_ID_KEY, OWNER_KEY, QUOTE_KEY,
+};
+use crate::state::{Auction, AuctionBid, Side, AUCTIONS};
+use crate::validation::{normalize_coins, validate_denom, validate_id};
+
+// create auction entrypoint
+#[allow(clippy::too_many_arguments)]
+pub fn create_auction(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    end_time: Timestamp,
+    min_increment: Uint128,
+    reserve_price: Coin,
+    time_extension: Option<u64>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&id)?;
+    let base = normalize_coins(info.funds, "base")?;
+    if base.is_empty() {
+        return Err(ContractError::MissingAuctionBase);
+    }
+    if reserve_price.denom.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "reserve_price".into(),
+        });
+    }
+    validate_denom(&reserve_price.denom)?;
+    if min_increment.is_zero() {
+        return Err(ContractError::InvalidField {
+            field: "min_increment".into(),
+        });
+    }
+    if end_time <= env.block.time {
+        return Err(ContractError::InvalidField {
+            field: "end_time".into(),
+        });
+    }
+    if AUCTIONS.has(deps.storage, &id) {
+        return Err(ContractError::AuctionAlreadyExists {});
+    }
+    check_allowed_denoms(deps.storage, &base)?;
+    check_allowed_denoms(deps.storage, &[reserve_price.to_owned()])?;
+
+    let auction = Auction {
+        base,
+        end_time,
+        high_bid: None,
+        id,
+        min_increment,
+        owner: info.sender,
+        reserve_price,
+        time_extension,
+    };
+
+    AUCTIONS.save(deps.storage, &auction.id, &auction)?;
+    deposit_escrow(deps.storage, &auction.base)?;
+
+    Ok(Response::new()
+        .add_attributes(EventAttributes::new("create_auction", &env).order(
+            Side::Ask,
+            &auction.id,
+            auction.owner.as_str(),
+            &auction.base,
+            &[auction.reserve_price.to_owned()],
+        ))
+        .set_data(to_binary(&auction)?))
+}
+
+// place a bid on an open auction, refunding the bidder it displaces
+pub fn bid_auction(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&id)?;
+    let mut auction = AUCTIONS.load(deps.storage, &id)?;
+
+    if env.block.time >= auction.end_time {
+        return Err(ContractError::AuctionClosed {});
+    }
+    if info.sender == auction.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let quote = match normalize_coins(info.funds, "quote")?.as_slice() {
+        [quote] if quote.denom == auction.reserve_price.denom => quote.to_owned(),
+        _ => return Err(ContractError::InvalidAuctionBid {}),
+    };
+
+    let minimum = minimum_bid(&auction);
+    if quote.amount < minimum.amount {
+        return Err(ContractError::AuctionBidTooLow { minimum });
+    }
+
+    let mut response = Response::new();
+
+    // 'refund quote to outbid bidder' message
+    if let Some(outbid) = auction.high_bid.take() {
+        release_escrow(deps.storage, &[outbid.quote.to_owned()])?;
+        response = response.add_message(BankMsg::Send {
+            to_address: outbid.bidder.to_string(),
+            amount: vec![outbid.quote],
+        });
+    }
+
+    // a bid inside the extension window pushes the end time out to keep the auction open
+    if let Some(time_extension) = auction.time_extension {
+        let extended_end_time = env.block.time.plus_seconds(time_extension);
+        if extended_end_time > auction.end_time {
+            auction.end_time = extended_end_time;
+        }
+    }
+
+    let attributes = EventAttributes::new("bid_auction", &env).order(
+        Side::Bid,
+        &auction.id,
+        info.sender.as_str(),
+        &auction.base,
+        &[quote.to_owned()],
+    );
+
+    deposit_escrow(deps.storage, &[quote.to_owned()])?;
+    auction.high_bid = Some(AuctionBid {
+        bidder: info.sender,
+        quote,
+    });
+
+    AUCTIONS.save(deps.storage, &id, &auction)?;
+
+    Ok(response
+        .add_attributes(attributes)
+        .set_data(to_binary(&auction)?))
+}
+
+// settle an auction once its end time has passed
+pub fn finalize_auction(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&id)?;
+    // return error if funds sent
+    if !info.funds.is_empty() {
+        return Err(ContractError::FinalizeWithFunds {});
+    }
+
+    let auction = AUCTIONS.load(deps.storage, &id)?;
+
+    if env.block.time < auction.end_time {
+        return Err(ContractError::AuctionOpen {});
+    }
+
+    let attributes = EventAttributes::new("finalize_auction", &env).add(ORDER_ID_KEY, &id);
+
+    let (attributes, messages) = match auction.high_bid {
+        // 'send quote to seller' and 'send base to winning bidder' messages
+        Some(high_bid) => (
+            attributes
+                .add(ASKER_KEY, auction.owner.as_str())
+                .add(BIDDER_KEY, high_bid.bidder.as_str())
+                .add_coins(BASE_KEY, &auction.base)
+                .add_coins(QUOTE_KEY, &[high_bid.quote.to_owned()]),
+            vec![
+                BankMsg::Send {
+                    to_address: auction.owner.to_string(),
+                    amount: vec![high_bid.quote],
+                },
+                BankMsg::Send {
+                    to_address: high_bid.bidder.to_string(),
+                    amount: auction.base,
+                },
+            ],
+        ),
+        // 'send base back to seller' message
+        None => (
+            attributes
+                .add(OWNER_KEY, auction.owner.as_str())
+                .add_coins(BASE_KEY, &auction.base),
+            vec![BankMsg::Send {
+                to_address: auction.owner.to_string(),
+                amount: auction.base,
+            }],
+        ),
+    };
+
+    AUCTIONS.remove(deps.storage, &id);
+    for message in &messages {
+        if let BankMsg::Send { amount, .. } = message {
+            release_escrow(deps.storage, amount)?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attributes(attributes)
+        .add_messages(messages))
+}
+
+// the smallest bid the auction will currently accept
+fn minimum_bid(auction: &Auction) -> Coin {
+    match &auction.high_bid {
+        Some(high_bid) => coin(
+            (high_bid.quote.amount + auction.min_increment).u128(),
+            &auction.reserve_price.denom,
+        ),
+        None => auction.reserve_price.to_owned(),
+    }
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_info;
+    use cosmwasm_std::{attr, coins, from_binary, Addr, CosmosMsg};
+    use provwasm_mocks::mock_dependencies;
+
+    use crate::contract::{execute, query};
+    use crate::msg::{ConfigMsg, ExecuteMsg, QueryMsg};
+
+    use super::*;
+    use crate::testing::{env_at, instantiate_venue};
+
+    fn create_auction_msg(time_extension: Option<u64>) -> ExecuteMsg {
+        ExecuteMsg::CreateAuction {
+            id: "auction_id".into(),
+            end_time: Timestamp::from_seconds(1_000),
+            min_increment: Uint128::new(10),
+            reserve_price: coin(100, "quote_1"),
+            time_extension,
+        }
+    }
+
+    #[test]
+    fn create_auction_with_valid_data() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+
+        let seller_info = mock_info("seller", &coins(2, "base_1"));
+
+        // execute create auction
+        match execute(
+            deps.as_mut(),
+            env_at(100),
+            seller_info.clone(),
+            create_auction_msg(None),
+        ) {
+            Ok(response) => {
+                assert_eq!(
+                    response.attributes,
+                    vec![
+                        attr("action", "create_auction"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "100000000000"),
+                        attr("order_id", "auction_id"),
+                        attr("side", "ask"),
+                        attr("owner", "seller"),
+                        attr("base", "2base_1"),
+                        attr("quote", "100quote_1"),
+                    ]
+                );
+            }
+            Err(error) => panic!("failed to create auction: {:?}", error),
+        }
+
+        // verify auction stored
+        let query_response = query(
+            deps.as_ref(),
+            env_at(100),
+            QueryMsg::GetAuction {
+                id: "auction_id".into(),
+            },
+        );
+        assert_eq!(
+            from_binary::<Auction>(&query_response.unwrap()).unwrap(),
+            Auction {
+                base: seller_info.funds,
+                end_time: Timestamp::from_seconds(1_000),
+                high_bid: None,
+                id: "auction_id".into(),
+                min_increment: Uint128::new(10),
+                owner: seller_info.sender,
+                reserve_price: coin(100, "quote_1"),
+                time_extension: None,
+            }
+        );
+
+        // creating an auction with an existing id returns ContractError::AuctionAlreadyExists
+        match execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("seller", &coins(2, "base_1")),
+            create_auction_msg(None),
+        ) {
+            Err(ContractError::AuctionAlreadyExists {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn create_auction_with_invalid_data() {
+        let mut deps = mock_dependencies(&[]);
+
+        // create auction missing base returns ContractError::MissingAuctionBase
+        match execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("seller", &[]),
+            create_auction_msg(None),
+        ) {
+            Err(ContractError::MissingAuctionBase) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // create auction ending in the past returns ContractError::InvalidField { end_time }
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("seller", &coins(2, "base_1")),
+            create_auction_msg(None),
+        ) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "end_time"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // create auction with zero increment returns ContractError::InvalidField { min_increment }
+        match execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("seller", &coins(2, "base_1")),
+            ExecuteMsg::CreateAuction {
+                id: "auction_id".into(),
+                end_time: Timestamp::from_seconds(1_000),
+                min_increment: Uint128::zero(),
+                reserve_price: coin(100, "quote_1"),
+                time_extension: None,
+            },
+        ) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "min_increment"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // create auction with an invalid id returns ContractError::InvalidId
+        match execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("seller", &coins(2, "base_1")),
+            ExecuteMsg::CreateAuction {
+                id: "auction id".into(),
+                end_time: Timestamp::from_seconds(1_000),
+                min_increment: Uint128::new(10),
+                reserve_price: coin(100, "quote_1"),
+                time_extension: None,
+            },
+        ) {
+            Err(ContractError::InvalidId { id }) => assert_eq!(id, "auction id"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // create auction with a zero base coin returns ContractError::ZeroAmount
+        match execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("seller", &[coin(2, "base_1"), coin(0, "base_2")]),
+            create_auction_msg(None),
+        ) {
+            Err(ContractError::ZeroAmount { denom, field }) => {
+                assert_eq!((denom.as_str(), field.as_str()), ("base_2", "base"))
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn bid_auction_refunds_outbid_bidder() {
+        let mut deps = mock_dependencies(&[]);
+        if let Err(error) = execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("seller", &coins(2, "base_1")),
+            create_auction_msg(None),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+
+        let bid_msg = ExecuteMsg::BidAuction {
+            id: "auction_id".into(),
+        };
+
+        // bid below the reserve price returns ContractError::AuctionBidTooLow
+        match execute(
+            deps.as_mut(),
+            env_at(200),
+            mock_info("bidder_1", &coins(99, "quote_1")),
+            bid_msg.clone(),
+        ) {
+            Err(ContractError::AuctionBidTooLow { minimum }) => {
+                assert_eq!(minimum, coin(100, "quote_1"))
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // bid in the wrong denom returns ContractError::InvalidAuctionBid
+        match execute(
+            deps.as_mut(),
+            env_at(200),
+            mock_info("bidder_1", &coins(100, "quote_2")),
+            bid_msg.clone(),
+        ) {
+            Err(ContractError::InvalidAuctionBid {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // seller bidding on their own auction returns ContractError::Unauthorized
+        match execute(
+            deps.as_mut(),
+            env_at(200),
+            mock_info("seller", &coins(100, "quote_1")),
+            bid_msg.clone(),
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // first bid at the reserve price has nothing to refund
+        match execute(
+            deps.as_mut(),
+            env_at(200),
+            mock_info("bidder_1", &coins(100, "quote_1")),
+            bid_msg.clone(),
+        ) {
+            Ok(response) => {
+                assert_eq!(response.messages.len(), 0);
+                assert_eq!(response.attributes[0], attr("action", "bid_auction"));
+            }
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+
+        // bid under the minimum increment returns ContractError::AuctionBidTooLow
+        match execute(
+            deps.as_mut(),
+            env_at(300),
+            mock_info("bidder_2", &coins(109, "quote_1")),
+            bid_msg.clone(),
+        ) {
+            Err(ContractError::AuctionBidTooLow { minimum }) => {
+                assert_eq!(minimum, coin(110, "quote_1"))
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // outbidding refunds the previous high bidder
+        match execute(
+            deps.as_mut(),
+            env_at(300),
+            mock_info("bidder_2", &coins(110, "quote_1")),
+            bid_msg,
+        ) {
+            Ok(response) => {
+                assert_eq!(response.messages.len(), 1);
+                assert_eq!(
+                    response.messages[0].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "bidder_1".into(),
+                        amount: coins(100, "quote_1"),
+                    })
+                );
+            }
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+
+        let auction = AUCTIONS.load(&deps.storage, "auction_id").unwrap();
+        assert_eq!(
+            auction.high_bid,
+            Some(AuctionBid {
+                bidder: Addr::unchecked("bidder_2"),
+                quote: coin(110, "quote_1"),
+            })
+        );
+        assert_eq!(auction.end_time, Timestamp::from_seconds(1_000));
+    }
+
+    #[test]
+    fn bid_auction_extends_end_time() {
+        let mut deps = mock_dependencies(&[]);
+        if let Err(error) = execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("seller", &coins(2, "base_1")),
+            create_auction_msg(Some(60)),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+
+        let bid_msg = ExecuteMsg::BidAuction {
+            id: "auction_id".into(),
+        };
+
+        // a bid outside the extension window leaves the end time alone
+        if let Err(error) = execute(
+            deps.as_mut(),
+            env_at(900),
+            mock_info("bidder_1", &coins(100, "quote_1")),
+            bid_msg.clone(),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+        assert_eq!(
+            AUCTIONS.load(&deps.storage, "auction_id").unwrap().end_time,
+            Timestamp::from_seconds(1_000)
+        );
+
+        // a bid inside the extension window pushes the end time out
+        if let Err(error) = execute(
+            deps.as_mut(),
+            env_at(990),
+            mock_info("bidder_2", &coins(110, "quote_1")),
+            bid_msg.clone(),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+        assert_eq!(
+            AUCTIONS.load(&deps.storage, "auction_id").unwrap().end_time,
+            Timestamp::from_seconds(1_050)
+        );
+
+        // the original end time no longer closes the auction
+        if let Err(error) = execute(
+            deps.as_mut(),
+            env_at(1_020),
+            mock_info("bidder_1", &coins(120, "quote_1")),
+            bid_msg.clone(),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+
+        // bidding after the extended end time returns ContractError::AuctionClosed
+        match execute(
+            deps.as_mut(),
+            env_at(1_080),
+            mock_info("bidder_2", &coins(130, "quote_1")),
+            bid_msg,
+        ) {
+            Err(ContractError::AuctionClosed {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn finalize_auction_settles_or_returns_base() {
+        let mut deps = mock_dependencies(&[]);
+        if let Err(error) = execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("seller", &coins(2, "base_1")),
+            create_auction_msg(None),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+        if let Err(error) = execute(
+            deps.as_mut(),
+            env_at(200),
+            mock_info("bidder_1", &coins(150, "quote_1")),
+            ExecuteMsg::BidAuction {
+                id: "auction_id".into(),
+            },
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+
+        let finalize_msg = ExecuteMsg::FinalizeAuction {
+            id: "auction_id".into(),
+        };
+
+        // finalize before the end time returns ContractError::AuctionOpen
+        match execute(
+            deps.as_mut(),
+            env_at(999),
+            mock_info("anyone", &[]),
+            finalize_msg.clone(),
+        ) {
+            Err(ContractError::AuctionOpen {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // finalize with funds returns ContractError::FinalizeWithFunds
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("anyone", &coins(1, "sent_coin")),
+            finalize_msg.clone(),
+        ) {
+            Err(ContractError::FinalizeWithFunds {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // anyone may finalize once the auction has ended
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("anyone", &[]),
+            finalize_msg,
+        ) {
+            Ok(response) => {
+                assert_eq!(
+                    response.attributes,
+                    vec![
+                        attr("action", "finalize_auction"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "1000000000000"),
+                        attr("order_id", "auction_id"),
+                        attr("asker", "seller"),
+                        attr("bidder", "bidder_1"),
+                        attr("base", "2base_1"),
+                        attr("quote", "150quote_1"),
+                    ]
+                );
+                assert_eq!(response.messages.len(), 2);
+                assert_eq!(
+                    response.messages[0].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "seller".into(),
+                        amount: coins(150, "quote_1"),
+                    })
+                );
+                assert_eq!(
+                    response.messages[1].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "bidder_1".into(),
+                        amount: coins(2, "base_1"),
+                    })
+                );
+            }
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+        assert!(!AUCTIONS.has(&deps.storage, "auction_id"));
+
+        // an auction without bids returns the base to the seller
+        if let Err(error) = execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("seller", &coins(2, "base_1")),
+            create_auction_msg(None),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("anyone", &[]),
+            ExecuteMsg::FinalizeAuction {
+                id: "auction_id".into(),
+            },
+        ) {
+            Ok(response) => {
+                assert_eq!(response.messages.len(), 1);
+                assert_eq!(
+                    response.messages[0].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "seller".into(),
+                        amount: coins(2, "base_1"),
+                    })
+                );
+            }
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+    }
+}
@@ -0,0 +1,676 @@
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order, Response,
+};
+use provwasm_std::{transfer_marker_coins, ProvenanceMsg, ProvenanceQuerier, ProvenanceQuery};
+
+use crate::config::get_config;
+use crate::contract::{add_coins, cancel_ask_order, cancel_bid_order, check_open, sorted_coins};
+use crate::contract_info::get_contract_info;
+use crate::error::ContractError;
+use crate::events::{EventAttributes, OWNER_KEY};
+use crate::msg::CancelResponse;
+use crate::state::{
+    get_ask_storage_read, get_bid_storage_read, load_ask, load_bid, order_key, AskOrder, BidOrder,
+    Side, MARKETS, OWNER_ORDERS,
+};
+use crate::validation::{validate_order_id, validate_reason};
+
+// most orders cancelled by a single bulk cancel
+const CANCEL_LIMIT: u32 = 30;
+
+// cancel the sender's open orders, optionally of one side or trading a denom on either side
+pub fn cancel_all(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    side: Option<Side>,
+    denom: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::CancelWithFunds {});
+    }
+    let limit = limit.unwrap_or(CANCEL_LIMIT).min(CANCEL_LIMIT) as usize;
+
+    let sides = match side {
+        Some(side) => vec![side],
+        None => vec![Side::Ask, Side::Bid],
+    };
+    // at most limit of the owner's open orders are visited, whatever they trade
+    let mut keys = vec![];
+    for side in sides {
+        for item in OWNER_ORDERS
+            .prefix((info.sender.as_str(), &side.to_string()))
+            .range(deps.storage, None, None, Order::Ascending)
+            .take(limit - keys.len())
+        {
+            let (id, market) = item?;
+            keys.push((side, order_key(market.as_deref(), &id)));
+        }
+    }
+    let trades_denom = |base: &[Coin], quote: &[Coin]| match &denom {
+        Some(denom) => base.iter().chain(quote).any(|coin| &coin.denom == denom),
+        None => true,
+    };
+
+    let mut ask_orders = vec![];
+    let mut bid_orders = vec![];
+    for (side, key) in keys {
+        match side {
+            Side::Ask => {
+                let ask_order = get_ask_storage_read(deps.storage).load(&key)?;
+                // good-till-time orders past their expiration are left for the expiry sweep
+                if !ask_order.status_at(env.block.time).is_closed()
+                    && trades_denom(&ask_order.base, &ask_order.quote)
+                {
+                    ask_orders.push(ask_order);
+                }
+            }
+            Side::Bid => {
+                let bid_order = get_bid_storage_read(deps.storage).load(&key)?;
+                if !bid_order.status_at(env.block.time).is_closed()
+                    && trades_denom(&bid_order.base, &bid_order.quote)
+                {
+                    bid_orders.push(bid_order);
+                }
+            }
+        }
+    }
+
+    cancel_orders(deps, env, info, "cancel_all", ask_orders, bid_orders)
+}
+
+// cancel the listed orders of a market, all of which must be open orders of the sender
+pub fn cancel_many(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    ask_ids: Vec<String>,
+    bid_ids: Vec<String>,
+    market: Option<String>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::CancelWithFunds {});
+    }
+    if ask_ids.len() + bid_ids.len() > CANCEL_LIMIT as usize {
+        return Err(ContractError::TooManyOrders { max: CANCEL_LIMIT });
+    }
+
+    let mut ask_orders: Vec<AskOrder> = vec![];
+    for id in ask_ids {
+        validate_order_id(&id, market.as_deref())?;
+        let ask_order = load_ask(deps.storage, market.as_deref(), &id)
+            .map_err(|_| ContractError::AskNotFound { id: id.to_owned() })?;
+        if ask_order.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+        check_open(ask_order.status_at(env.block.time))?;
+        // a repeated id is cancelled once
+        if !ask_orders.iter().any(|ask_order| ask_order.id == id) {
+            ask_orders.push(ask_order);
+        }
+    }
+    let mut bid_orders: Vec<BidOrder> = vec![];
+    for id in bid_ids {
+        validate_order_id(&id, market.as_deref())?;
+        let bid_order = load_bid(deps.storage, market.as_deref(), &id)
+            .map_err(|_| ContractError::BidNotFound { id: id.to_owned() })?;
+        if bid_order.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+        check_open(bid_order.status_at(env.block.time))?;
+        if !bid_orders.iter().any(|bid_order| bid_order.id == id) {
+            bid_orders.push(bid_order);
+        }
+    }
+
+    cancel_orders(deps, env, info, "cancel_many", ask_orders, bid_orders)
+}
+
+// cancel open orders of the sender and refund their escrow in one bank send
+fn cancel_orders(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    action: &str,
+    ask_orders: Vec<AskOrder>,
+    bid_orders: Vec<BidOrder>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let mut refund = vec![];
+    let mut ask_ids = vec![];
+    for mut ask_order in ask_orders {
+        cancel_ask_order(deps.storage, &mut ask_order, env.block.time, None)?;
+        refund = add_coins(&refund, &ask_order.base);
+        ask_ids.push(ask_order.id);
+    }
+    let mut bid_ids = vec![];
+    for mut bid_order in bid_orders {
+        cancel_bid_order(deps.storage, &mut bid_order, env.block.time, None)?;
+        refund = add_coins(&refund, &bid_order.quote);
+        bid_ids.push(bid_order.id);
+    }
+    let refund = sorted_coins(&refund);
+
+    let mut response = Response::new().add_attributes(
+        EventAttributes::new(action, &env)
+            .add(OWNER_KEY, info.sender.as_str())
+            .add("cancelled", (ask_ids.len() + bid_ids.len()).to_string())
+            .add_coins("refund", &refund),
+    );
+    if !refund.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund.to_owned(),
+        });
+    }
+
+    Ok(response.set_data(to_binary(&CancelResponse {
+        ask_ids,
+        bid_ids,
+        refund,
+    })?))
+}
+
+// cancel any open ask, admin or the market admin only, the reason is kept in the ask's status
+// history
+pub fn force_cancel_ask(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    reason: String,
+    market: Option<String>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_order_id(&id, market.as_deref())?;
+    check_force_cancel(deps.as_ref(), &info, market.as_deref(), &reason)?;
+
+    let mut ask_order = load_ask(deps.storage, market.as_deref(), &id)
+        .map_err(|_| ContractError::AskNotFound { id })?;
+    check_open(ask_order.status_at(env.block.time))?;
+    let (messages, compliance_refund) =
+        force_refund(deps.as_ref(), &env, &ask_order.owner, &ask_order.base)?;
+    cancel_ask_order(
+        deps.storage,
+        &mut ask_order,
+        env.block.time,
+        Some(reason.to_owned()),
+    )?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("force_cancel_ask", &env)
+                .ask(&ask_order)
+                .add("reason", reason)
+                .add_coins("compliance_refund", &compliance_refund),
+        )
+        .add_messages(messages)
+        .set_data(to_binary(&ask_order)?))
+}
+
+// cancel any open bid, admin or the market admin only, the reason is kept in the bid's status
+// history
+pub fn force_cancel_bid(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    reason: String,
+    market: Option<String>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_order_id(&id, market.as_deref())?;
+    check_force_cancel(deps.as_ref(), &info, market.as_deref(), &reason)?;
+
+    let mut bid_order = load_bid(deps.storage, market.as_deref(), &id)
+        .map_err(|_| ContractError::BidNotFound { id })?;
+    check_open(bid_order.status_at(env.block.time))?;
+    let (messages, compliance_refund) =
+        force_refund(deps.as_ref(), &env, &bid_order.owner, &bid_order.quote)?;
+    cancel_bid_order(
+        deps.storage,
+        &mut bid_order,
+        env.block.time,
+        Some(reason.to_owned()),
+    )?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("force_cancel_bid", &env)
+                .bid(&bid_order)
+                .add("reason", reason)
+                .add_coins("compliance_refund", &compliance_refund),
+        )
+        .add_messages(messages)
+        .set_data(to_binary(&bid_order)?))
+}
+
+fn check_force_cancel(
+    deps: Deps<ProvenanceQuery>,
+    info: &MessageInfo,
+    market: Option<&str>,
+    reason: &str,
+) -> Result<(), ContractError> {
+    let is_market_admin = match market {
+        Some(market) => matches!(
+            MARKETS.may_load(deps.storage, market)?,
+            Some(market) if market.admin == info.sender
+        ),
+        None => false,
+    };
+    if !is_market_admin && info.sender != get_contract_info(deps.storage)?.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !info.funds.is_empty() {
+        return Err(ContractError::CancelWithFunds {});
+    }
+    validate_reason(reason)
+}
+
+// refund escrow to the owner, except coins of restricted markers which can not be bank sent and are
+// transferred to the compliance address instead. Returns the messages and the coins transferred.
+fn force_refund(
+    deps: Deps<ProvenanceQuery>,
+    env: &Env,
+    owner: &Addr,
+    escrow: &[Coin],
+) -> Result<(Vec<CosmosMsg<ProvenanceMsg>>, Vec<Coin>), ContractError> {
+    let querier = ProvenanceQuerier::new(&deps.querier);
+    let (restricted, unrestricted): (Vec<Coin>, Vec<Coin>) =
+        escrow.iter().cloned().partition(|coin| {
+            // denoms without a marker are plain bank coins
+            matches!(
+                querier.get_marker_by_denom(&coin.denom),
+                Ok(marker) if marker.bank_sends_disabled()
+            )
+        });
+
+    let mut messages = vec![];
+    if !unrestricted.is_empty() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: owner.to_string(),
+            amount: unrestricted,
+        }));
+    }
+    if !restricted.is_empty() {
+        let compliance_address = get_config(deps.storage)?
+            .compliance_address
+            .ok_or(ContractError::ComplianceAddressNotSet {})?;
+        for coin in &restricted {
+            messages.push(transfer_marker_coins(
+                coin.amount.u128(),
+                &coin.denom,
+                compliance_address.to_owned(),
+                env.contract.address.to_owned(),
+            )?);
+        }
+    }
+    Ok((messages, restricted))
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{coin, coins, from_binary, Addr, CosmosMsg, Decimal};
+    use provwasm_mocks::mock_dependencies;
+    use provwasm_std::{Marker, MarkerStatus, MarkerType};
+
+    use super::*;
+    use crate::contract::{execute, query};
+    use crate::msg::{ConfigMsg, ExecuteMsg, QueryMsg};
+    use crate::state::OrderStatus;
+    use crate::testing::instantiate_venue;
+
+    fn create_order(deps: DepsMut<ProvenanceQuery>, owner: &str, side: Side, funds: Vec<Coin>) {
+        let msg = match side {
+            Side::Ask => ExecuteMsg::CreateAsk {
+                client_order_id: None,
+                quote: coins(10, "quote_1"),
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+            Side::Bid => ExecuteMsg::CreateBid {
+                base: coins(10, "base_1"),
+                client_order_id: None,
+                effective_time: None,
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        };
+        execute(deps, mock_env(), mock_info(owner, &funds), msg).unwrap();
+    }
+
+    fn setup(deps: DepsMut<ProvenanceQuery>) {
+        instantiate_venue(deps, ConfigMsg::default());
+    }
+
+    fn cancel(
+        deps: DepsMut<ProvenanceQuery>,
+        msg: ExecuteMsg,
+    ) -> Result<(CancelResponse, Vec<CosmosMsg<ProvenanceMsg>>), ContractError> {
+        execute(deps, mock_env(), mock_info("trader", &[]), msg).map(|response| {
+            (
+                from_binary(&response.data.unwrap()).unwrap(),
+                response.messages.into_iter().map(|msg| msg.msg).collect(),
+            )
+        })
+    }
+
+    #[test]
+    fn cancel_all_filters_and_refunds_in_one_send() {
+        let mut deps = mock_dependencies(&[]);
+        setup(deps.as_mut());
+        // ids 1 to 5
+        create_order(deps.as_mut(), "trader", Side::Ask, coins(10, "base_1"));
+        create_order(deps.as_mut(), "trader", Side::Ask, coins(20, "base_2"));
+        create_order(deps.as_mut(), "trader", Side::Bid, coins(10, "quote_1"));
+        create_order(deps.as_mut(), "trader", Side::Bid, coins(5, "quote_1"));
+        create_order(deps.as_mut(), "other", Side::Ask, coins(10, "base_1"));
+
+        let (cancelled, messages) = cancel(
+            deps.as_mut(),
+            ExecuteMsg::CancelAll {
+                side: Some(Side::Bid),
+                denom: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            cancelled,
+            CancelResponse {
+                ask_ids: vec![],
+                bid_ids: vec!["3".into(), "4".into()],
+                refund: coins(15, "quote_1"),
+            }
+        );
+        assert_eq!(
+            messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                to_address: "trader".into(),
+                amount: coins(15, "quote_1"),
+            })]
+        );
+
+        // asks trading base_2 on either side
+        let (cancelled, _) = cancel(
+            deps.as_mut(),
+            ExecuteMsg::CancelAll {
+                side: None,
+                denom: Some("base_2".into()),
+                limit: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(cancelled.ask_ids, vec!["2".to_string()]);
+
+        // the other owner's ask is left open
+        let (cancelled, _) = cancel(
+            deps.as_mut(),
+            ExecuteMsg::CancelAll {
+                side: None,
+                denom: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(cancelled.ask_ids, vec!["1".to_string()]);
+        assert_eq!(
+            get_ask_storage_read(&deps.storage)
+                .load(b"1")
+                .unwrap()
+                .status,
+            OrderStatus::Cancelled
+        );
+        assert_eq!(
+            get_ask_storage_read(&deps.storage)
+                .load(b"5")
+                .unwrap()
+                .status,
+            OrderStatus::Open
+        );
+
+        // nothing is left to cancel or refund
+        let (cancelled, messages) = cancel(
+            deps.as_mut(),
+            ExecuteMsg::CancelAll {
+                side: None,
+                denom: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(cancelled.refund, vec![]);
+        assert_eq!(messages, vec![]);
+        assert_eq!(
+            query(deps.as_ref(), mock_env(), QueryMsg::GetEscrowTotals {}),
+            to_binary(&coins(10, "base_1"))
+        );
+    }
+
+    #[test]
+    fn cancel_all_respects_limit() {
+        let mut deps = mock_dependencies(&[]);
+        setup(deps.as_mut());
+        for _ in 0..3 {
+            create_order(deps.as_mut(), "trader", Side::Ask, coins(10, "base_1"));
+        }
+
+        let (cancelled, messages) = cancel(
+            deps.as_mut(),
+            ExecuteMsg::CancelAll {
+                side: None,
+                denom: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap();
+        assert_eq!(cancelled.ask_ids, vec!["1".to_string(), "2".to_string()]);
+        assert_eq!(
+            messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                to_address: "trader".into(),
+                amount: coins(20, "base_1"),
+            })]
+        );
+
+        // the limit bounds the orders looked at, not the orders cancelled
+        create_order(deps.as_mut(), "trader", Side::Ask, coins(10, "base_2"));
+        create_order(deps.as_mut(), "trader", Side::Ask, coins(10, "base_1"));
+        let (cancelled, _) = cancel(
+            deps.as_mut(),
+            ExecuteMsg::CancelAll {
+                side: None,
+                denom: Some("base_1".into()),
+                limit: Some(2),
+            },
+        )
+        .unwrap();
+        assert_eq!(cancelled.ask_ids, vec!["3".to_string()]);
+    }
+
+    #[test]
+    fn cancel_many_cancels_all_or_nothing() {
+        let mut deps = mock_dependencies(&[]);
+        setup(deps.as_mut());
+        create_order(deps.as_mut(), "trader", Side::Ask, coins(10, "base_1"));
+        create_order(deps.as_mut(), "trader", Side::Bid, coins(10, "quote_1"));
+        create_order(deps.as_mut(), "other", Side::Ask, coins(10, "base_1"));
+
+        let mut cancel_many = |ask_ids: &[&str], bid_ids: &[&str]| {
+            cancel(
+                deps.as_mut(),
+                ExecuteMsg::CancelMany {
+                    ask_ids: ask_ids.iter().map(|id| id.to_string()).collect(),
+                    bid_ids: bid_ids.iter().map(|id| id.to_string()).collect(),
+                    market: None,
+                },
+            )
+        };
+
+        match cancel_many(&["1", "3"], &[]) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match cancel_many(&["1"], &["1"]) {
+            Err(ContractError::BidNotFound { id }) => assert_eq!(id, "1"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match cancel_many(&["1"; CANCEL_LIMIT as usize + 1], &[]) {
+            Err(ContractError::TooManyOrders { max }) => assert_eq!(max, CANCEL_LIMIT),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let (cancelled, messages) = cancel_many(&["1", "1"], &["2"]).unwrap();
+        assert_eq!(
+            cancelled,
+            CancelResponse {
+                ask_ids: vec!["1".into()],
+                bid_ids: vec!["2".into()],
+                refund: vec![coin(10, "base_1"), coin(10, "quote_1")],
+            }
+        );
+        assert_eq!(messages.len(), 1);
+
+        match cancel_many(&["1"], &[]) {
+            Err(ContractError::OrderClosed { status }) => assert_eq!(status, "cancelled"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    fn restricted_marker(denom: &str) -> Marker {
+        Marker {
+            address: Addr::unchecked("marker_address"),
+            coins: vec![],
+            account_number: 10,
+            sequence: 0,
+            manager: "".into(),
+            permissions: vec![],
+            status: MarkerStatus::Active,
+            denom: denom.into(),
+            total_supply: Decimal::from_ratio(1000u128, 1u128),
+            marker_type: MarkerType::Restricted,
+            supply_fixed: true,
+        }
+    }
+
+    fn force_cancel_ask(
+        deps: DepsMut<ProvenanceQuery>,
+        sender: &str,
+        reason: &str,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::ForceCancelAsk {
+                id: "1".into(),
+                reason: reason.into(),
+                market: None,
+            },
+        )
+    }
+
+    #[test]
+    fn force_cancel_sends_restricted_coins_to_compliance_address() {
+        let mut deps = mock_dependencies(&[]);
+        deps.querier
+            .with_markers(vec![restricted_marker("restricted_1")]);
+        setup(deps.as_mut());
+        create_order(
+            deps.as_mut(),
+            "trader",
+            Side::Ask,
+            vec![coin(10, "base_1"), coin(5, "restricted_1")],
+        );
+        create_order(deps.as_mut(), "trader", Side::Bid, coins(10, "quote_1"));
+
+        match force_cancel_ask(deps.as_mut(), "trader", "sanctioned") {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match force_cancel_ask(deps.as_mut(), "venue_admin", " ") {
+            Err(ContractError::MissingField { field }) => assert_eq!(field, "reason"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match force_cancel_ask(deps.as_mut(), "venue_admin", "sanctioned") {
+            Err(ContractError::ComplianceAddressNotSet {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            ExecuteMsg::UpdateConfig {
+                admin: None,
+                config: ConfigMsg {
+                    compliance_address: Some("compliance".into()),
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+        let response = force_cancel_ask(deps.as_mut(), "venue_admin", "sanctioned").unwrap();
+        assert_eq!(
+            response
+                .messages
+                .into_iter()
+                .map(|msg| msg.msg)
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "trader".into(),
+                    amount: coins(10, "base_1"),
+                }),
+                transfer_marker_coins(
+                    5,
+                    "restricted_1",
+                    Addr::unchecked("compliance"),
+                    mock_env().contract.address,
+                )
+                .unwrap(),
+            ]
+        );
+        assert!(response
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "reason" && attr.value == "sanctioned"));
+        let ask_order: AskOrder = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(ask_order.status, OrderStatus::Cancelled);
+        assert_eq!(
+            ask_order.status_updates.last().unwrap().reason,
+            Some("sanctioned".into())
+        );
+
+        match force_cancel_ask(deps.as_mut(), "venue_admin", "sanctioned") {
+            Err(ContractError::OrderClosed { status }) => assert_eq!(status, "cancelled"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // unrestricted escrow is refunded to the owner
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            ExecuteMsg::ForceCancelBid {
+                id: "2".into(),
+                reason: "sanctioned".into(),
+                market: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "trader".into(),
+                amount: coins(10, "quote_1"),
+            })
+        );
+        assert_eq!(
+            query(deps.as_ref(), mock_env(), QueryMsg::GetEscrowTotals {}),
+            to_binary(&Vec::<Coin>::new())
+        );
+    }
+}
@@ -0,0 +1,652 @@
+use cosmwasm_std::{
+    to_binary, BankMsg, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Order, QueryRequest,
+    Response, StdResult, Storage, Timestamp, WasmQuery,
+};
+use cw_storage_plus::Bound;
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+
+use crate::config::{check_allowed_denoms, get_config};
+use crate::contract::{next_order_id, open_ask, open_bid};
+use crate::error::ContractError;
+use crate::escrow::{deposit_escrow, release_escrow};
+use crate::events::EventAttributes;
+use crate::markets::load_market;
+use crate::msg::{OraclePriceResponse, OracleQueryMsg, TriggerOrdersResponse};
+use crate::pairs::validate_order_pair;
+use crate::state::{
+    price_key, ConditionalOrder, Pair, PriceTrigger, Side, TimeInForce, TriggerCondition,
+    CONDITIONAL_ORDERS, PAIR_CONDITIONAL_ORDERS, PAIR_CONDITIONAL_TRIGGERS, PAIR_STATS,
+};
+use crate::validation::{normalize_coins, validate_id};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+// most conditional orders placed by a single trigger
+const TRIGGER_LIMIT: u32 = 30;
+
+// create a conditional ask, escrowing the base sent until the ask is triggered or cancelled
+pub fn create_conditional_ask(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    client_order_id: Option<String>,
+    quote: Vec<Coin>,
+    market: Option<String>,
+    trigger: PriceTrigger,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if info.funds.is_empty() {
+        return Err(ContractError::MissingAskBase);
+    }
+    if quote.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "quote".into(),
+        });
+    }
+    let conditional_order = ConditionalOrder {
+        base: normalize_coins(info.funds, "base")?,
+        client_order_id,
+        effective_time: None,
+        id: next_order_id(deps.storage)?,
+        market,
+        owner: info.sender,
+        quote: normalize_coins(quote, "quote")?,
+        side: Side::Ask,
+        trigger,
+    };
+    save_conditional_order(deps.storage, &conditional_order)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("create_conditional_ask", &env)
+                .conditional_order(&conditional_order),
+        )
+        .set_data(to_binary(&conditional_order)?))
+}
+
+// create a conditional bid, escrowing the quote sent until the bid is triggered or cancelled
+#[allow(clippy::too_many_arguments)]
+pub fn create_conditional_bid(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    base: Vec<Coin>,
+    client_order_id: Option<String>,
+    effective_time: Option<Timestamp>,
+    market: Option<String>,
+    trigger: PriceTrigger,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if base.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "base".into(),
+        });
+    }
+    if info.funds.is_empty() {
+        return Err(ContractError::MissingBidQuote);
+    }
+    let conditional_order = ConditionalOrder {
+        base: normalize_coins(base, "base")?,
+        client_order_id,
+        effective_time,
+        id: next_order_id(deps.storage)?,
+        market,
+        owner: info.sender,
+        quote: normalize_coins(info.funds, "quote")?,
+        side: Side::Bid,
+        trigger,
+    };
+    save_conditional_order(deps.storage, &conditional_order)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("create_conditional_bid", &env)
+                .conditional_order(&conditional_order),
+        )
+        .set_data(to_binary(&conditional_order)?))
+}
+
+// validate, store and escrow a new conditional order. The order is checked like an ask or bid now
+// and again when it is triggered, since the venue may have changed in between.
+fn save_conditional_order(
+    storage: &mut dyn Storage,
+    conditional_order: &ConditionalOrder,
+) -> Result<(), ContractError> {
+    let (base_denom, quote_denom) = conditional_pair(conditional_order)?;
+    if conditional_order.trigger.price.is_zero() {
+        return Err(ContractError::InvalidField {
+            field: "price".into(),
+        });
+    }
+    if let Some(client_order_id) = &conditional_order.client_order_id {
+        validate_id(client_order_id)?;
+    }
+    load_market(storage, conditional_order.market.as_deref())?;
+    validate_order_pair(storage, &conditional_order.base, &conditional_order.quote)?;
+    check_allowed_denoms(storage, &conditional_order.base)?;
+    check_allowed_denoms(storage, &conditional_order.quote)?;
+
+    CONDITIONAL_ORDERS.save(storage, &conditional_order.id, conditional_order)?;
+    PAIR_CONDITIONAL_ORDERS.save(
+        storage,
+        (&base_denom, &quote_denom, &conditional_order.id),
+        &Empty {},
+    )?;
+    PAIR_CONDITIONAL_TRIGGERS.save(
+        storage,
+        (
+            (&base_denom, &quote_denom),
+            &conditional_order.trigger.condition.to_string(),
+            (
+                price_key(conditional_order.trigger.price),
+                &conditional_order.id,
+            ),
+        ),
+        &Empty {},
+    )?;
+    deposit_escrow(storage, conditional_order.escrow())?;
+    Ok(())
+}
+
+// the (base denom, quote denom) whose reference price triggers the order
+fn conditional_pair(
+    conditional_order: &ConditionalOrder,
+) -> Result<(String, String), ContractError> {
+    match (
+        conditional_order.base.as_slice(),
+        conditional_order.quote.as_slice(),
+    ) {
+        ([base], [quote]) => Ok((base.denom.to_owned(), quote.denom.to_owned())),
+        _ => Err(ContractError::InvalidConditionalOrder {}),
+    }
+}
+
+fn remove_conditional_order(
+    storage: &mut dyn Storage,
+    conditional_order: &ConditionalOrder,
+) -> Result<(), ContractError> {
+    let (base_denom, quote_denom) = conditional_pair(conditional_order)?;
+    CONDITIONAL_ORDERS.remove(storage, &conditional_order.id);
+    PAIR_CONDITIONAL_ORDERS.remove(storage, (&base_denom, &quote_denom, &conditional_order.id));
+    PAIR_CONDITIONAL_TRIGGERS.remove(
+        storage,
+        (
+            (&base_denom, &quote_denom),
+            &conditional_order.trigger.condition.to_string(),
+            (
+                price_key(conditional_order.trigger.price),
+                &conditional_order.id,
+            ),
+        ),
+    );
+    release_escrow(storage, conditional_order.escrow())?;
+    Ok(())
+}
+
+// cancel a conditional order of the sender that has not been triggered
+pub fn cancel_conditional_order(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&id)?;
+    if !info.funds.is_empty() {
+        return Err(ContractError::CancelWithFunds {});
+    }
+    let conditional_order = CONDITIONAL_ORDERS
+        .may_load(deps.storage, &id)?
+        .ok_or(ContractError::ConditionalOrderNotFound { id })?;
+    if conditional_order.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    remove_conditional_order(deps.storage, &conditional_order)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("cancel_conditional_order", &env)
+                .conditional_order(&conditional_order),
+        )
+        .add_message(BankMsg::Send {
+            to_address: conditional_order.owner.to_string(),
+            amount: conditional_order.escrow().to_vec(),
+        }))
+}
+
+// place up to `limit` conditional orders of the pair whose trigger the reference price meets, the
+// orders whose trigger price was crossed first come first. Anyone may trigger orders, an order that
+// can no longer be placed, ie. because its pair was disabled, is dropped and its escrow refunded to
+// the owner.
+pub fn trigger_orders(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    pair: Pair,
+    limit: Option<u32>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::ExecuteWithFunds {});
+    }
+    let limit = limit.unwrap_or(TRIGGER_LIMIT).min(TRIGGER_LIMIT) as usize;
+    let reference_price = reference_price(deps.as_ref(), &pair)?;
+
+    // only the orders the price meets are visited: at or above triggers priced up to the
+    // reference price, lowest first, and at or below triggers priced from it up, highest first
+    let pair_key = (pair.base_denom.as_str(), pair.quote_denom.as_str());
+    let (at_or_above, at_or_below) = (
+        TriggerCondition::AtOrAbove.to_string(),
+        TriggerCondition::AtOrBelow.to_string(),
+    );
+    let above_reference = reference_price
+        .atomics()
+        .u128()
+        .checked_add(1)
+        .map(|atomics| Bound::exclusive((atomics.to_be_bytes().to_vec(), "")));
+    let at_reference = Bound::inclusive((price_key(reference_price), ""));
+    let triggered = PAIR_CONDITIONAL_TRIGGERS
+        .prefix((pair_key, &at_or_above))
+        .keys(deps.storage, None, above_reference, Order::Ascending)
+        .chain(
+            PAIR_CONDITIONAL_TRIGGERS
+                .prefix((pair_key, &at_or_below))
+                .keys(deps.storage, Some(at_reference), None, Order::Descending),
+        )
+        .take(limit)
+        .map(|key| CONDITIONAL_ORDERS.load(deps.storage, &key?.1))
+        .collect::<StdResult<Vec<ConditionalOrder>>>()?;
+
+    let mut response = TriggerOrdersResponse {
+        asks: vec![],
+        bids: vec![],
+        dropped: vec![],
+        reference_price,
+    };
+    let mut messages = vec![];
+    for conditional_order in triggered {
+        remove_conditional_order(deps.storage, &conditional_order)?;
+        let ConditionalOrder {
+            base,
+            client_order_id,
+            effective_time,
+            id,
+            market,
+            owner,
+            quote,
+            side,
+            ..
+        } = conditional_order.to_owned();
+        let placed = match side {
+            Side::Ask => open_ask(
+                deps.storage,
+                &env,
+                owner,
+                base,
+                quote,
+                client_order_id,
+                market,
+                TimeInForce::Gtc,
+            )
+            .map(|ask_order| response.asks.push(ask_order)),
+            Side::Bid => open_bid(
+                deps.storage,
+                &env,
+                owner,
+                base,
+                quote,
+                client_order_id,
+                effective_time,
+                market,
+                TimeInForce::Gtc,
+            )
+            .map(|bid_order| response.bids.push(bid_order)),
+        };
+        if placed.is_err() {
+            response.dropped.push(id);
+            messages.push(BankMsg::Send {
+                to_address: conditional_order.owner.to_string(),
+                amount: conditional_order.escrow().to_vec(),
+            });
+        }
+    }
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("trigger_orders", &env)
+                .add("base_denom", &pair.base_denom)
+                .add("quote_denom", &pair.quote_denom)
+                .add("reference_price", reference_price.to_string())
+                .add(
+                    "triggered",
+                    (response.asks.len() + response.bids.len()).to_string(),
+                )
+                .add("dropped", response.dropped.len().to_string()),
+        )
+        .add_messages(messages)
+        .set_data(to_binary(&response)?))
+}
+
+// the price oracle's price of the pair when one is set, otherwise the pair's last trade price
+fn reference_price(deps: Deps<ProvenanceQuery>, pair: &Pair) -> Result<Decimal, ContractError> {
+    match get_config(deps.storage)?.price_oracle {
+        Some(price_oracle) => {
+            let response: OraclePriceResponse =
+                deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: price_oracle.to_string(),
+                    msg: to_binary(&OracleQueryMsg::Price {
+                        base_denom: pair.base_denom.to_owned(),
+                        quote_denom: pair.quote_denom.to_owned(),
+                    })?,
+                }))?;
+            Ok(response.price)
+        }
+        None => PAIR_STATS
+            .may_load(deps.storage, (&pair.base_denom, &pair.quote_denom))?
+            .map(|stats| stats.last_price)
+            .ok_or(ContractError::NoReferencePrice {}),
+    }
+}
+
+pub fn get_conditional_orders(
+    storage: &dyn Storage,
+    pair: &Pair,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ConditionalOrder>> {
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    PAIR_CONDITIONAL_ORDERS
+        .prefix((&pair.base_denom, &pair.quote_denom))
+        .keys(storage, start, None, Order::Ascending)
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .map(|id| CONDITIONAL_ORDERS.load(storage, &id?))
+        .collect()
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{coin, coins, from_binary, BankMsg, CosmosMsg};
+    use provwasm_mocks::mock_dependencies;
+
+    use super::*;
+    use crate::contract::{execute, query};
+    use crate::escrow::get_escrow_totals;
+    use crate::msg::{ConfigMsg, ExecuteMsg, QueryMsg};
+    use crate::state::{AskOrder, BidOrder, TriggerCondition};
+    use crate::testing::instantiate_venue;
+
+    fn pair() -> Pair {
+        Pair {
+            base_denom: "base_1".into(),
+            quote_denom: "quote_1".into(),
+        }
+    }
+
+    // an ask and bid of 100 base_1 at the price, matched by the admin
+    fn trade_at(deps: &mut DepsMut<ProvenanceQuery>, price: u128) {
+        let ask_order = execute(
+            deps.branch(),
+            mock_env(),
+            mock_info("asker", &coins(100, "base_1")),
+            ExecuteMsg::CreateAsk {
+                client_order_id: None,
+                quote: coins(100 * price, "quote_1"),
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+        .unwrap();
+        let bid_order = execute(
+            deps.branch(),
+            mock_env(),
+            mock_info("bidder", &coins(100 * price, "quote_1")),
+            ExecuteMsg::CreateBid {
+                base: coins(100, "base_1"),
+                client_order_id: None,
+                effective_time: None,
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+        .unwrap();
+        let ask_id = from_binary::<AskOrder>(&ask_order.data.unwrap())
+            .unwrap()
+            .id;
+        let bid_id = from_binary::<BidOrder>(&bid_order.data.unwrap())
+            .unwrap()
+            .id;
+        execute(
+            deps.branch(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            ExecuteMsg::ExecuteMatch {
+                ask_id,
+                bid_id,
+                market: None,
+            },
+        )
+        .unwrap();
+    }
+
+    fn create_stop_ask(
+        deps: DepsMut<ProvenanceQuery>,
+        base: Vec<Coin>,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("stop_asker", &base),
+            ExecuteMsg::CreateConditionalAsk {
+                client_order_id: Some("stop_loss".into()),
+                quote: coins(800, "quote_1"),
+                market: None,
+                trigger: PriceTrigger {
+                    condition: TriggerCondition::AtOrBelow,
+                    price: Decimal::percent(800),
+                },
+            },
+        )
+    }
+
+    fn trigger(deps: DepsMut<ProvenanceQuery>) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::TriggerOrders {
+                pair: pair(),
+                limit: None,
+            },
+        )
+    }
+
+    #[test]
+    fn conditional_orders_trigger_on_last_trade_price() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+
+        match create_stop_ask(deps.as_mut(), vec![coin(50, "base_1"), coin(50, "base_2")]) {
+            Err(ContractError::InvalidConditionalOrder {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let response = create_stop_ask(deps.as_mut(), coins(100, "base_1")).unwrap();
+        let conditional_order: ConditionalOrder = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(
+            get_escrow_totals(&deps.storage).unwrap(),
+            coins(100, "base_1")
+        );
+
+        // the order stays dormant until the pair has traded at or below the trigger price
+        match trigger(deps.as_mut()) {
+            Err(ContractError::NoReferencePrice {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        trade_at(&mut deps.as_mut(), 10);
+        let response: TriggerOrdersResponse =
+            from_binary(&trigger(deps.as_mut()).unwrap().data.unwrap()).unwrap();
+        assert!(response.asks.is_empty());
+        assert_eq!(response.reference_price, Decimal::percent(1000));
+
+        trade_at(&mut deps.as_mut(), 8);
+        let response: TriggerOrdersResponse =
+            from_binary(&trigger(deps.as_mut()).unwrap().data.unwrap()).unwrap();
+        assert_eq!(response.asks.len(), 1);
+        let ask_order = &response.asks[0];
+        assert_eq!(ask_order.base, coins(100, "base_1"));
+        assert_eq!(ask_order.client_order_id, Some("stop_loss".into()));
+        assert_ne!(ask_order.id, conditional_order.id);
+        assert!(query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetConditionalOrder {
+                id: conditional_order.id,
+            },
+        )
+        .is_err());
+        assert_eq!(
+            get_escrow_totals(&deps.storage).unwrap(),
+            coins(100, "base_1")
+        );
+    }
+
+    #[test]
+    fn trigger_orders_visits_only_met_orders() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+
+        // orders of 10 base_1 for 80 quote_1 triggered at the price
+        let create = |deps: DepsMut<ProvenanceQuery>,
+                      client_order_id: &str,
+                      condition: TriggerCondition,
+                      price: u64| {
+            let trigger = PriceTrigger {
+                condition,
+                price: Decimal::percent(price * 100),
+            };
+            let msg = match condition {
+                TriggerCondition::AtOrAbove => ExecuteMsg::CreateConditionalBid {
+                    base: coins(10, "base_1"),
+                    client_order_id: Some(client_order_id.into()),
+                    effective_time: None,
+                    market: None,
+                    trigger,
+                },
+                TriggerCondition::AtOrBelow => ExecuteMsg::CreateConditionalAsk {
+                    client_order_id: Some(client_order_id.into()),
+                    quote: coins(80, "quote_1"),
+                    market: None,
+                    trigger,
+                },
+            };
+            let funds = match condition {
+                TriggerCondition::AtOrAbove => coins(80, "quote_1"),
+                TriggerCondition::AtOrBelow => coins(10, "base_1"),
+            };
+            execute(deps, mock_env(), mock_info("trader", &funds), msg).unwrap();
+        };
+        create(deps.as_mut(), "above_9", TriggerCondition::AtOrAbove, 9);
+        create(deps.as_mut(), "above_8", TriggerCondition::AtOrAbove, 8);
+        create(deps.as_mut(), "above_7", TriggerCondition::AtOrAbove, 7);
+        create(deps.as_mut(), "below_7", TriggerCondition::AtOrBelow, 7);
+        create(deps.as_mut(), "below_8", TriggerCondition::AtOrBelow, 8);
+        create(deps.as_mut(), "below_9", TriggerCondition::AtOrBelow, 9);
+        trade_at(&mut deps.as_mut(), 8);
+
+        // the orders whose trigger price was crossed first are placed first
+        let trigger_limit = |deps: DepsMut<ProvenanceQuery>, limit: Option<u32>| {
+            let response = execute(
+                deps,
+                mock_env(),
+                mock_info("anyone", &[]),
+                ExecuteMsg::TriggerOrders {
+                    pair: pair(),
+                    limit,
+                },
+            )
+            .unwrap();
+            from_binary::<TriggerOrdersResponse>(&response.data.unwrap()).unwrap()
+        };
+        let client_order_ids = |response: &TriggerOrdersResponse| {
+            let asks = response.asks.iter().map(|order| &order.client_order_id);
+            let bids = response.bids.iter().map(|order| &order.client_order_id);
+            bids.chain(asks)
+                .map(|client_order_id| client_order_id.to_owned().unwrap())
+                .collect::<Vec<String>>()
+        };
+        let response = trigger_limit(deps.as_mut(), Some(3));
+        assert_eq!(
+            client_order_ids(&response),
+            vec!["above_7", "above_8", "below_9"]
+        );
+        let response = trigger_limit(deps.as_mut(), None);
+        assert_eq!(client_order_ids(&response), vec!["below_8"]);
+
+        // the dormant orders are left waiting on their triggers
+        let dormant = get_conditional_orders(&deps.storage, &pair(), None, None)
+            .unwrap()
+            .into_iter()
+            .map(|order| order.client_order_id.unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(dormant, vec!["above_9", "below_7"]);
+        assert_eq!(
+            PAIR_CONDITIONAL_TRIGGERS
+                .keys_raw(&deps.storage, None, None, Order::Ascending)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn cancel_conditional_order_refunds_owner() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        create_stop_ask(deps.as_mut(), coins(100, "base_1")).unwrap();
+
+        let cancel = ExecuteMsg::CancelConditionalOrder { id: "1".into() };
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[]),
+            cancel.to_owned(),
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("stop_asker", &[]),
+            cancel,
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "stop_asker".into(),
+                amount: coins(100, "base_1"),
+            })
+        );
+        assert!(get_escrow_totals(&deps.storage).unwrap().is_empty());
+    }
+
+    #[test]
+    fn price_oracle_is_queried_for_reference_price() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(
+            deps.as_mut(),
+            ConfigMsg {
+                price_oracle: Some("price_oracle".into()),
+                ..Default::default()
+            },
+        );
+        create_stop_ask(deps.as_mut(), coins(100, "base_1")).unwrap();
+        trade_at(&mut deps.as_mut(), 8);
+
+        // the mock querier has no contracts, the oracle takes precedence over the last trade
+        match trigger(deps.as_mut()) {
+            Err(ContractError::Std(error)) => assert!(error.to_string().contains("price_oracle")),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,529 @@
+use cosmwasm_std::{
+    to_binary, Addr, Api, Coin, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+
+use crate::contract_info::{get_contract_info, set_contract_info};
+use crate::error::ContractError;
+use crate::events::EventAttributes;
+use crate::msg::{ConfigMsg, ExecuteMsg, FeeSchedule};
+use crate::state::{Config, Fees, Market, MatchingMode, CONFIG};
+use crate::validation::validate_denom;
+
+// fees may take at most the whole quote
+const MAX_FEE_BPS: u16 = 10_000;
+
+// contracts instantiated before the config was stored run with the defaults
+pub fn get_config(storage: &dyn Storage) -> StdResult<Config> {
+    Ok(CONFIG.may_load(storage)?.unwrap_or_default())
+}
+
+// validate a config message and apply the fields it sets
+pub fn apply_config(
+    api: &dyn Api,
+    config: &mut Config,
+    msg: ConfigMsg,
+) -> Result<(), ContractError> {
+    if let Some(mut allowed_denoms) = msg.allowed_denoms {
+        for denom in &allowed_denoms {
+            validate_denom(denom)?;
+        }
+        allowed_denoms.sort();
+        allowed_denoms.dedup();
+        config.allowed_denoms = allowed_denoms;
+    }
+    if let Some(compliance_address) = msg.compliance_address {
+        config.compliance_address = Some(api.addr_validate(&compliance_address)?);
+    }
+    if let Some(fees) = msg.fees {
+        config.fees = validate_fees(api, fees)?;
+    }
+    if let Some(matchers) = msg.matchers {
+        config.matchers = validate_addresses(api, &matchers)?;
+    }
+    if let Some(matching_mode) = msg.matching_mode {
+        config.matching_mode = matching_mode;
+    }
+    if let Some(order_retention) = msg.order_retention {
+        config.order_retention = order_retention;
+    }
+    if let Some(paused) = msg.paused {
+        config.paused = paused;
+    }
+    if let Some(pausers) = msg.pausers {
+        config.pausers = validate_addresses(api, &pausers)?;
+    }
+    if let Some(price_oracle) = msg.price_oracle {
+        config.price_oracle = Some(api.addr_validate(&price_oracle)?);
+    }
+    Ok(())
+}
+
+// a fee of zero basis points means no fees
+pub fn validate_fees(api: &dyn Api, fees: FeeSchedule) -> Result<Option<Fees>, ContractError> {
+    if fees.quote_fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::InvalidField {
+            field: "quote_fee_bps".into(),
+        });
+    }
+    let collector = api.addr_validate(&fees.collector)?;
+    Ok(match fees.quote_fee_bps {
+        0 => None,
+        quote_fee_bps => Some(Fees {
+            collector,
+            quote_fee_bps,
+        }),
+    })
+}
+
+pub fn validate_addresses(api: &dyn Api, addresses: &[String]) -> StdResult<Vec<Addr>> {
+    let mut addresses = addresses
+        .iter()
+        .map(|address| api.addr_validate(address))
+        .collect::<StdResult<Vec<Addr>>>()?;
+    addresses.sort();
+    addresses.dedup();
+    Ok(addresses)
+}
+
+// update the admin and venue configuration, admin only
+pub fn update_config(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    admin: Option<String>,
+    msg: ConfigMsg,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let mut contract_info = get_contract_info(deps.storage)?;
+    if info.sender != contract_info.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut config = get_config(deps.storage)?;
+    apply_config(deps.api, &mut config, msg)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    if let Some(admin) = admin {
+        contract_info.admin = deps.api.addr_validate(&admin)?;
+        set_contract_info(deps.storage, &contract_info)?;
+    }
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("update_config", &env)
+                .add("admin", contract_info.admin.as_str())
+                .add("paused", config.paused.to_string()),
+        )
+        .set_data(to_binary(&config)?))
+}
+
+// pause or unpause trading, the admin and pausers may pause but only the admin may unpause
+pub fn set_paused(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let mut config = get_config(deps.storage)?;
+    let is_admin = info.sender == get_contract_info(deps.storage)?.admin;
+    let is_pauser = config.pausers.contains(&info.sender);
+    if !(is_admin || paused && is_pauser) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attributes(EventAttributes::new("set_paused", &env).add("paused", paused.to_string())))
+}
+
+// return error if the action creates, amends or settles while trading is paused
+pub fn check_not_paused(storage: &dyn Storage, msg: &ExecuteMsg) -> Result<(), ContractError> {
+    let pausable = matches!(
+        msg,
+        ExecuteMsg::AcceptRfqQuote { .. }
+            | ExecuteMsg::BidAuction { .. }
+            | ExecuteMsg::CreateAsk { .. }
+            | ExecuteMsg::CreateAskFor { .. }
+            | ExecuteMsg::CreateAuction { .. }
+            | ExecuteMsg::CreateBid { .. }
+            | ExecuteMsg::CreateBidFor { .. }
+            | ExecuteMsg::CreateConditionalAsk { .. }
+            | ExecuteMsg::CreateConditionalBid { .. }
+            | ExecuteMsg::CreateHtlc { .. }
+            | ExecuteMsg::CreateRfq { .. }
+            | ExecuteMsg::CreateRfqQuote { .. }
+            | ExecuteMsg::ExecuteMatch { .. }
+            | ExecuteMsg::FinalizeAuction { .. }
+            | ExecuteMsg::FundSwap { .. }
+            | ExecuteMsg::ProposeSwap { .. }
+            | ExecuteMsg::TriggerOrders { .. }
+            | ExecuteMsg::UpdateAsk { .. }
+            | ExecuteMsg::UpdateBid { .. }
+    );
+    if pausable && get_config(storage)?.paused {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+// return error unless the sender may execute matches, in a market only its admin and matchers may,
+// in the default market it depends on the matching mode
+pub fn check_matcher(
+    storage: &dyn Storage,
+    market: Option<&Market>,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    if let Some(market) = market {
+        if *sender == market.admin || market.matchers.contains(sender) {
+            return Ok(());
+        }
+        return Err(ContractError::Unauthorized {});
+    }
+    let config = get_config(storage)?;
+    match config.matching_mode {
+        MatchingMode::Permissionless => Ok(()),
+        MatchingMode::Restricted
+            if *sender == get_contract_info(storage)?.admin || config.matchers.contains(sender) =>
+        {
+            Ok(())
+        }
+        MatchingMode::Restricted => Err(ContractError::Unauthorized {}),
+    }
+}
+
+// return error if a coin's denom is not one the venue trades
+pub fn check_allowed_denoms(storage: &dyn Storage, coins: &[Coin]) -> Result<(), ContractError> {
+    let allowed_denoms = get_config(storage)?.allowed_denoms;
+    if allowed_denoms.is_empty() {
+        return Ok(());
+    }
+    match coins
+        .iter()
+        .find(|coin| !allowed_denoms.contains(&coin.denom))
+    {
+        Some(coin) => Err(ContractError::DenomNotAllowed {
+            denom: coin.denom.to_owned(),
+        }),
+        None => Ok(()),
+    }
+}
+
+// the part of a quote taken as fees, rounded down
+pub fn quote_fees(fees: Option<&Fees>, quote: &[Coin]) -> Vec<Coin> {
+    match fees {
+        Some(fees) => quote
+            .iter()
+            .map(|coin| Coin {
+                denom: coin.denom.to_owned(),
+                amount: coin
+                    .amount
+                    .multiply_ratio(fees.quote_fee_bps, Uint128::new(MAX_FEE_BPS.into())),
+            })
+            .filter(|coin| !coin.amount.is_zero())
+            .collect(),
+        None => vec![],
+    }
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{coin, coins, from_binary, BankMsg, CosmosMsg};
+    use provwasm_mocks::mock_dependencies;
+
+    use super::*;
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{FeeSchedule, InstantiateMsg, QueryMsg};
+    use crate::state::{Trade, DEFAULT_ORDER_RETENTION};
+    use crate::testing::instantiate_venue;
+
+    fn create_orders(deps: &mut DepsMut<ProvenanceQuery>) {
+        execute(
+            deps.branch(),
+            mock_env(),
+            mock_info("asker", &coins(100, "base_1")),
+            ExecuteMsg::CreateAsk {
+                client_order_id: None,
+                quote: coins(1000, "quote_1"),
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.branch(),
+            mock_env(),
+            mock_info("bidder", &coins(1000, "quote_1")),
+            ExecuteMsg::CreateBid {
+                base: coins(100, "base_1"),
+                client_order_id: None,
+                effective_time: None,
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+        .unwrap();
+    }
+
+    fn execute_match(
+        deps: DepsMut<ProvenanceQuery>,
+        sender: &str,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::ExecuteMatch {
+                ask_id: "1".into(),
+                bid_id: "2".into(),
+                market: None,
+            },
+        )
+    }
+
+    #[test]
+    fn instantiate_validates_and_stores_config() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(
+            deps.as_mut(),
+            ConfigMsg {
+                allowed_denoms: Some(vec!["quote_1".into(), "base_1".into(), "base_1".into()]),
+                fees: Some(FeeSchedule {
+                    collector: "collector".into(),
+                    quote_fee_bps: 25,
+                }),
+                matchers: Some(vec!["matcher".into()]),
+                matching_mode: Some(MatchingMode::Restricted),
+                pausers: Some(vec!["pauser".into()]),
+                ..Default::default()
+            },
+        );
+
+        let config: Config =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                allowed_denoms: vec!["base_1".into(), "quote_1".into()],
+                compliance_address: None,
+                fees: Some(Fees {
+                    collector: Addr::unchecked("collector"),
+                    quote_fee_bps: 25,
+                }),
+                matchers: vec![Addr::unchecked("matcher")],
+                matching_mode: MatchingMode::Restricted,
+                order_retention: DEFAULT_ORDER_RETENTION,
+                paused: false,
+                pausers: vec![Addr::unchecked("pauser")],
+                price_oracle: None,
+            }
+        );
+        assert_eq!(
+            get_contract_info(&deps.storage).unwrap().admin,
+            Addr::unchecked("venue_admin")
+        );
+
+        for (config, expected_field) in [
+            (
+                ConfigMsg {
+                    fees: Some(FeeSchedule {
+                        collector: "collector".into(),
+                        quote_fee_bps: MAX_FEE_BPS + 1,
+                    }),
+                    ..Default::default()
+                },
+                "quote_fee_bps",
+            ),
+            (
+                ConfigMsg {
+                    allowed_denoms: Some(vec!["1bad".into()]),
+                    ..Default::default()
+                },
+                "allowed_denoms",
+            ),
+        ] {
+            let result = instantiate(
+                mock_dependencies(&[]).as_mut(),
+                mock_env(),
+                mock_info("deployer", &[]),
+                InstantiateMsg {
+                    bind_name: Some("contract_bind_name".into()),
+                    config,
+                    contract_name: "contract_name".into(),
+                    ..Default::default()
+                },
+            );
+            match (result, expected_field) {
+                (Err(ContractError::InvalidField { field }), "quote_fee_bps") => {
+                    assert_eq!(field, "quote_fee_bps")
+                }
+                (Err(ContractError::InvalidDenom { denom }), "allowed_denoms") => {
+                    assert_eq!(denom, "1bad")
+                }
+                (result, _) => panic!("unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn matches_pay_fees_to_collector_and_respect_matching_mode() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(
+            deps.as_mut(),
+            ConfigMsg {
+                fees: Some(FeeSchedule {
+                    collector: "collector".into(),
+                    quote_fee_bps: 25,
+                }),
+                matchers: Some(vec!["matcher".into()]),
+                ..Default::default()
+            },
+        );
+        create_orders(&mut deps.as_mut());
+
+        match execute_match(deps.as_mut(), "anyone") {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let response = execute_match(deps.as_mut(), "matcher").unwrap();
+        assert_eq!(
+            response
+                .messages
+                .into_iter()
+                .map(|msg| msg.msg)
+                .collect::<Vec<CosmosMsg<ProvenanceMsg>>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "asker".into(),
+                    amount: coins(998, "quote_1"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "bidder".into(),
+                    amount: coins(100, "base_1"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "collector".into(),
+                    amount: coins(2, "quote_1"),
+                }),
+            ]
+        );
+        let trade: Trade = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(trade.fees, coins(2, "quote_1"));
+        assert_eq!(trade.quote, coins(1000, "quote_1"));
+
+        // anyone may match once matching is permissionless
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            ExecuteMsg::UpdateConfig {
+                admin: None,
+                config: ConfigMsg {
+                    matching_mode: Some(MatchingMode::Permissionless),
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+        create_orders(&mut deps.as_mut());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ExecuteMatch {
+                ask_id: "3".into(),
+                bid_id: "4".into(),
+                market: None,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn pause_blocks_trading_but_not_cancels() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(
+            deps.as_mut(),
+            ConfigMsg {
+                allowed_denoms: Some(vec!["base_1".into(), "quote_1".into()]),
+                pausers: Some(vec!["pauser".into()]),
+                ..Default::default()
+            },
+        );
+        create_orders(&mut deps.as_mut());
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[coin(100, "base_2")]),
+            ExecuteMsg::CreateAsk {
+                client_order_id: None,
+                quote: coins(1000, "quote_1"),
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        ) {
+            Err(ContractError::DenomNotAllowed { denom }) => assert_eq!(denom, "base_2"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let mut set_paused = |sender: &str, paused: bool| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::SetPaused { paused },
+            )
+        };
+        match set_paused("anyone", true) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        set_paused("pauser", true).unwrap();
+        match set_paused("pauser", false) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        match execute_match(deps.as_mut(), "venue_admin") {
+            Err(ContractError::Paused {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[]),
+            ExecuteMsg::CancelAsk {
+                id: "1".into(),
+                market: None,
+            },
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            ExecuteMsg::SetPaused { paused: false },
+        )
+        .unwrap();
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("pauser", &[]),
+            ExecuteMsg::UpdateConfig {
+                admin: Some("pauser".into()),
+                config: ConfigMsg::default(),
+            },
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
@@ -1,16 +1,49 @@
 use cosmwasm_std::{
-    attr, entry_point, to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Timestamp,
+    entry_point, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Order,
+    Response, StdError, StdResult, Storage, Timestamp,
 };
+use cw_storage_plus::Bound;
 use provwasm_std::{bind_name, NameBinding, ProvenanceMsg, ProvenanceQuery};
 
+use crate::auction::{bid_auction, create_auction, finalize_auction};
+use crate::cancel::{cancel_all, cancel_many, force_cancel_ask, force_cancel_bid};
+use crate::conditional::{
+    cancel_conditional_order, create_conditional_ask, create_conditional_bid,
+    get_conditional_orders, trigger_orders,
+};
+use crate::config::{
+    apply_config, check_allowed_denoms, check_matcher, check_not_paused, get_config, quote_fees,
+    set_paused, update_config,
+};
 use crate::contract_info::{get_contract_info, set_contract_info, ContractInfo};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::escrow::{
+    check_solvency, deposit_escrow, get_escrow_totals, release_escrow, sweep_excess,
+};
+use crate::events::{EventAttributes, BASE_BEFORE_KEY, FEES_KEY, QUOTE_BEFORE_KEY};
+use crate::htlc::{claim_htlc, create_htlc, get_htlcs, refund_htlc};
+use crate::markets::{create_market, get_market, get_markets, load_market, update_market};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MatchFailure, QueryMsg, SimulateMatchResponse};
+use crate::operators::{
+    approve_operator, check_owner_or_operator, create_ask_for, create_bid_for,
+    get_operator_approvals, revoke_operator,
+};
+use crate::pairs::{get_trading_pair, get_trading_pairs, set_trading_pair, validate_order_pair};
+use crate::rfq::{accept_rfq_quote, create_rfq, create_rfq_quote, expire_rfq, get_rfq_quotes};
 use crate::state::{
-    get_ask_storage, get_ask_storage_read, get_bid_storage, get_bid_storage_read, AskOrder,
-    BidOrder,
+    get_ask_storage, get_bid_storage, load_ask, load_bid, order_key, AskOrder, BidOrder, Config,
+    Fees, Market, OrderStatus, Side, StatusUpdate, TimeInForce, Trade, AUCTIONS, CLIENT_ORDER_IDS,
+    CLOSED_ORDERS, CONDITIONAL_ORDERS, CONFIG, HTLCS, ORDER_EXPIRATIONS, ORDER_SEQUENCE,
+    OWNER_ORDERS, RFQS, SWAPS,
 };
+use crate::stats::{get_all_pair_stats, get_pair_stats, update_pair_stats};
+use crate::swaps::{cancel_swap, fund_swap, propose_swap};
+use crate::time_in_force::{check_time_in_force, created_ask, created_bid, expire_orders};
+use crate::trade::{get_trade, get_trades_by_denom, get_trades_by_owner, record_trade};
+use crate::twap::{get_twap, record_price_observation};
+use crate::validation::{normalize_coins, validate_id, validate_order_id};
+
+const PRUNE_LIMIT: u32 = 30;
 
 // smart contract initialization entrypoint
 #[entry_point]
@@ -20,7 +53,7 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response<ProvenanceMsg>, ContractError> {
-    if msg.bind_name.is_empty() {
+    if msg.bind_name.as_deref() == Some("") {
         return Err(ContractError::MissingField {
             field: "bind_name".into(),
         });
@@ -31,27 +64,71 @@ pub fn instantiate(
         });
     }
 
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    let mut config = Config::default();
+    apply_config(deps.api, &mut config, msg.config)?;
+    CONFIG.save(deps.storage, &config)?;
+
     // set contract info
-    let contract_info = ContractInfo::new(info.sender, msg.bind_name, msg.contract_name);
+    let contract_info = ContractInfo::new(admin, msg.bind_name, msg.contract_name);
     set_contract_info(deps.storage, &contract_info)?;
 
-    // create name binding provenance message
+    let mut attributes = EventAttributes::new("instantiate", &env)
+        .add("admin", contract_info.admin.as_str())
+        .add("contract_name", &contract_info.contract_name);
+
+    // create name binding provenance message, unless no name is bound
+    let mut messages = vec![];
+    if let Some(name) = contract_info.bind_name {
+        attributes = attributes.add("bind_name", &name);
+        messages.push(bind_name(
+            name,
+            env.contract.address,
+            msg.name_binding.unwrap_or(NameBinding::Restricted),
+        )?);
+    }
+
+    // build response
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+// bind an additional name to the contract, admin only
+fn bind_contract_name(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    name_binding: Option<NameBinding>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let mut contract_info = get_contract_info(deps.storage)?;
+    if info.sender != contract_info.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if name.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "name".into(),
+        });
+    }
+    if contract_info.bound_names.contains(&name) {
+        return Err(ContractError::NameAlreadyBound { name });
+    }
+
     let bind_name_msg = bind_name(
-        contract_info.bind_name,
-        env.contract.address,
-        NameBinding::Restricted,
+        &name,
+        env.contract.address.to_owned(),
+        name_binding.unwrap_or(NameBinding::Restricted),
     )?;
+    contract_info.bound_names.push(name.to_owned());
+    set_contract_info(deps.storage, &contract_info)?;
 
-    // build response
     Ok(Response::new()
-        .add_messages(vec![bind_name_msg])
-        .add_attributes(vec![
-            attr(
-                "contract_info",
-                format!("{:?}", get_contract_info(deps.storage)?),
-            ),
-            attr("action", "init"),
-        ]))
+        .add_message(bind_name_msg)
+        .add_attributes(EventAttributes::new("bind_name", &env).add("name", name)))
 }
 
 // smart contract execute entrypoint
@@ -62,31 +139,225 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response<ProvenanceMsg>, ContractError> {
+    check_not_paused(deps.storage, &msg)?;
+
     match msg {
-        ExecuteMsg::CreateAsk { id, quote } => create_ask(deps, info, id, quote),
-        ExecuteMsg::CreateBid {
+        ExecuteMsg::AcceptRfqQuote { rfq_id, quote_id } => {
+            accept_rfq_quote(deps, env, info, rfq_id, quote_id)
+        }
+        ExecuteMsg::ApproveOperator { operator, expires } => {
+            approve_operator(deps, env, info, operator, expires)
+        }
+        ExecuteMsg::BidAuction { id } => bid_auction(deps, env, info, id),
+        ExecuteMsg::BindName { name, name_binding } => {
+            bind_contract_name(deps, env, info, name, name_binding)
+        }
+        ExecuteMsg::CreateAsk {
+            client_order_id,
+            quote,
+            market,
+            take,
+            time_in_force,
+        } => create_ask(
+            deps,
+            env,
+            info,
+            client_order_id,
+            quote,
+            market,
+            take,
+            time_in_force.unwrap_or_default(),
+        ),
+        ExecuteMsg::CreateAskFor {
+            base,
+            client_order_id,
+            owner,
+            quote,
+            market,
+            take,
+            time_in_force,
+        } => create_ask_for(
+            deps,
+            env,
+            info,
+            owner,
+            base,
+            quote,
+            client_order_id,
+            market,
+            take,
+            time_in_force.unwrap_or_default(),
+        ),
+        ExecuteMsg::CreateAuction {
             id,
+            end_time,
+            min_increment,
+            reserve_price,
+            time_extension,
+        } => create_auction(
+            deps,
+            env,
+            info,
+            id,
+            end_time,
+            min_increment,
+            reserve_price,
+            time_extension,
+        ),
+        ExecuteMsg::CreateBid {
+            base,
+            client_order_id,
+            effective_time,
+            market,
+            take,
+            time_in_force,
+        } => create_bid(
+            deps,
+            env,
+            info,
+            base,
+            client_order_id,
+            effective_time,
+            market,
+            take,
+            time_in_force.unwrap_or_default(),
+        ),
+        ExecuteMsg::CreateBidFor {
             base,
+            client_order_id,
             effective_time,
-        } => create_bid(deps, info, id, base, effective_time),
-        ExecuteMsg::CancelAsk { id } => cancel_ask(deps, env, info, id),
-        ExecuteMsg::CancelBid { id } => cancel_bid(deps, env, info, id),
-        ExecuteMsg::ExecuteMatch { ask_id, bid_id } => {
-            execute_match(deps, env, info, ask_id, bid_id)
+            owner,
+            quote,
+            market,
+            take,
+            time_in_force,
+        } => create_bid_for(
+            deps,
+            env,
+            info,
+            owner,
+            base,
+            quote,
+            client_order_id,
+            effective_time,
+            market,
+            take,
+            time_in_force.unwrap_or_default(),
+        ),
+        ExecuteMsg::CreateConditionalAsk {
+            client_order_id,
+            quote,
+            market,
+            trigger,
+        } => create_conditional_ask(deps, env, info, client_order_id, quote, market, trigger),
+        ExecuteMsg::CreateConditionalBid {
+            base,
+            client_order_id,
+            effective_time,
+            market,
+            trigger,
+        } => create_conditional_bid(
+            deps,
+            env,
+            info,
+            base,
+            client_order_id,
+            effective_time,
+            market,
+            trigger,
+        ),
+        ExecuteMsg::CreateHtlc {
+            id,
+            counterparty,
+            hash,
+            timeout,
+        } => create_htlc(deps, env, info, id, counterparty, hash, timeout),
+        ExecuteMsg::CreateMarket {
+            id,
+            admin,
+            fees,
+            matchers,
+        } => create_market(deps, env, info, id, admin, fees, matchers),
+        ExecuteMsg::CancelAll { side, denom, limit } => {
+            cancel_all(deps, env, info, side, denom, limit)
         }
+        ExecuteMsg::CancelAsk { id, market } => cancel_ask(deps, env, info, id, market),
+        ExecuteMsg::CancelBid { id, market } => cancel_bid(deps, env, info, id, market),
+        ExecuteMsg::CancelConditionalOrder { id } => cancel_conditional_order(deps, env, info, id),
+        ExecuteMsg::CancelMany {
+            ask_ids,
+            bid_ids,
+            market,
+        } => cancel_many(deps, env, info, ask_ids, bid_ids, market),
+        ExecuteMsg::CancelSwap { id } => cancel_swap(deps, env, info, id),
+        ExecuteMsg::CheckSolvency {} => check_solvency(deps, env, info),
+        ExecuteMsg::ClaimHtlc { id, preimage } => claim_htlc(deps, env, info, id, preimage),
+        ExecuteMsg::ExecuteMatch {
+            ask_id,
+            bid_id,
+            market,
+        } => execute_match(deps, env, info, ask_id, bid_id, market),
+        ExecuteMsg::ExpireOrders { limit } => expire_orders(deps, env, limit),
+        ExecuteMsg::CreateRfq {
+            id,
+            base,
+            expiration,
+        } => create_rfq(deps, env, info, id, base, expiration),
+        ExecuteMsg::CreateRfqQuote { rfq_id, id, quote } => {
+            create_rfq_quote(deps, env, info, rfq_id, id, quote)
+        }
+        ExecuteMsg::ExpireRfq { id } => expire_rfq(deps, env, info, id),
+        ExecuteMsg::FinalizeAuction { id } => finalize_auction(deps, env, info, id),
+        ExecuteMsg::FundSwap { id, legs } => fund_swap(deps, env, info, id, legs),
+        ExecuteMsg::ForceCancelAsk { id, reason, market } => {
+            force_cancel_ask(deps, env, info, id, reason, market)
+        }
+        ExecuteMsg::ForceCancelBid { id, reason, market } => {
+            force_cancel_bid(deps, env, info, id, reason, market)
+        }
+        ExecuteMsg::ProposeSwap { id, legs } => propose_swap(deps, env, info, id, legs),
+        ExecuteMsg::PruneOrders { limit } => prune_orders(deps, env, limit),
+        ExecuteMsg::RefundHtlc { id } => refund_htlc(deps, env, info, id),
+        ExecuteMsg::RevokeOperator { operator } => revoke_operator(deps, env, info, operator),
+        ExecuteMsg::SetOrderRetention { seconds } => set_order_retention(deps, env, info, seconds),
+        ExecuteMsg::SetPaused { paused } => set_paused(deps, env, info, paused),
+        ExecuteMsg::SetTradingPair { pair } => set_trading_pair(deps, env, info, pair),
+        ExecuteMsg::SweepExcess { denom, to } => sweep_excess(deps, env, info, denom, to),
+        ExecuteMsg::TriggerOrders { pair, limit } => trigger_orders(deps, env, info, pair, limit),
+        ExecuteMsg::UpdateAsk {
+            id,
+            quote,
+            base_refund,
+            market,
+        } => update_ask(deps, env, info, id, quote, base_refund, market),
+        ExecuteMsg::UpdateBid {
+            id,
+            base,
+            quote_refund,
+            market,
+        } => update_bid(deps, env, info, id, base, quote_refund, market),
+        ExecuteMsg::UpdateConfig { admin, config } => update_config(deps, env, info, admin, config),
+        ExecuteMsg::UpdateMarket {
+            id,
+            admin,
+            fees,
+            matchers,
+        } => update_market(deps, env, info, id, admin, fees, matchers),
     }
 }
 
 // create ask entrypoint
+#[allow(clippy::too_many_arguments)]
 fn create_ask(
     deps: DepsMut<ProvenanceQuery>,
+    env: Env,
     info: MessageInfo,
-    id: String,
+    client_order_id: Option<String>,
     quote: Vec<Coin>,
+    market: Option<String>,
+    take: Option<String>,
+    time_in_force: TimeInForce,
 ) -> Result<Response<ProvenanceMsg>, ContractError> {
-    if id.is_empty() {
-        return Err(ContractError::MissingField { field: "id".into() });
-    }
     if info.funds.is_empty() {
         return Err(ContractError::MissingAskBase);
     }
@@ -95,97 +366,254 @@ fn create_ask(
             field: "quote".into(),
         });
     }
+    let base = normalize_coins(info.funds, "base")?;
+    let quote = normalize_coins(quote, "quote")?;
+    check_time_in_force(&env, time_in_force, take.as_deref())?;
+    let ask_order = open_ask(
+        deps.storage,
+        &env,
+        info.sender.to_owned(),
+        base,
+        quote,
+        client_order_id,
+        market,
+        time_in_force,
+    )?;
+    created_ask(
+        deps.storage,
+        &env,
+        &info.sender,
+        ask_order,
+        take,
+        time_in_force,
+    )
+}
 
-    let mut ask_storage = get_ask_storage(deps.storage);
+// validate, store and escrow a new ask of the owner, whose base is already held by the contract
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn open_ask(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: Addr,
+    base: Vec<Coin>,
+    quote: Vec<Coin>,
+    client_order_id: Option<String>,
+    market: Option<String>,
+    time_in_force: TimeInForce,
+) -> Result<AskOrder, ContractError> {
+    load_market(storage, market.as_deref())?;
+    validate_order_pair(storage, &base, &quote)?;
+    check_allowed_denoms(storage, &base)?;
+    check_allowed_denoms(storage, &quote)?;
+
+    let id = assign_order_id(storage, Side::Ask, &owner, client_order_id.as_deref())?;
 
     let ask_order = AskOrder {
-        base: info.funds,
+        base,
+        client_order_id,
         id,
-        owner: info.sender,
+        market,
+        owner,
         quote,
+        status: OrderStatus::Open,
+        status_updates: vec![StatusUpdate {
+            reason: None,
+            status: OrderStatus::Open,
+            time: env.block.time,
+        }],
+        time_in_force,
     };
 
-    ask_storage.save(ask_order.id.as_bytes(), &ask_order)?;
-
-    Ok(Response::new()
-        .add_attributes(vec![attr("action", "create_ask")])
-        .set_data(to_binary(&ask_order)?))
+    get_ask_storage(storage).save(&ask_order.key(), &ask_order)?;
+    save_open_order(
+        storage,
+        Side::Ask,
+        &ask_order.owner,
+        &ask_order.id,
+        ask_order.market.as_deref(),
+        time_in_force,
+    )?;
+    deposit_escrow(storage, &ask_order.base)?;
+    Ok(ask_order)
 }
 
 // create bid entrypoint
+#[allow(clippy::too_many_arguments)]
 fn create_bid(
     deps: DepsMut<ProvenanceQuery>,
+    env: Env,
     info: MessageInfo,
-    id: String,
     base: Vec<Coin>,
+    client_order_id: Option<String>,
     effective_time: Option<Timestamp>,
+    market: Option<String>,
+    take: Option<String>,
+    time_in_force: TimeInForce,
 ) -> Result<Response<ProvenanceMsg>, ContractError> {
     if base.is_empty() {
         return Err(ContractError::MissingField {
             field: "base".into(),
         });
     }
-    if id.is_empty() {
-        return Err(ContractError::MissingField { field: "id".into() });
-    }
     if info.funds.is_empty() {
         return Err(ContractError::MissingBidQuote);
     }
+    let base = normalize_coins(base, "base")?;
+    let quote = normalize_coins(info.funds, "quote")?;
+    check_time_in_force(&env, time_in_force, take.as_deref())?;
+    let bid_order = open_bid(
+        deps.storage,
+        &env,
+        info.sender.to_owned(),
+        base,
+        quote,
+        client_order_id,
+        effective_time,
+        market,
+        time_in_force,
+    )?;
+    created_bid(
+        deps.storage,
+        &env,
+        &info.sender,
+        bid_order,
+        take,
+        time_in_force,
+    )
+}
+
+// validate, store and escrow a new bid of the owner, whose quote is already held by the contract
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn open_bid(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: Addr,
+    base: Vec<Coin>,
+    quote: Vec<Coin>,
+    client_order_id: Option<String>,
+    effective_time: Option<Timestamp>,
+    market: Option<String>,
+    time_in_force: TimeInForce,
+) -> Result<BidOrder, ContractError> {
+    load_market(storage, market.as_deref())?;
+    validate_order_pair(storage, &base, &quote)?;
+    check_allowed_denoms(storage, &base)?;
+    check_allowed_denoms(storage, &quote)?;
 
-    let mut bid_storage = get_bid_storage(deps.storage);
+    let id = assign_order_id(storage, Side::Bid, &owner, client_order_id.as_deref())?;
 
     let bid_order = BidOrder {
         base,
+        client_order_id,
         effective_time,
         id,
-        owner: info.sender,
-        quote: info.funds,
+        market,
+        owner,
+        quote,
+        status: OrderStatus::Open,
+        status_updates: vec![StatusUpdate {
+            reason: None,
+            status: OrderStatus::Open,
+            time: env.block.time,
+        }],
+        time_in_force,
     };
 
-    bid_storage.save(bid_order.id.as_bytes(), &bid_order)?;
+    get_bid_storage(storage).save(&bid_order.key(), &bid_order)?;
+    save_open_order(
+        storage,
+        Side::Bid,
+        &bid_order.owner,
+        &bid_order.id,
+        bid_order.market.as_deref(),
+        time_in_force,
+    )?;
+    deposit_escrow(storage, &bid_order.quote)?;
+    Ok(bid_order)
+}
 
-    Ok(Response::new()
-        .add_attributes(vec![attr("action", "create_bid")])
-        .set_data(to_binary(&bid_order)?))
+// assign the next canonical order id, shared by asks and bids, and point the owner's client order
+// id at it. A client order id may only be reused once the order it names has closed.
+fn assign_order_id(
+    storage: &mut dyn Storage,
+    side: Side,
+    owner: &Addr,
+    client_order_id: Option<&str>,
+) -> Result<String, ContractError> {
+    let side_key = side.to_string();
+    if let Some(client_order_id) = client_order_id {
+        validate_id(client_order_id)?;
+        let key = (side_key.as_str(), owner.as_str(), client_order_id);
+        if let Some(existing_id) = CLIENT_ORDER_IDS.may_load(storage, key)? {
+            // only open orders are indexed by owner
+            if OWNER_ORDERS.has(storage, (owner.as_str(), &side_key, &existing_id)) {
+                return Err(ContractError::ClientOrderIdExists {
+                    client_order_id: client_order_id.into(),
+                    id: existing_id,
+                });
+            }
+        }
+    }
+
+    let id = next_order_id(storage)?;
+
+    if let Some(client_order_id) = client_order_id {
+        CLIENT_ORDER_IDS.save(storage, (&side_key, owner.as_str(), client_order_id), &id)?;
+    }
+    Ok(id)
+}
+
+// next id of the sequence shared by asks, bids and conditional orders
+pub(crate) fn next_order_id(storage: &mut dyn Storage) -> StdResult<String> {
+    let id = ORDER_SEQUENCE.may_load(storage)?.unwrap_or_default() + 1;
+    ORDER_SEQUENCE.save(storage, &id)?;
+    Ok(id.to_string())
+}
+
+// canonical id of an owner's order by its client order id
+fn get_order_id(
+    storage: &dyn Storage,
+    side: Side,
+    owner: &str,
+    client_order_id: &str,
+) -> StdResult<String> {
+    CLIENT_ORDER_IDS.load(storage, (&side.to_string(), owner, client_order_id))
 }
 
 // cancel ask entrypoint
 fn cancel_ask(
     deps: DepsMut<ProvenanceQuery>,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     id: String,
+    market: Option<String>,
 ) -> Result<Response<ProvenanceMsg>, ContractError> {
-    // return error if id is empty
-    if id.is_empty() {
-        return Err(ContractError::Unauthorized {});
-    }
+    validate_order_id(&id, market.as_deref())?;
 
     // return error if funds sent
     if !info.funds.is_empty() {
         return Err(ContractError::CancelWithFunds {});
     }
 
-    let ask_storage = get_ask_storage_read(deps.storage);
-    let stored_ask_order = ask_storage.load(id.as_bytes());
+    let stored_ask_order = load_ask(deps.storage, market.as_deref(), &id);
     match stored_ask_order {
-        Err(_) => Err(ContractError::Unauthorized {}),
-        Ok(stored_ask_order) => {
-            if !info.sender.eq(&stored_ask_order.owner) {
-                return Err(ContractError::Unauthorized {});
-            }
-
-            // remove the ask order from storage
-            let mut ask_storage = get_ask_storage(deps.storage);
-            ask_storage.remove(id.as_bytes());
+        Err(_) => Err(ContractError::AskNotFound { id }),
+        Ok(mut stored_ask_order) => {
+            check_owner_or_operator(deps.storage, &env, &stored_ask_order.owner, &info.sender)?;
+            check_open(stored_ask_order.status_at(env.block.time))?;
+            cancel_ask_order(deps.storage, &mut stored_ask_order, env.block.time, None)?;
 
             // 'send base back to owner' message
             Ok(Response::new()
+                .add_attributes(
+                    EventAttributes::new("cancel_ask", &env)
+                        .ask(&stored_ask_order)
+                        .operator(&stored_ask_order.owner, &info.sender),
+                )
                 .add_message(BankMsg::Send {
                     to_address: stored_ask_order.owner.to_string(),
                     amount: stored_ask_order.base,
-                })
-                .add_attributes(vec![attr("action", "cancel_ask")]))
+                }))
         }
     }
 }
@@ -193,138 +621,777 @@ fn cancel_ask(
 // cancel bid entrypoint
 fn cancel_bid(
     deps: DepsMut<ProvenanceQuery>,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     id: String,
+    market: Option<String>,
 ) -> Result<Response<ProvenanceMsg>, ContractError> {
-    // return error if id is empty
-    if id.is_empty() {
-        return Err(ContractError::Unauthorized {});
-    }
+    validate_order_id(&id, market.as_deref())?;
 
     // return error if funds sent
     if !info.funds.is_empty() {
         return Err(ContractError::CancelWithFunds {});
     }
 
-    let bid_storage = get_bid_storage_read(deps.storage);
-    let stored_bid_order = bid_storage.load(id.as_bytes());
+    let stored_bid_order = load_bid(deps.storage, market.as_deref(), &id);
     match stored_bid_order {
-        Ok(stored_bid_order) => {
-            if !info.sender.eq(&stored_bid_order.owner) {
-                return Err(ContractError::Unauthorized {});
-            }
-
-            // remove the ask order from storage
-            let mut bid_storage = get_bid_storage(deps.storage);
-            bid_storage.remove(id.as_bytes());
+        Ok(mut stored_bid_order) => {
+            check_owner_or_operator(deps.storage, &env, &stored_bid_order.owner, &info.sender)?;
+            check_open(stored_bid_order.status_at(env.block.time))?;
+            cancel_bid_order(deps.storage, &mut stored_bid_order, env.block.time, None)?;
 
             // 'send quote back to owner' message
             Ok(Response::new()
+                .add_attributes(
+                    EventAttributes::new("cancel_bid", &env)
+                        .bid(&stored_bid_order)
+                        .operator(&stored_bid_order.owner, &info.sender),
+                )
                 .add_message(BankMsg::Send {
                     to_address: stored_bid_order.owner.to_string(),
                     amount: stored_bid_order.quote,
-                })
-                .add_attributes(vec![attr("action", "cancel_bid")]))
+                }))
+        }
+        Err(_) => Err(ContractError::BidNotFound { id }),
+    }
+}
+
+// mark an open ask cancelled and release its escrow, the caller refunds the base to the owner.
+// The cancelled ask is kept in storage until it is pruned.
+pub(crate) fn cancel_ask_order(
+    storage: &mut dyn Storage,
+    ask_order: &mut AskOrder,
+    time: Timestamp,
+    reason: Option<String>,
+) -> Result<(), ContractError> {
+    ask_order.update_status(OrderStatus::Cancelled, time, reason);
+    get_ask_storage(storage).save(&ask_order.key(), ask_order)?;
+    save_closed_order(
+        storage,
+        Side::Ask,
+        &ask_order.owner,
+        &ask_order.id,
+        ask_order.market.as_deref(),
+        time,
+    )?;
+    release_escrow(storage, &ask_order.base)?;
+    Ok(())
+}
+
+// mark an open bid cancelled and release its escrow, the caller refunds the quote to the owner.
+// The cancelled bid is kept in storage until it is pruned.
+pub(crate) fn cancel_bid_order(
+    storage: &mut dyn Storage,
+    bid_order: &mut BidOrder,
+    time: Timestamp,
+    reason: Option<String>,
+) -> Result<(), ContractError> {
+    bid_order.update_status(OrderStatus::Cancelled, time, reason);
+    get_bid_storage(storage).save(&bid_order.key(), bid_order)?;
+    save_closed_order(
+        storage,
+        Side::Bid,
+        &bid_order.owner,
+        &bid_order.id,
+        bid_order.market.as_deref(),
+        time,
+    )?;
+    release_escrow(storage, &bid_order.quote)?;
+    Ok(())
+}
+
+// update ask entrypoint, funds sent top up the escrowed base
+fn update_ask(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    quote: Option<Vec<Coin>>,
+    base_refund: Option<Vec<Coin>>,
+    market: Option<String>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_order_id(&id, market.as_deref())?;
+    if let Some(quote) = &quote {
+        if quote.is_empty() {
+            return Err(ContractError::MissingField {
+                field: "quote".into(),
+            });
+        }
+    }
+    let quote = quote
+        .map(|quote| normalize_coins(quote, "quote"))
+        .transpose()?;
+    let funds = normalize_coins(info.funds, "base")?;
+    let base_refund = normalize_coins(base_refund.unwrap_or_default(), "base_refund")?;
+
+    let ask_order = load_ask(deps.storage, market.as_deref(), &id)
+        .map_err(|_| ContractError::AskNotFound { id: id.to_owned() })?;
+    check_owner_or_operator(deps.storage, &env, &ask_order.owner, &info.sender)?;
+    // an operator's funds would be refunded to the owner
+    if info.sender != ask_order.owner && !funds.is_empty() {
+        return Err(ContractError::OperatorWithFunds {});
+    }
+    check_open(ask_order.status_at(env.block.time))?;
+
+    let updated_ask_order = AskOrder {
+        base: subtract_coins(&add_coins(&ask_order.base, &funds), &base_refund)?,
+        quote: quote.unwrap_or_else(|| ask_order.quote.to_owned()),
+        ..ask_order.to_owned()
+    };
+    validate_order_pair(
+        deps.storage,
+        &updated_ask_order.base,
+        &updated_ask_order.quote,
+    )?;
+    check_allowed_denoms(deps.storage, &updated_ask_order.base)?;
+    check_allowed_denoms(deps.storage, &updated_ask_order.quote)?;
+
+    get_ask_storage(deps.storage).save(&updated_ask_order.key(), &updated_ask_order)?;
+    deposit_escrow(deps.storage, &funds)?;
+    release_escrow(deps.storage, &base_refund)?;
+
+    let mut response = Response::new();
+
+    // 'send refunded base back to owner' message
+    if !base_refund.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: ask_order.owner.to_string(),
+            amount: base_refund,
+        });
+    }
+
+    Ok(response
+        .add_attributes(
+            EventAttributes::new("update_ask", &env)
+                .ask(&updated_ask_order)
+                .add_coins(BASE_BEFORE_KEY, &ask_order.base)
+                .add_coins(QUOTE_BEFORE_KEY, &ask_order.quote)
+                .operator(&ask_order.owner, &info.sender),
+        )
+        .set_data(to_binary(&updated_ask_order)?))
+}
+
+// update bid entrypoint, funds sent top up the escrowed quote
+fn update_bid(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    base: Option<Vec<Coin>>,
+    quote_refund: Option<Vec<Coin>>,
+    market: Option<String>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_order_id(&id, market.as_deref())?;
+    if let Some(base) = &base {
+        if base.is_empty() {
+            return Err(ContractError::MissingField {
+                field: "base".into(),
+            });
         }
-        Err(_) => Err(ContractError::Unauthorized {}),
     }
+    let base = base.map(|base| normalize_coins(base, "base")).transpose()?;
+    let funds = normalize_coins(info.funds, "quote")?;
+    let quote_refund = normalize_coins(quote_refund.unwrap_or_default(), "quote_refund")?;
+
+    let bid_order = load_bid(deps.storage, market.as_deref(), &id)
+        .map_err(|_| ContractError::BidNotFound { id: id.to_owned() })?;
+    check_owner_or_operator(deps.storage, &env, &bid_order.owner, &info.sender)?;
+    // an operator's funds would be refunded to the owner
+    if info.sender != bid_order.owner && !funds.is_empty() {
+        return Err(ContractError::OperatorWithFunds {});
+    }
+    check_open(bid_order.status_at(env.block.time))?;
+
+    let updated_bid_order = BidOrder {
+        base: base.unwrap_or_else(|| bid_order.base.to_owned()),
+        quote: subtract_coins(&add_coins(&bid_order.quote, &funds), &quote_refund)?,
+        ..bid_order.to_owned()
+    };
+    validate_order_pair(
+        deps.storage,
+        &updated_bid_order.base,
+        &updated_bid_order.quote,
+    )?;
+    check_allowed_denoms(deps.storage, &updated_bid_order.base)?;
+    check_allowed_denoms(deps.storage, &updated_bid_order.quote)?;
+
+    get_bid_storage(deps.storage).save(&updated_bid_order.key(), &updated_bid_order)?;
+    deposit_escrow(deps.storage, &funds)?;
+    release_escrow(deps.storage, &quote_refund)?;
+
+    let mut response = Response::new();
+
+    // 'send refunded quote back to owner' message
+    if !quote_refund.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: bid_order.owner.to_string(),
+            amount: quote_refund,
+        });
+    }
+
+    Ok(response
+        .add_attributes(
+            EventAttributes::new("update_bid", &env)
+                .bid(&updated_bid_order)
+                .add_coins(BASE_BEFORE_KEY, &bid_order.base)
+                .add_coins(QUOTE_BEFORE_KEY, &bid_order.quote)
+                .operator(&bid_order.owner, &info.sender),
+        )
+        .set_data(to_binary(&updated_bid_order)?))
 }
 
-// match and execute an ask and bid order
+// match and execute an ask and bid order of the same market
 fn execute_match(
     deps: DepsMut<ProvenanceQuery>,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     ask_id: String,
     bid_id: String,
+    market: Option<String>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    // only the admin and matchers may execute matches unless matching is permissionless
+    let market = load_market(deps.storage, market.as_deref())?;
+    check_matcher(deps.storage, market.as_ref(), &info.sender)?;
+
+    // an id that is not a plain id cannot name an order
+    validate_id(&ask_id)?;
+    validate_id(&bid_id)?;
+
+    // return error if funds sent
+    if !info.funds.is_empty() {
+        return Err(ContractError::ExecuteWithFunds {});
+    }
+
+    // both orders are loaded from the market's order space, an order of another market is not found
+    let market_id = market.as_ref().map(|market| market.id.as_str());
+    let (ask_order, bid_order) = load_match(deps.storage, &env, market_id, &ask_id, &bid_id)?;
+    let (messages, trade) = settle_match(
+        deps.storage,
+        &env,
+        info.sender,
+        market.as_ref(),
+        ask_order,
+        bid_order,
+    )?;
+
+    let mut attributes = EventAttributes::new("execute", &env).matched(&trade.ask, &trade.bid);
+    if !trade.fees.is_empty() {
+        attributes = attributes.add_coins(FEES_KEY, &trade.fees);
+    }
+    Ok(Response::new()
+        .add_attributes(attributes)
+        .add_messages(messages)
+        .set_data(to_binary(&trade)?))
+}
+
+// mark a matched ask and bid filled, release their escrow and keep a receipt of the trade,
+// returning the messages that settle it
+pub(crate) fn settle_match(
+    storage: &mut dyn Storage,
+    env: &Env,
+    matcher: Addr,
+    market: Option<&Market>,
+    mut ask_order: AskOrder,
+    mut bid_order: BidOrder,
+) -> Result<(Vec<BankMsg>, Trade), ContractError> {
+    let fee_schedule = match_fees(&get_config(storage)?, market);
+    let fees = quote_fees(fee_schedule.as_ref(), &ask_order.quote);
+    let messages = match_messages(fee_schedule.as_ref(), &ask_order, &bid_order);
+
+    ask_order.update_status(OrderStatus::Filled, env.block.time, None);
+    bid_order.update_status(OrderStatus::Filled, env.block.time, None);
+    get_ask_storage(storage).save(&ask_order.key(), &ask_order)?;
+    get_bid_storage(storage).save(&bid_order.key(), &bid_order)?;
+    save_closed_order(
+        storage,
+        Side::Ask,
+        &ask_order.owner,
+        &ask_order.id,
+        ask_order.market.as_deref(),
+        env.block.time,
+    )?;
+    save_closed_order(
+        storage,
+        Side::Bid,
+        &bid_order.owner,
+        &bid_order.id,
+        bid_order.market.as_deref(),
+        env.block.time,
+    )?;
+    release_escrow(storage, &ask_order.base)?;
+    release_escrow(storage, &bid_order.quote)?;
+    let trade = record_trade(storage, env, matcher, ask_order, bid_order, fees)?;
+    update_pair_stats(storage, &trade)?;
+    record_price_observation(storage, &trade)?;
+
+    Ok((messages, trade))
+}
+
+// set how long closed orders are kept, admin only
+fn set_order_retention(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    seconds: u64,
 ) -> Result<Response<ProvenanceMsg>, ContractError> {
-    // only the admin may execute matches
     if info.sender != get_contract_info(deps.storage)?.admin {
         return Err(ContractError::Unauthorized {});
     }
 
-    // return error if id is empty
-    if ask_id.is_empty() | bid_id.is_empty() {
-        return Err(ContractError::Unauthorized {});
+    let mut config = get_config(deps.storage)?;
+    config.order_retention = seconds;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(
+        EventAttributes::new("set_order_retention", &env).add("retention", seconds.to_string()),
+    ))
+}
+
+// remove closed orders older than the retention period, oldest first
+fn prune_orders(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let retention = get_config(deps.storage)?.order_retention;
+    let cutoff = env
+        .block
+        .time
+        .nanos()
+        .saturating_sub(retention.saturating_mul(1_000_000_000));
+
+    let closed_orders = CLOSED_ORDERS
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::exclusive((cutoff, "", ""))),
+            Order::Ascending,
+        )
+        .take(limit.unwrap_or(PRUNE_LIMIT).min(PRUNE_LIMIT) as usize)
+        .collect::<StdResult<Vec<((u64, String, String), Option<String>)>>>()?;
+
+    for ((closed_time, side, id), market) in &closed_orders {
+        let key = order_key(market.as_deref(), id);
+        CLOSED_ORDERS.remove(deps.storage, (*closed_time, side, id));
+        if side == &Side::Ask.to_string() {
+            let mut ask_storage = get_ask_storage(deps.storage);
+            if let Some(ask_order) = ask_storage.may_load(&key)? {
+                ask_storage.remove(&key);
+                release_client_order_id(
+                    deps.storage,
+                    Side::Ask,
+                    &ask_order.owner,
+                    ask_order.client_order_id.as_deref(),
+                    id,
+                )?;
+            }
+        } else {
+            let mut bid_storage = get_bid_storage(deps.storage);
+            if let Some(bid_order) = bid_storage.may_load(&key)? {
+                bid_storage.remove(&key);
+                release_client_order_id(
+                    deps.storage,
+                    Side::Bid,
+                    &bid_order.owner,
+                    bid_order.client_order_id.as_deref(),
+                    id,
+                )?;
+            }
+        }
     }
 
-    // return error if funds sent
-    if !info.funds.is_empty() {
-        return Err(ContractError::ExecuteWithFunds {});
+    Ok(Response::new().add_attributes(
+        EventAttributes::new("prune_orders", &env).add("pruned", closed_orders.len().to_string()),
+    ))
+}
+
+// forget a pruned order's client order id unless it has since been reused
+fn release_client_order_id(
+    storage: &mut dyn Storage,
+    side: Side,
+    owner: &Addr,
+    client_order_id: Option<&str>,
+    id: &str,
+) -> StdResult<()> {
+    if let Some(client_order_id) = client_order_id {
+        let side_key = side.to_string();
+        let key = (side_key.as_str(), owner.as_str(), client_order_id);
+        if CLIENT_ORDER_IDS.may_load(storage, key)?.as_deref() == Some(id) {
+            CLIENT_ORDER_IDS.remove(storage, key);
+        }
     }
+    Ok(())
+}
 
-    let ask_storage_read = get_ask_storage_read(deps.storage);
-    let ask_order_result = ask_storage_read.load(ask_id.as_bytes());
-    if ask_order_result.is_err() {
-        return Err(ContractError::AskBidMismatch {});
+// return error if the order has been filled, cancelled or expired
+pub(crate) fn check_open(status: OrderStatus) -> Result<(), ContractError> {
+    if status.is_closed() {
+        return Err(ContractError::OrderClosed {
+            status: status.to_string(),
+        });
     }
+    Ok(())
+}
 
-    let bid_storage_read = get_bid_storage_read(deps.storage);
-    let bid_order_result = bid_storage_read.load(bid_id.as_bytes());
-    if bid_order_result.is_err() {
-        return Err(ContractError::AskBidMismatch {});
+// index an owner's open order, and a good-till-time order by its expiration
+fn save_open_order(
+    storage: &mut dyn Storage,
+    side: Side,
+    owner: &Addr,
+    id: &str,
+    market: Option<&str>,
+    time_in_force: TimeInForce,
+) -> StdResult<()> {
+    let side = side.to_string();
+    if let TimeInForce::Gtt { expires } = time_in_force {
+        ORDER_EXPIRATIONS.save(
+            storage,
+            (expires.nanos(), &side, id),
+            &market.map(String::from),
+        )?;
     }
+    OWNER_ORDERS.save(
+        storage,
+        (owner.as_str(), &side, id),
+        &market.map(String::from),
+    )
+}
 
-    let ask_order = ask_order_result.unwrap();
-    let bid_order = bid_order_result.unwrap();
+// index a closed order for pruning, it no longer counts among the owner's open orders
+pub(crate) fn save_closed_order(
+    storage: &mut dyn Storage,
+    side: Side,
+    owner: &Addr,
+    id: &str,
+    market: Option<&str>,
+    time: Timestamp,
+) -> StdResult<()> {
+    let side = side.to_string();
+    OWNER_ORDERS.remove(storage, (owner.as_str(), &side, id));
+    CLOSED_ORDERS.save(
+        storage,
+        (time.nanos(), &side, id),
+        &market.map(String::from),
+    )
+}
 
-    if !is_executable(&ask_order, &bid_order) {
-        return Err(ContractError::AskBidMismatch {});
+// load an ask and bid and check that they can be matched now
+pub(crate) fn load_match(
+    storage: &dyn Storage,
+    env: &Env,
+    market: Option<&str>,
+    ask_id: &str,
+    bid_id: &str,
+) -> Result<(AskOrder, BidOrder), MatchFailure> {
+    let ask_order = load_ask(storage, market, ask_id)
+        .map_err(|_| MatchFailure::AskNotFound { id: ask_id.into() })?;
+    let bid_order = load_bid(storage, market, bid_id)
+        .map_err(|_| MatchFailure::BidNotFound { id: bid_id.into() })?;
+
+    // a good-till-time order past its expiration is expired even before it is swept
+    let ask_status = ask_order.status_at(env.block.time);
+    if ask_status.is_closed() {
+        return Err(MatchFailure::AskClosed { status: ask_status });
+    }
+    let bid_status = bid_order.status_at(env.block.time);
+    if bid_status.is_closed() {
+        return Err(MatchFailure::BidClosed { status: bid_status });
+    }
+    if let Some(effective_time) = bid_order.effective_time {
+        if effective_time > env.block.time {
+            return Err(MatchFailure::BidNotEffective { effective_time });
+        }
     }
 
-    // 'send quote to asker' and 'send base to bidder' messages
-    let response = Response::new()
-        .add_messages(vec![
-            BankMsg::Send {
-                to_address: ask_order.owner.to_string(),
-                amount: ask_order.quote,
-            },
-            BankMsg::Send {
-                to_address: bid_order.owner.to_string(),
-                amount: bid_order.base,
-            },
-        ])
-        .add_attributes(vec![attr("action", "execute")]);
+    let (ask_base, bid_base) = (sorted_coins(&ask_order.base), sorted_coins(&bid_order.base));
+    if ask_base != bid_base {
+        return Err(if denoms(&ask_base) != denoms(&bid_base) {
+            MatchFailure::BaseDenomMismatch {
+                ask: ask_base,
+                bid: bid_base,
+            }
+        } else {
+            MatchFailure::BaseAmountMismatch {
+                ask: ask_base,
+                bid: bid_base,
+            }
+        });
+    }
+    let (ask_quote, bid_quote) = (
+        sorted_coins(&ask_order.quote),
+        sorted_coins(&bid_order.quote),
+    );
+    if ask_quote != bid_quote {
+        return Err(if denoms(&ask_quote) != denoms(&bid_quote) {
+            MatchFailure::QuoteDenomMismatch {
+                ask: ask_quote,
+                bid: bid_quote,
+            }
+        } else {
+            MatchFailure::QuoteAmountMismatch {
+                ask: ask_quote,
+                bid: bid_quote,
+            }
+        });
+    }
+
+    Ok((ask_order, bid_order))
+}
+
+// fees of the market, or of the venue config in the default market
+fn match_fees(config: &Config, market: Option<&Market>) -> Option<Fees> {
+    match market {
+        Some(market) => market.fees.to_owned(),
+        None => config.fees.to_owned(),
+    }
+}
+
+// 'send quote less fees to asker', 'send base to bidder' and 'send fees to collector' messages
+fn match_messages(
+    fee_schedule: Option<&Fees>,
+    ask_order: &AskOrder,
+    bid_order: &BidOrder,
+) -> Vec<BankMsg> {
+    let fees = quote_fees(fee_schedule, &ask_order.quote);
+    let mut messages = vec![];
+    // a fee of the whole quote leaves the asker nothing to receive
+    let proceeds = subtract_coins(&ask_order.quote, &fees).unwrap_or_default();
+    if !proceeds.is_empty() {
+        messages.push(BankMsg::Send {
+            to_address: ask_order.owner.to_string(),
+            amount: proceeds,
+        });
+    }
+    messages.push(BankMsg::Send {
+        to_address: bid_order.owner.to_string(),
+        amount: bid_order.base.to_owned(),
+    });
+    if let Some(fee_schedule) = fee_schedule.filter(|_| !fees.is_empty()) {
+        messages.push(BankMsg::Send {
+            to_address: fee_schedule.collector.to_string(),
+            amount: fees,
+        });
+    }
+    messages
+}
+
+// dry run of a match by the matcher, reporting the messages it would send or why it would fail
+fn simulate_match(
+    deps: Deps<ProvenanceQuery>,
+    env: Env,
+    ask_id: String,
+    bid_id: String,
+    market: Option<String>,
+    matcher: String,
+) -> StdResult<SimulateMatchResponse> {
+    let matcher = deps.api.addr_validate(&matcher)?;
+    let market_info = market
+        .as_deref()
+        .map(|market| get_market(deps.storage, market))
+        .transpose()?;
+    let market_id = market_info.as_ref().map(|market| market.id.as_str());
+    let fee_schedule = match_fees(&get_config(deps.storage)?, market_info.as_ref());
+
+    // the checks `execute` and `execute_match` make before loading the orders
+    let execute_match_msg = ExecuteMsg::ExecuteMatch {
+        ask_id: ask_id.to_owned(),
+        bid_id: bid_id.to_owned(),
+        market,
+    };
+    let checked = match check_not_paused(deps.storage, &execute_match_msg)
+        .and_then(|_| check_matcher(deps.storage, market_info.as_ref(), &matcher))
+    {
+        Ok(()) => load_match(deps.storage, &env, market_id, &ask_id, &bid_id),
+        Err(ContractError::Paused {}) => Err(MatchFailure::Paused {}),
+        Err(ContractError::Unauthorized {}) => Err(MatchFailure::MatcherNotAllowed {
+            matcher: matcher.into_string(),
+        }),
+        Err(ContractError::Std(error)) => return Err(error),
+        Err(error) => return Err(StdError::generic_err(error.to_string())),
+    };
 
-    // finally remove the orders from storage
-    get_ask_storage(deps.storage).remove(ask_id.as_bytes());
-    get_bid_storage(deps.storage).remove(bid_id.as_bytes());
+    Ok(match checked {
+        Ok((ask_order, bid_order)) => SimulateMatchResponse {
+            executable: true,
+            failure: None,
+            fees: quote_fees(fee_schedule.as_ref(), &ask_order.quote),
+            messages: match_messages(fee_schedule.as_ref(), &ask_order, &bid_order),
+        },
+        Err(failure) => SimulateMatchResponse {
+            executable: false,
+            failure: Some(failure),
+            fees: vec![],
+            messages: vec![],
+        },
+    })
+}
 
-    Ok(response)
+fn denoms(coins: &[Coin]) -> Vec<&str> {
+    coins.iter().map(|coin| coin.denom.as_str()).collect()
 }
 
-fn is_executable(ask_order: &AskOrder, bid_order: &BidOrder) -> bool {
-    // sort the base and quote vectors by the order chain: denom, amount
-    let coin_sorter =
-        |a: &Coin, b: &Coin| a.denom.cmp(&b.denom).then_with(|| a.amount.cmp(&b.amount));
+pub(crate) fn is_executable(ask_order: &AskOrder, bid_order: &BidOrder) -> bool {
+    sorted_coins(&ask_order.base) == sorted_coins(&bid_order.base)
+        && sorted_coins(&ask_order.quote) == sorted_coins(&bid_order.quote)
+}
 
-    let mut ask_base = ask_order.base.to_owned();
-    ask_base.sort_by(coin_sorter);
-    let mut bid_base = bid_order.base.to_owned();
-    bid_base.sort_by(coin_sorter);
+// sort a coin vector by the order chain: denom, amount
+pub(crate) fn sorted_coins(coins: &[Coin]) -> Vec<Coin> {
+    let mut sorted = coins.to_owned();
+    sorted
+        .sort_by(|a: &Coin, b: &Coin| a.denom.cmp(&b.denom).then_with(|| a.amount.cmp(&b.amount)));
+    sorted
+}
 
-    let mut ask_quote = ask_order.quote.to_owned();
-    ask_quote.sort_by(coin_sorter);
-    let mut bid_quote = bid_order.quote.to_owned();
-    bid_quote.sort_by(coin_sorter);
+// merge two coin vectors, summing amounts of the same denom
+pub(crate) fn add_coins(coins: &[Coin], addition: &[Coin]) -> Vec<Coin> {
+    let mut total = coins.to_owned();
+    for coin in addition {
+        match total.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(existing) => existing.amount += coin.amount,
+            None => total.push(coin.to_owned()),
+        }
+    }
+    total
+}
 
-    ask_base == bid_base && ask_quote == bid_quote
+// remove an exact amount of coins from a coin vector, which must not end up empty
+pub(crate) fn subtract_coins(
+    coins: &[Coin],
+    subtraction: &[Coin],
+) -> Result<Vec<Coin>, ContractError> {
+    let mut remaining = coins.to_owned();
+    for coin in subtraction {
+        match remaining.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(existing) if existing.amount >= coin.amount => existing.amount -= coin.amount,
+            _ => return Err(ContractError::InvalidRefund {}),
+        }
+    }
+    remaining.retain(|c| !c.amount.is_zero());
+    if remaining.is_empty() {
+        return Err(ContractError::InvalidRefund {});
+    }
+    Ok(remaining)
 }
 
 // smart contract query entrypoint
 #[entry_point]
-pub fn query(deps: Deps<ProvenanceQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<ProvenanceQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetAsk { id } => {
-            let ask_storage_read = get_ask_storage_read(deps.storage);
-            return to_binary(&ask_storage_read.load(id.as_bytes())?);
+        QueryMsg::AllPairStats { start_after, limit } => {
+            to_binary(&get_all_pair_stats(deps.storage, &env, start_after, limit)?)
+        }
+        QueryMsg::GetAsk { id, market } => {
+            to_binary(&load_ask(deps.storage, market.as_deref(), &id)?)
+        }
+        QueryMsg::GetAskByClientOrderId {
+            client_order_id,
+            owner,
+            market,
+        } => {
+            let id = get_order_id(deps.storage, Side::Ask, &owner, &client_order_id)?;
+            to_binary(&load_ask(deps.storage, market.as_deref(), &id)?)
+        }
+        QueryMsg::GetAuction { id } => to_binary(&AUCTIONS.load(deps.storage, &id)?),
+        QueryMsg::GetBid { id, market } => {
+            to_binary(&load_bid(deps.storage, market.as_deref(), &id)?)
+        }
+        QueryMsg::GetBidByClientOrderId {
+            client_order_id,
+            owner,
+            market,
+        } => {
+            let id = get_order_id(deps.storage, Side::Bid, &owner, &client_order_id)?;
+            to_binary(&load_bid(deps.storage, market.as_deref(), &id)?)
+        }
+        QueryMsg::GetComplianceAddress {} => {
+            to_binary(&get_config(deps.storage)?.compliance_address)
         }
-        QueryMsg::GetBid { id } => {
-            let bid_storage_read = get_bid_storage_read(deps.storage);
-            return to_binary(&bid_storage_read.load(id.as_bytes())?);
+        QueryMsg::GetConditionalOrder { id } => {
+            to_binary(&CONDITIONAL_ORDERS.load(deps.storage, &id)?)
         }
+        QueryMsg::GetConditionalOrders {
+            pair,
+            start_after,
+            limit,
+        } => to_binary(&get_conditional_orders(
+            deps.storage,
+            &pair,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetConfig {} => to_binary(&get_config(deps.storage)?),
         QueryMsg::GetContractInfo {} => to_binary(&get_contract_info(deps.storage)?),
+        QueryMsg::GetEscrowTotals {} => to_binary(&get_escrow_totals(deps.storage)?),
+        QueryMsg::GetHtlc { id } => to_binary(&HTLCS.load(deps.storage, &id)?),
+        QueryMsg::GetHtlcs { start_after, limit } => {
+            to_binary(&get_htlcs(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::GetMarket { id } => to_binary(&get_market(deps.storage, &id)?),
+        QueryMsg::GetMarkets { start_after, limit } => {
+            to_binary(&get_markets(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::GetOperatorApprovals {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&get_operator_approvals(
+            deps.storage,
+            &owner,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetOrderRetention {} => to_binary(&get_config(deps.storage)?.order_retention),
+        QueryMsg::GetRfq { id } => to_binary(&RFQS.load(deps.storage, &id)?),
+        QueryMsg::GetRfqQuotes { rfq_id } => to_binary(&get_rfq_quotes(deps.storage, &rfq_id)?),
+        QueryMsg::GetSwap { id } => to_binary(&SWAPS.load(deps.storage, &id)?),
+        QueryMsg::GetTrade { id } => to_binary(&get_trade(deps.storage, id)?),
+        QueryMsg::PairStats {
+            base_denom,
+            quote_denom,
+        } => to_binary(&get_pair_stats(
+            deps.storage,
+            &env,
+            &base_denom,
+            &quote_denom,
+        )?),
+        QueryMsg::GetTradingPair {
+            base_denom,
+            quote_denom,
+        } => to_binary(&get_trading_pair(deps.storage, &base_denom, &quote_denom)?),
+        QueryMsg::GetTradingPairs { start_after, limit } => {
+            to_binary(&get_trading_pairs(deps.storage, start_after, limit)?)
+        }
+        QueryMsg::SimulateMatch {
+            ask_id,
+            bid_id,
+            market,
+            matcher,
+        } => to_binary(&simulate_match(deps, env, ask_id, bid_id, market, matcher)?),
+        QueryMsg::TradesByDenom {
+            denom,
+            start_after,
+            limit,
+        } => to_binary(&get_trades_by_denom(
+            deps.storage,
+            &denom,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::TradesByOwner {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&get_trades_by_owner(
+            deps.storage,
+            &owner,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Twap {
+            pair,
+            window_seconds,
+        } => to_binary(&get_twap(deps.storage, &env, &pair, window_seconds)?),
     }
 }
 
@@ -332,77 +1399,118 @@ pub fn query(deps: Deps<ProvenanceQuery>, _env: Env, msg: QueryMsg) -> StdResult
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
-    use cosmwasm_std::{coin, coins, Addr, BankMsg};
+    use cosmwasm_std::{attr, coin, coins, from_binary, Addr, BankMsg};
     use cosmwasm_std::{CosmosMsg, Uint128};
     use provwasm_mocks::mock_dependencies;
     use provwasm_std::{NameMsgParams, ProvenanceMsg, ProvenanceMsgParams, ProvenanceRoute};
 
     use crate::contract_info::{ContractInfo, CONTRACT_TYPE, CONTRACT_VERSION};
-    use crate::state::get_bid_storage_read;
+    use crate::state::{get_ask_storage_read, get_bid_storage_read, Trade};
 
     use super::*;
     use crate::msg::ExecuteMsg;
+    use crate::testing::env_at;
 
     #[test]
     fn test_is_executable() {
         assert!(is_executable(
             &AskOrder {
                 base: coins(100, "base_1"),
+                client_order_id: None,
                 id: "ask_id".to_string(),
+                market: None,
                 owner: Addr::unchecked("asker"),
                 quote: coins(100, "quote_1"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
             },
             &BidOrder {
                 base: coins(100, "base_1"),
+                client_order_id: None,
                 effective_time: Some(Timestamp::default()),
                 id: "bid_id".to_string(),
+                market: None,
                 owner: Addr::unchecked("bidder"),
                 quote: coins(100, "quote_1"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
             }
         ));
         assert!(is_executable(
             &AskOrder {
                 base: vec![coin(100, "base_1"), coin(200, "base_2")],
+                client_order_id: None,
                 id: "ask_id".to_string(),
+                market: None,
                 owner: Addr::unchecked("asker"),
                 quote: coins(100, "quote_1"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
             },
             &BidOrder {
                 base: vec![coin(200, "base_2"), coin(100, "base_1")],
+                client_order_id: None,
                 effective_time: Some(Timestamp::default()),
                 id: "bid_id".to_string(),
+                market: None,
                 owner: Addr::unchecked("bidder"),
                 quote: coins(100, "quote_1"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
             }
         ));
         assert!(!is_executable(
             &AskOrder {
                 base: coins(100, "base_1"),
+                client_order_id: None,
                 id: "ask_id".to_string(),
+                market: None,
                 owner: Addr::unchecked("asker"),
                 quote: coins(100, "quote_1"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
             },
             &BidOrder {
                 base: coins(100, "base_2"),
+                client_order_id: None,
                 effective_time: Some(Timestamp::default()),
                 id: "bid_id".to_string(),
+                market: None,
                 owner: Addr::unchecked("bidder"),
                 quote: coins(100, "quote_1"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
             }
         ));
         assert!(!is_executable(
             &AskOrder {
                 base: coins(100, "base_1"),
+                client_order_id: None,
                 id: "ask_id".to_string(),
+                market: None,
                 owner: Addr::unchecked("asker"),
                 quote: coins(100, "quote_1"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
             },
             &BidOrder {
                 base: coins(100, "base_1"),
+                client_order_id: None,
                 effective_time: Some(Timestamp::default()),
                 id: "bid_id".to_string(),
+                market: None,
                 owner: Addr::unchecked("bidder"),
                 quote: coins(100, "quote_2"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
             }
         ));
     }
@@ -413,8 +1521,9 @@ mod tests {
         let mut deps = mock_dependencies(&[]);
         let info = mock_info("contract_admin", &[]);
         let init_msg = InstantiateMsg {
-            bind_name: "contract_bind_name".to_string(),
+            bind_name: Some("contract_bind_name".into()),
             contract_name: "contract_name".to_string(),
+            ..Default::default()
         };
 
         // initialize
@@ -429,40 +1538,106 @@ mod tests {
                     CosmosMsg::Custom(ProvenanceMsg {
                         route: ProvenanceRoute::Name,
                         params: ProvenanceMsgParams::Name(NameMsgParams::BindName {
-                            name: init_msg.bind_name,
+                            name: init_msg.bind_name.unwrap(),
                             address: Addr::unchecked(MOCK_CONTRACT_ADDR),
                             restrict: true
                         }),
                         version: "2.0.0".to_string(),
                     })
                 );
-                let expected_contract_info = ContractInfo {
-                    admin: Addr::unchecked("contract_admin"),
-                    bind_name: "contract_bind_name".to_string(),
-                    contract_name: "contract_name".to_string(),
-                    contract_type: CONTRACT_TYPE.into(),
-                    contract_version: CONTRACT_VERSION.into(),
-                };
-
-                assert_eq!(init_response.attributes.len(), 2);
                 assert_eq!(
-                    init_response.attributes[0],
-                    attr("contract_info", format!("{:?}", expected_contract_info))
+                    init_response.attributes,
+                    vec![
+                        attr("action", "instantiate"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "1571797419879305533"),
+                        attr("admin", "contract_admin"),
+                        attr("contract_name", "contract_name"),
+                        attr("bind_name", "contract_bind_name"),
+                    ]
+                );
+
+                // verify contract info stored
+                assert_eq!(
+                    get_contract_info(&deps.storage).unwrap(),
+                    ContractInfo {
+                        admin: Addr::unchecked("contract_admin"),
+                        bind_name: Some("contract_bind_name".into()),
+                        bound_names: vec!["contract_bind_name".into()],
+                        contract_name: "contract_name".to_string(),
+                        contract_type: CONTRACT_TYPE.into(),
+                        contract_version: CONTRACT_VERSION.into(),
+                    }
                 );
-                assert_eq!(init_response.attributes[1], attr("action", "init"));
             }
             error => panic!("failed to initialize: {:?}", error),
         }
     }
 
     #[test]
-    fn instantiate_with_invalid_data() {
-        // create invalid init data
+    fn instantiate_binds_names_optionally() {
+        let mut deps = mock_dependencies(&[]);
+        let init_response = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("contract_admin", &[]),
+            InstantiateMsg {
+                contract_name: "contract_name".into(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(init_response.messages.is_empty());
+        assert_eq!(get_contract_info(&deps.storage).unwrap().bind_name, None);
+
+        // aliases are bound by the admin, unrestricted when asked
+        let bind = |deps: DepsMut<ProvenanceQuery>, sender: &str, name: &str| {
+            execute(
+                deps,
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::BindName {
+                    name: name.into(),
+                    name_binding: Some(NameBinding::Unrestricted),
+                },
+            )
+        };
+        match bind(deps.as_mut(), "anyone", "alias.sc") {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let bind_response = bind(deps.as_mut(), "contract_admin", "alias.sc").unwrap();
+        assert_eq!(
+            bind_response.messages[0].msg,
+            CosmosMsg::Custom(ProvenanceMsg {
+                route: ProvenanceRoute::Name,
+                params: ProvenanceMsgParams::Name(NameMsgParams::BindName {
+                    name: "alias.sc".into(),
+                    address: Addr::unchecked(MOCK_CONTRACT_ADDR),
+                    restrict: false
+                }),
+                version: "2.0.0".to_string(),
+            })
+        );
+        match bind(deps.as_mut(), "contract_admin", "alias.sc") {
+            Err(ContractError::NameAlreadyBound { name }) => assert_eq!(name, "alias.sc"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let contract_info: ContractInfo =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetContractInfo {}).unwrap())
+                .unwrap();
+        assert_eq!(contract_info.bound_names, vec!["alias.sc"]);
+    }
+
+    #[test]
+    fn instantiate_with_invalid_data() {
+        // create invalid init data
         let mut deps = mock_dependencies(&[]);
         let info = mock_info("contract_owner", &[]);
         let init_msg = InstantiateMsg {
-            bind_name: "".to_string(),
+            bind_name: Some("".into()),
             contract_name: "contract_name".to_string(),
+            ..Default::default()
         };
 
         // initialize
@@ -480,8 +1655,9 @@ mod tests {
         }
 
         let init_msg = InstantiateMsg {
-            bind_name: "bind_name".to_string(),
+            bind_name: Some("bind_name".into()),
             contract_name: "".to_string(),
+            ..Default::default()
         };
 
         // initialize
@@ -506,7 +1682,7 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         ) {
@@ -515,8 +1691,11 @@ mod tests {
 
         // create ask data
         let create_ask_msg = ExecuteMsg::CreateAsk {
-            id: "ask_id".into(),
+            client_order_id: Some("client_ask_id".into()),
             quote: coins(100, "quote_1"),
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         let asker_info = mock_info("asker", &coins(2, "base_1"));
@@ -532,8 +1711,20 @@ mod tests {
         // verify handle create ask response
         match create_ask_response {
             Ok(response) => {
-                assert_eq!(response.attributes.len(), 1);
-                assert_eq!(response.attributes[0], attr("action", "create_ask"));
+                assert_eq!(
+                    response.attributes,
+                    vec![
+                        attr("action", "create_ask"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "1571797419879305533"),
+                        attr("order_id", "1"),
+                        attr("side", "ask"),
+                        attr("owner", "asker"),
+                        attr("base", "2base_1"),
+                        attr("quote", "100quote_1"),
+                        attr("client_order_id", "client_ask_id"),
+                    ]
+                );
             }
             Err(error) => {
                 panic!("failed to create ask: {:?}", error)
@@ -542,16 +1733,32 @@ mod tests {
 
         // verify ask order stored
         let ask_storage = get_ask_storage_read(&deps.storage);
-        if let ExecuteMsg::CreateAsk { id, quote } = create_ask_msg {
-            match ask_storage.load("ask_id".to_string().as_bytes()) {
+        if let ExecuteMsg::CreateAsk {
+            client_order_id,
+            quote,
+            market: None,
+            take: None,
+            time_in_force: None,
+        } = create_ask_msg
+        {
+            match ask_storage.load("1".to_string().as_bytes()) {
                 Ok(stored_order) => {
                     assert_eq!(
                         stored_order,
                         AskOrder {
                             base: asker_info.funds,
-                            id,
+                            client_order_id,
+                            id: "1".into(),
+                            market: None,
                             owner: asker_info.sender,
                             quote,
+                            status: OrderStatus::Open,
+                            status_updates: vec![StatusUpdate {
+                                reason: None,
+                                status: OrderStatus::Open,
+                                time: mock_env().block.time,
+                            }],
+                            time_in_force: TimeInForce::Gtc,
                         }
                     )
                 }
@@ -571,7 +1778,7 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         ) {
@@ -580,8 +1787,11 @@ mod tests {
 
         // create ask invalid data
         let create_ask_msg = ExecuteMsg::CreateAsk {
-            id: "".into(),
+            client_order_id: None,
             quote: vec![],
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         // handle create ask
@@ -592,21 +1802,22 @@ mod tests {
             create_ask_msg,
         );
 
-        // verify handle create ask response returns ContractError::MissingField { id }
+        // verify handle create ask response returns ContractError::MissingAskBase
         match create_ask_response {
             Ok(_) => panic!("expected error, but handle_create_ask_response ok"),
             Err(error) => match error {
-                ContractError::MissingField { field } => {
-                    assert_eq!(field, "id")
-                }
+                ContractError::MissingAskBase => {}
                 error => panic!("unexpected error: {:?}", error),
             },
         }
 
-        // create ask missing id
+        // create ask with empty client order id
         let create_ask_msg = ExecuteMsg::CreateAsk {
-            id: "".into(),
+            client_order_id: Some("".into()),
             quote: coins(100, "quote_1"),
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         // handle create ask
@@ -617,21 +1828,22 @@ mod tests {
             create_ask_msg,
         );
 
-        // verify execute create ask response returns ContractError::MissingField { id }
+        // verify execute create ask response returns ContractError::InvalidId
         match create_ask_response {
             Ok(_) => panic!("expected error, but execute_create_ask_response ok"),
             Err(error) => match error {
-                ContractError::MissingField { field } => {
-                    assert_eq!(field, "id")
-                }
+                ContractError::InvalidId { id } => assert_eq!(id, ""),
                 error => panic!("unexpected error: {:?}", error),
             },
         }
 
         // create ask missing quote
         let create_ask_msg = ExecuteMsg::CreateAsk {
-            id: "id".into(),
+            client_order_id: None,
             quote: vec![],
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         // execute create ask
@@ -655,8 +1867,11 @@ mod tests {
 
         // create ask missing base
         let create_ask_msg = ExecuteMsg::CreateAsk {
-            id: "id".into(),
+            client_order_id: None,
             quote: coins(100, "quote_1"),
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         // execute create ask
@@ -671,7 +1886,7 @@ mod tests {
         match create_ask_response {
             Ok(_) => panic!("expected error, but execute_create_ask_response ok"),
             Err(error) => match error {
-                ContractError::MissingAskBase {} => {}
+                ContractError::MissingAskBase => {}
                 error => panic!("unexpected error: {:?}", error),
             },
         }
@@ -684,7 +1899,7 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         ) {
@@ -693,9 +1908,12 @@ mod tests {
 
         // create bid data
         let create_bid_msg = ExecuteMsg::CreateBid {
-            id: "bid_id".into(),
             base: coins(100, "base_1"),
+            client_order_id: None,
             effective_time: Some(Timestamp::default()),
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         let bidder_info = mock_info("bidder", &coins(2, "mark_2"));
@@ -711,8 +1929,19 @@ mod tests {
         // verify execute create bid response
         match create_bid_response {
             Ok(response) => {
-                assert_eq!(response.attributes.len(), 1);
-                assert_eq!(response.attributes[0], attr("action", "create_bid"));
+                assert_eq!(
+                    response.attributes,
+                    vec![
+                        attr("action", "create_bid"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "1571797419879305533"),
+                        attr("order_id", "1"),
+                        attr("side", "bid"),
+                        attr("owner", "bidder"),
+                        attr("base", "100base_1"),
+                        attr("quote", "2mark_2"),
+                    ]
+                );
             }
             Err(error) => {
                 panic!("failed to create bid: {:?}", error)
@@ -722,21 +1951,33 @@ mod tests {
         // verify bid order stored
         let bid_storage = get_bid_storage_read(&deps.storage);
         if let ExecuteMsg::CreateBid {
-            id,
             base,
+            client_order_id,
             effective_time,
+            market: None,
+            take: None,
+            time_in_force: None,
         } = create_bid_msg
         {
-            match bid_storage.load("bid_id".to_string().as_bytes()) {
+            match bid_storage.load("1".to_string().as_bytes()) {
                 Ok(stored_order) => {
                     assert_eq!(
                         stored_order,
                         BidOrder {
                             base,
+                            client_order_id,
                             effective_time,
-                            id,
+                            id: "1".into(),
+                            market: None,
                             owner: bidder_info.sender,
                             quote: bidder_info.funds,
+                            status: OrderStatus::Open,
+                            status_updates: vec![StatusUpdate {
+                                reason: None,
+                                status: OrderStatus::Open,
+                                time: mock_env().block.time,
+                            }],
+                            time_in_force: TimeInForce::Gtc,
                         }
                     )
                 }
@@ -756,18 +1997,21 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         ) {
             panic!("unexpected error: {:?}", error)
         }
 
-        // create bid missing id
+        // create bid with empty client order id
         let create_bid_msg = ExecuteMsg::CreateBid {
-            id: "".into(),
             base: coins(100, "base_1"),
+            client_order_id: Some("".into()),
             effective_time: Some(Timestamp::default()),
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         // execute create bid
@@ -778,22 +2022,23 @@ mod tests {
             create_bid_msg,
         );
 
-        // verify execute create bid response returns ContractError::MissingField { id }
+        // verify execute create bid response returns ContractError::InvalidId
         match create_bid_response {
             Ok(_) => panic!("expected error, but create_bid_response ok"),
             Err(error) => match error {
-                ContractError::MissingField { field } => {
-                    assert_eq!(field, "id")
-                }
+                ContractError::InvalidId { id } => assert_eq!(id, ""),
                 error => panic!("unexpected error: {:?}", error),
             },
         }
 
         // create bid missing base
         let create_bid_msg = ExecuteMsg::CreateBid {
-            id: "id".into(),
             base: vec![],
+            client_order_id: None,
             effective_time: Some(Timestamp::default()),
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         // execute create bid
@@ -817,9 +2062,12 @@ mod tests {
 
         // create bid missing quote
         let create_bid_msg = ExecuteMsg::CreateBid {
-            id: "id".into(),
             base: coins(100, "base_1"),
+            client_order_id: None,
             effective_time: Some(Timestamp::default()),
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         // execute create bid
@@ -834,7 +2082,7 @@ mod tests {
         match create_bid_response {
             Ok(_) => panic!("expected error, but create_bid_response ok"),
             Err(error) => match error {
-                ContractError::MissingBidQuote {} => {}
+                ContractError::MissingBidQuote => {}
                 error => panic!("unexpected error: {:?}", error),
             },
         }
@@ -847,7 +2095,7 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         ) {
@@ -858,8 +2106,11 @@ mod tests {
         let asker_info = mock_info("asker", &coins(200, "base_1"));
 
         let create_ask_msg = ExecuteMsg::CreateAsk {
-            id: "ask_id".into(),
+            client_order_id: None,
             quote: coins(100, "quote_1"),
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         // execute create ask
@@ -869,13 +2120,14 @@ mod tests {
 
         // verify ask order stored
         let ask_storage = get_ask_storage_read(&deps.storage);
-        assert!(ask_storage.load("ask_id".to_string().as_bytes()).is_ok());
+        assert!(ask_storage.load("1".to_string().as_bytes()).is_ok());
 
         // cancel ask order
         let asker_info = mock_info("asker", &[]);
 
         let cancel_ask_msg = ExecuteMsg::CancelAsk {
-            id: "ask_id".to_string(),
+            id: "1".to_string(),
+            market: None,
         };
         let cancel_ask_response = execute(
             deps.as_mut(),
@@ -886,10 +2138,18 @@ mod tests {
 
         match cancel_ask_response {
             Ok(cancel_ask_response) => {
-                assert_eq!(cancel_ask_response.attributes.len(), 1);
                 assert_eq!(
-                    cancel_ask_response.attributes[0],
-                    attr("action", "cancel_ask")
+                    cancel_ask_response.attributes,
+                    vec![
+                        attr("action", "cancel_ask"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "1571797419879305533"),
+                        attr("order_id", "1"),
+                        attr("side", "ask"),
+                        attr("owner", "asker"),
+                        attr("base", "200base_1"),
+                        attr("quote", "100quote_1"),
+                    ]
                 );
                 assert_eq!(cancel_ask_response.messages.len(), 1);
                 assert_eq!(
@@ -903,19 +2163,25 @@ mod tests {
             Err(error) => panic!("unexpected error: {:?}", error),
         }
 
-        // verify ask order removed from storage
+        // verify ask order kept as cancelled
         let ask_storage = get_ask_storage_read(&deps.storage);
-        assert!(ask_storage.load("ask_id".to_string().as_bytes()).is_err());
+        assert_eq!(
+            ask_storage.load("1".to_string().as_bytes()).unwrap().status,
+            OrderStatus::Cancelled
+        );
 
         // create bid data
         let bidder_info = mock_info("bidder", &coins(100, "quote_1"));
         let create_bid_msg = ExecuteMsg::CreateBid {
-            id: "bid_id".into(),
             base: vec![Coin {
                 denom: "base_1".into(),
                 amount: Uint128::new(200),
             }],
+            client_order_id: None,
             effective_time: Some(Timestamp::default()),
+            market: None,
+            take: None,
+            time_in_force: None,
         };
 
         // execute create bid
@@ -925,13 +2191,14 @@ mod tests {
 
         // verify bid order stored
         let bid_storage = get_bid_storage_read(&deps.storage);
-        assert!(bid_storage.load("bid_id".to_string().as_bytes()).is_ok(),);
+        assert!(bid_storage.load("2".to_string().as_bytes()).is_ok(),);
 
         // cancel bid order
         let bidder_info = mock_info("bidder", &[]);
 
         let cancel_bid_msg = ExecuteMsg::CancelBid {
-            id: "bid_id".to_string(),
+            id: "2".to_string(),
+            market: None,
         };
 
         let cancel_bid_response = execute(
@@ -943,10 +2210,18 @@ mod tests {
 
         match cancel_bid_response {
             Ok(cancel_bid_response) => {
-                assert_eq!(cancel_bid_response.attributes.len(), 1);
                 assert_eq!(
-                    cancel_bid_response.attributes[0],
-                    attr("action", "cancel_bid")
+                    cancel_bid_response.attributes,
+                    vec![
+                        attr("action", "cancel_bid"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "1571797419879305533"),
+                        attr("order_id", "2"),
+                        attr("side", "bid"),
+                        attr("owner", "bidder"),
+                        attr("base", "200base_1"),
+                        attr("quote", "100quote_1"),
+                    ]
                 );
                 assert_eq!(cancel_bid_response.messages.len(), 1);
                 assert_eq!(
@@ -960,9 +2235,12 @@ mod tests {
             Err(error) => panic!("unexpected error: {:?}", error),
         }
 
-        // verify bid order removed from storage
+        // verify bid order kept as cancelled
         let bid_storage = get_bid_storage_read(&deps.storage);
-        assert!(bid_storage.load("bid_id".to_string().as_bytes()).is_err());
+        assert_eq!(
+            bid_storage.load("2".to_string().as_bytes()).unwrap().status,
+            OrderStatus::Cancelled
+        );
     }
 
     #[test]
@@ -972,7 +2250,7 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         ) {
@@ -981,8 +2259,11 @@ mod tests {
 
         let asker_info = mock_info("asker", &[]);
 
-        // cancel ask order with missing id returns ContractError::Unauthorized
-        let cancel_ask_msg = ExecuteMsg::CancelAsk { id: "".to_string() };
+        // cancel ask order with missing id returns ContractError::InvalidId
+        let cancel_ask_msg = ExecuteMsg::CancelAsk {
+            id: "".to_string(),
+            market: None,
+        };
         let cancel_response = execute(
             deps.as_mut(),
             mock_env(),
@@ -992,7 +2273,7 @@ mod tests {
 
         match cancel_response {
             Err(error) => match error {
-                ContractError::Unauthorized {} => {}
+                ContractError::InvalidId { id } => assert_eq!(id, ""),
                 _ => {
                     panic!("unexpected error: {:?}", error)
                 }
@@ -1000,9 +2281,10 @@ mod tests {
             Ok(_) => panic!("expected error, but cancel_response ok"),
         }
 
-        // cancel non-existent ask order returns ContractError::Unauthorized
+        // cancel non-existent ask order returns ContractError::AskNotFound
         let cancel_ask_msg = ExecuteMsg::CancelAsk {
             id: "unknown_id".to_string(),
+            market: None,
         };
 
         let cancel_response = execute(
@@ -1014,7 +2296,7 @@ mod tests {
 
         match cancel_response {
             Err(error) => match error {
-                ContractError::Unauthorized {} => {}
+                ContractError::AskNotFound { id } => assert_eq!(id, "unknown_id"),
                 _ => {
                     panic!("unexpected error: {:?}", error)
                 }
@@ -1027,15 +2309,21 @@ mod tests {
             "ask_id".to_string().as_bytes(),
             &AskOrder {
                 base: coins(200, "base_1"),
+                client_order_id: None,
                 id: "ask_id".into(),
+                market: None,
                 owner: Addr::unchecked(""),
                 quote: coins(100, "quote_1"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
             },
         ) {
             panic!("unexpected error: {:?}", error)
         };
         let cancel_ask_msg = ExecuteMsg::CancelAsk {
             id: "ask_id".to_string(),
+            market: None,
         };
 
         let cancel_response = execute(deps.as_mut(), mock_env(), asker_info, cancel_ask_msg);
@@ -1054,6 +2342,7 @@ mod tests {
         let asker_info = mock_info("asker", &coins(1, "sent_coin"));
         let cancel_ask_msg = ExecuteMsg::CancelAsk {
             id: "ask_id".to_string(),
+            market: None,
         };
 
         let cancel_response = execute(deps.as_mut(), mock_env(), asker_info, cancel_ask_msg);
@@ -1077,7 +2366,7 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         ) {
@@ -1087,9 +2376,14 @@ mod tests {
         // store valid ask order
         let ask_order = AskOrder {
             base: vec![coin(100, "base_1"), coin(200, "base_2")],
+            client_order_id: None,
             id: "ask_id".into(),
+            market: None,
             owner: Addr::unchecked("asker"),
             quote: coins(200, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
         };
 
         let mut ask_storage = get_ask_storage(&mut deps.storage);
@@ -1100,10 +2394,15 @@ mod tests {
         // store valid bid order
         let bid_order = BidOrder {
             base: vec![coin(200, "base_2"), coin(100, "base_1")],
+            client_order_id: None,
             effective_time: Some(Timestamp::default()),
             id: "bid_id".to_string(),
+            market: None,
             owner: Addr::unchecked("bidder"),
             quote: coins(200, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
         };
 
         let mut bid_storage = get_bid_storage(&mut deps.storage);
@@ -1111,10 +2410,15 @@ mod tests {
             panic!("unexpected error: {:?}", error);
         };
 
+        // the escrow the stored orders hold
+        deposit_escrow(&mut deps.storage, &ask_order.base).unwrap();
+        deposit_escrow(&mut deps.storage, &bid_order.quote).unwrap();
+
         // execute on matched ask order and bid order
         let execute_msg = ExecuteMsg::ExecuteMatch {
             ask_id: ask_order.id,
             bid_id: bid_order.id,
+            market: None,
         };
 
         let execute_response = execute(
@@ -1128,8 +2432,20 @@ mod tests {
         match execute_response {
             Err(error) => panic!("unexpected error: {:?}", error),
             Ok(execute_response) => {
-                assert_eq!(execute_response.attributes.len(), 1);
-                assert_eq!(execute_response.attributes[0], attr("action", "execute"));
+                assert_eq!(
+                    execute_response.attributes,
+                    vec![
+                        attr("action", "execute"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "1571797419879305533"),
+                        attr("ask_id", "ask_id"),
+                        attr("bid_id", "bid_id"),
+                        attr("asker", "asker"),
+                        attr("bidder", "bidder"),
+                        attr("base", "200base_2,100base_1"),
+                        attr("quote", "200quote_1"),
+                    ]
+                );
                 assert_eq!(execute_response.messages.len(), 2);
                 assert_eq!(
                     execute_response.messages[0].msg,
@@ -1142,9 +2458,21 @@ mod tests {
                     execute_response.messages[1].msg,
                     CosmosMsg::Bank(BankMsg::Send {
                         to_address: bid_order.owner.to_string(),
-                        amount: bid_order.base,
+                        amount: bid_order.base.to_owned(),
                     })
                 );
+
+                // verify the trade receipt is returned and stored
+                let trade: Trade = from_binary(&execute_response.data.unwrap()).unwrap();
+                assert_eq!(trade.id, 1);
+                assert_eq!(trade.base, bid_order.base);
+                assert_eq!(trade.matcher, Addr::unchecked("contract_admin"));
+                assert_eq!(trade.ask.status, OrderStatus::Filled);
+                assert_eq!(trade.bid.status, OrderStatus::Filled);
+                assert_eq!(
+                    query(deps.as_ref(), mock_env(), QueryMsg::GetTrade { id: 1 }),
+                    to_binary(&trade)
+                );
             }
         }
     }
@@ -1157,7 +2485,7 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         ) {
@@ -1167,9 +2495,14 @@ mod tests {
         // store valid ask order
         let ask_order = AskOrder {
             base: coins(200, "base_1"),
+            client_order_id: None,
             id: "ask_id".into(),
+            market: None,
             owner: Addr::unchecked("asker"),
             quote: coins(100, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
         };
 
         let mut ask_storage = get_ask_storage(&mut deps.storage);
@@ -1180,10 +2513,15 @@ mod tests {
         // store valid bid order
         let bid_order = BidOrder {
             base: coins(100, "base_1"),
+            client_order_id: None,
             effective_time: Some(Timestamp::default()),
             id: "bid_id".into(),
+            market: None,
             owner: Addr::unchecked("bidder"),
             quote: coins(100, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
         };
 
         let mut bid_storage = get_bid_storage(&mut deps.storage);
@@ -1195,6 +2533,7 @@ mod tests {
         let execute_msg = ExecuteMsg::ExecuteMatch {
             ask_id: "ask_id".into(),
             bid_id: "bid_id".into(),
+            market: None,
         };
 
         let execute_response = execute(
@@ -1210,10 +2549,11 @@ mod tests {
             Ok(_) => panic!("expected error, but execute_response ok"),
         }
 
-        // execute on mismatched ask order and bid order returns ContractError::AskBidMismatch
+        // execute on mismatched ask order and bid order returns ContractError::BaseMismatch
         let execute_msg = ExecuteMsg::ExecuteMatch {
             ask_id: "ask_id".into(),
             bid_id: "bid_id".into(),
+            market: None,
         };
 
         let execute_response = execute(
@@ -1224,15 +2564,19 @@ mod tests {
         );
 
         match execute_response {
-            Err(ContractError::AskBidMismatch {}) => {}
+            Err(ContractError::BaseMismatch { ask, bid }) => {
+                assert_eq!(ask, "200base_1");
+                assert_eq!(bid, "100base_1");
+            }
             Err(error) => panic!("unexpected error: {:?}", error),
             Ok(_) => panic!("expected error, but execute_response ok"),
         }
 
-        // execute on non-existent ask order and bid order returns ContractError::AskBidMismatch
+        // execute on non-existent ask order returns ContractError::AskNotFound
         let execute_msg = ExecuteMsg::ExecuteMatch {
             ask_id: "no_ask_id".into(),
             bid_id: "bid_id".into(),
+            market: None,
         };
 
         let execute_response = execute(
@@ -1243,15 +2587,16 @@ mod tests {
         );
 
         match execute_response {
-            Err(ContractError::AskBidMismatch {}) => {}
+            Err(ContractError::AskNotFound { id }) => assert_eq!(id, "no_ask_id"),
             Err(error) => panic!("unexpected error: {:?}", error),
             Ok(_) => panic!("expected error, but execute_response ok"),
         }
 
-        // execute on non-existent ask order and bid order returns ContractError::AskBidMismatch
+        // execute on non-existent bid order returns ContractError::BidNotFound
         let execute_msg = ExecuteMsg::ExecuteMatch {
             ask_id: "ask_id".into(),
             bid_id: "no_bid_id".into(),
+            market: None,
         };
 
         let execute_response = execute(
@@ -1262,7 +2607,7 @@ mod tests {
         );
 
         match execute_response {
-            Err(ContractError::AskBidMismatch {}) => {}
+            Err(ContractError::BidNotFound { id }) => assert_eq!(id, "no_bid_id"),
             Err(error) => panic!("unexpected error: {:?}", error),
             Ok(_) => panic!("expected error, but execute_response ok"),
         }
@@ -1271,6 +2616,7 @@ mod tests {
         let execute_msg = ExecuteMsg::ExecuteMatch {
             ask_id: "ask_id".into(),
             bid_id: "bid_id".into(),
+            market: None,
         };
 
         let execute_response = execute(
@@ -1287,6 +2633,775 @@ mod tests {
         }
     }
 
+    #[test]
+    fn update_with_valid_data() {
+        let mut deps = mock_dependencies(&[]);
+
+        // store valid ask order
+        let ask_order = AskOrder {
+            base: coins(200, "base_1"),
+            client_order_id: None,
+            id: "ask_id".into(),
+            market: None,
+            owner: Addr::unchecked("asker"),
+            quote: coins(100, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
+        };
+        if let Err(error) = get_ask_storage(&mut deps.storage).save(b"ask_id", &ask_order) {
+            panic!("unexpected error: {:?}", error)
+        };
+
+        // the escrow the stored ask order holds
+        deposit_escrow(&mut deps.storage, &ask_order.base).unwrap();
+
+        // update quote and top up base with sent funds
+        let update_ask_response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[coin(50, "base_1"), coin(10, "base_2")]),
+            ExecuteMsg::UpdateAsk {
+                id: "ask_id".into(),
+                quote: Some(coins(150, "quote_1")),
+                base_refund: None,
+                market: None,
+            },
+        );
+
+        match update_ask_response {
+            Ok(response) => {
+                assert_eq!(response.messages.len(), 0);
+                assert_eq!(
+                    response.attributes,
+                    vec![
+                        attr("action", "update_ask"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "1571797419879305533"),
+                        attr("order_id", "ask_id"),
+                        attr("side", "ask"),
+                        attr("owner", "asker"),
+                        attr("base", "250base_1,10base_2"),
+                        attr("quote", "150quote_1"),
+                        attr("base_before", "200base_1"),
+                        attr("quote_before", "100quote_1"),
+                    ]
+                );
+            }
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+
+        // reduce base with an exact refund, keeping the same id
+        let update_ask_response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[]),
+            ExecuteMsg::UpdateAsk {
+                id: "ask_id".into(),
+                quote: None,
+                base_refund: Some(coins(10, "base_2")),
+                market: None,
+            },
+        );
+
+        match update_ask_response {
+            Ok(response) => {
+                assert_eq!(response.messages.len(), 1);
+                assert_eq!(
+                    response.messages[0].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "asker".into(),
+                        amount: coins(10, "base_2"),
+                    })
+                );
+            }
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+
+        assert_eq!(
+            get_ask_storage_read(&deps.storage).load(b"ask_id").unwrap(),
+            AskOrder {
+                base: coins(250, "base_1"),
+                quote: coins(150, "quote_1"),
+                ..ask_order
+            }
+        );
+
+        // store valid bid order
+        let bid_order = BidOrder {
+            base: coins(100, "base_1"),
+            client_order_id: None,
+            effective_time: Some(Timestamp::default()),
+            id: "bid_id".into(),
+            market: None,
+            owner: Addr::unchecked("bidder"),
+            quote: coins(100, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
+        };
+        if let Err(error) = get_bid_storage(&mut deps.storage).save(b"bid_id", &bid_order) {
+            panic!("unexpected error: {:?}", error)
+        };
+
+        // the escrow the stored bid order holds
+        deposit_escrow(&mut deps.storage, &bid_order.quote).unwrap();
+
+        // update base and reduce quote with an exact refund
+        let update_bid_response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bidder", &[]),
+            ExecuteMsg::UpdateBid {
+                id: "bid_id".into(),
+                base: Some(coins(90, "base_1")),
+                quote_refund: Some(coins(10, "quote_1")),
+                market: None,
+            },
+        );
+
+        match update_bid_response {
+            Ok(response) => {
+                assert_eq!(response.messages.len(), 1);
+                assert_eq!(
+                    response.messages[0].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "bidder".into(),
+                        amount: coins(10, "quote_1"),
+                    })
+                );
+                assert_eq!(
+                    response.attributes,
+                    vec![
+                        attr("action", "update_bid"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "1571797419879305533"),
+                        attr("order_id", "bid_id"),
+                        attr("side", "bid"),
+                        attr("owner", "bidder"),
+                        attr("base", "90base_1"),
+                        attr("quote", "90quote_1"),
+                        attr("base_before", "100base_1"),
+                        attr("quote_before", "100quote_1"),
+                    ]
+                );
+            }
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+
+        assert_eq!(
+            get_bid_storage_read(&deps.storage).load(b"bid_id").unwrap(),
+            BidOrder {
+                base: coins(90, "base_1"),
+                quote: coins(90, "quote_1"),
+                ..bid_order
+            }
+        );
+    }
+
+    #[test]
+    fn update_with_invalid_data() {
+        let mut deps = mock_dependencies(&[]);
+
+        // store valid ask order
+        if let Err(error) = get_ask_storage(&mut deps.storage).save(
+            b"ask_id",
+            &AskOrder {
+                base: coins(200, "base_1"),
+                client_order_id: None,
+                id: "ask_id".into(),
+                market: None,
+                owner: Addr::unchecked("asker"),
+                quote: coins(100, "quote_1"),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
+            },
+        ) {
+            panic!("unexpected error: {:?}", error)
+        };
+
+        // update ask by non-owner returns ContractError::Unauthorized
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user", &[]),
+            ExecuteMsg::UpdateAsk {
+                id: "ask_id".into(),
+                quote: Some(coins(150, "quote_1")),
+                base_refund: None,
+                market: None,
+            },
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // update ask with empty quote returns ContractError::MissingField { quote }
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[]),
+            ExecuteMsg::UpdateAsk {
+                id: "ask_id".into(),
+                quote: Some(vec![]),
+                base_refund: None,
+                market: None,
+            },
+        ) {
+            Err(ContractError::MissingField { field }) => assert_eq!(field, "quote"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // refunding more than escrowed returns ContractError::InvalidRefund
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[]),
+            ExecuteMsg::UpdateAsk {
+                id: "ask_id".into(),
+                quote: None,
+                base_refund: Some(coins(201, "base_1")),
+                market: None,
+            },
+        ) {
+            Err(ContractError::InvalidRefund {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // refunding the entire escrow returns ContractError::InvalidRefund
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[]),
+            ExecuteMsg::UpdateAsk {
+                id: "ask_id".into(),
+                quote: None,
+                base_refund: Some(coins(200, "base_1")),
+                market: None,
+            },
+        ) {
+            Err(ContractError::InvalidRefund {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // update non-existent bid returns ContractError::BidNotFound
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bidder", &[]),
+            ExecuteMsg::UpdateBid {
+                id: "unknown_id".into(),
+                base: Some(coins(90, "base_1")),
+                quote_refund: None,
+                market: None,
+            },
+        ) {
+            Err(ContractError::BidNotFound { id }) => assert_eq!(id, "unknown_id"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn simulate_match_reports_failures() {
+        let mut deps = mock_dependencies(&[]);
+        if let Err(error) = set_contract_info(
+            &mut deps.storage,
+            &ContractInfo::new(
+                Addr::unchecked("contract_admin"),
+                Some("contract_bind_name".into()),
+                "contract_name".into(),
+            ),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &coins(100, "base_1")),
+            ExecuteMsg::CreateAsk {
+                client_order_id: None,
+                quote: coins(50, "quote_1"),
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+        .unwrap();
+        let later = mock_env().block.time.plus_seconds(60);
+        for (client_order_id, base, quote, effective_time) in [
+            ("bid_id", coins(100, "base_1"), coins(50, "quote_1"), None),
+            (
+                "bid_base_amount",
+                coins(90, "base_1"),
+                coins(50, "quote_1"),
+                None,
+            ),
+            (
+                "bid_base_denom",
+                coins(100, "base_2"),
+                coins(50, "quote_1"),
+                None,
+            ),
+            (
+                "bid_quote_amount",
+                coins(100, "base_1"),
+                coins(40, "quote_1"),
+                None,
+            ),
+            (
+                "bid_quote_denom",
+                coins(100, "base_1"),
+                coins(50, "quote_2"),
+                None,
+            ),
+            (
+                "bid_later",
+                coins(100, "base_1"),
+                coins(50, "quote_1"),
+                Some(later),
+            ),
+        ] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("bidder", &quote),
+                ExecuteMsg::CreateBid {
+                    base,
+                    client_order_id: Some(client_order_id.into()),
+                    effective_time,
+                    market: None,
+                    take: None,
+                    time_in_force: None,
+                },
+            )
+            .unwrap();
+        }
+
+        let simulate_as =
+            |deps: Deps<ProvenanceQuery>, matcher: &str, ask_id: &str, bid_id: &str| {
+                from_binary::<SimulateMatchResponse>(
+                    &query(
+                        deps,
+                        mock_env(),
+                        QueryMsg::SimulateMatch {
+                            ask_id: ask_id.into(),
+                            bid_id: bid_id.into(),
+                            market: None,
+                            matcher: matcher.into(),
+                        },
+                    )
+                    .unwrap(),
+                )
+                .unwrap()
+            };
+        let simulate = |deps: Deps<ProvenanceQuery>, ask_id: &str, bid_id: &str| {
+            simulate_as(deps, "contract_admin", ask_id, bid_id)
+        };
+
+        assert_eq!(
+            simulate(deps.as_ref(), "1", "2"),
+            SimulateMatchResponse {
+                executable: true,
+                failure: None,
+                fees: vec![],
+                messages: vec![
+                    BankMsg::Send {
+                        to_address: "asker".into(),
+                        amount: coins(50, "quote_1"),
+                    },
+                    BankMsg::Send {
+                        to_address: "bidder".into(),
+                        amount: coins(100, "base_1"),
+                    },
+                ],
+            }
+        );
+
+        for (ask_id, bid_id, failure) in [
+            (
+                "unknown",
+                "2",
+                MatchFailure::AskNotFound {
+                    id: "unknown".into(),
+                },
+            ),
+            (
+                "1",
+                "unknown",
+                MatchFailure::BidNotFound {
+                    id: "unknown".into(),
+                },
+            ),
+            (
+                "1",
+                "3",
+                MatchFailure::BaseAmountMismatch {
+                    ask: coins(100, "base_1"),
+                    bid: coins(90, "base_1"),
+                },
+            ),
+            (
+                "1",
+                "4",
+                MatchFailure::BaseDenomMismatch {
+                    ask: coins(100, "base_1"),
+                    bid: coins(100, "base_2"),
+                },
+            ),
+            (
+                "1",
+                "5",
+                MatchFailure::QuoteAmountMismatch {
+                    ask: coins(50, "quote_1"),
+                    bid: coins(40, "quote_1"),
+                },
+            ),
+            (
+                "1",
+                "6",
+                MatchFailure::QuoteDenomMismatch {
+                    ask: coins(50, "quote_1"),
+                    bid: coins(50, "quote_2"),
+                },
+            ),
+            (
+                "1",
+                "7",
+                MatchFailure::BidNotEffective {
+                    effective_time: later,
+                },
+            ),
+        ] {
+            let simulation = simulate(deps.as_ref(), ask_id, bid_id);
+            assert!(!simulation.executable);
+            assert_eq!(simulation.failure, Some(failure));
+            assert_eq!(simulation.messages, vec![]);
+        }
+
+        // only the admin and matchers may match in the restricted default market
+        assert_eq!(
+            simulate_as(deps.as_ref(), "anyone", "1", "2").failure,
+            Some(MatchFailure::MatcherNotAllowed {
+                matcher: "anyone".into(),
+            })
+        );
+
+        // no one may match while trading is paused
+        let set_paused = |deps: DepsMut<ProvenanceQuery>, paused: bool| {
+            execute(
+                deps,
+                mock_env(),
+                mock_info("contract_admin", &[]),
+                ExecuteMsg::SetPaused { paused },
+            )
+            .unwrap();
+        };
+        set_paused(deps.as_mut(), true);
+        let simulation = simulate(deps.as_ref(), "1", "2");
+        assert!(!simulation.executable);
+        assert_eq!(simulation.failure, Some(MatchFailure::Paused {}));
+        set_paused(deps.as_mut(), false);
+
+        // the simulated failure is the error the match returns
+        let execute_response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("contract_admin", &[]),
+            ExecuteMsg::ExecuteMatch {
+                ask_id: "1".into(),
+                bid_id: "7".into(),
+                market: None,
+            },
+        );
+        match execute_response {
+            Err(ContractError::BidNotEffective { effective_time }) => {
+                assert_eq!(effective_time, later)
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("contract_admin", &[]),
+            ExecuteMsg::ExecuteMatch {
+                ask_id: "1".into(),
+                bid_id: "2".into(),
+                market: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            simulate(deps.as_ref(), "1", "5").failure,
+            Some(MatchFailure::AskClosed {
+                status: OrderStatus::Filled,
+            })
+        );
+    }
+
+    #[test]
+    fn prune_closed_orders() {
+        // setup
+        let mut deps = mock_dependencies(&[]);
+        if let Err(error) = set_contract_info(
+            &mut deps.storage,
+            &ContractInfo::new(
+                Addr::unchecked("contract_admin"),
+                Some("contract_bind_name".into()),
+                "contract_name".into(),
+            ),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+        // create and cancel an ask, leave a bid open
+        execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("asker", &coins(200, "base_1")),
+            ExecuteMsg::CreateAsk {
+                client_order_id: Some("client_ask_id".into()),
+                quote: coins(100, "quote_1"),
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("bidder", &coins(100, "quote_1")),
+            ExecuteMsg::CreateBid {
+                base: coins(200, "base_1"),
+                client_order_id: None,
+                effective_time: None,
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env_at(2_000),
+            mock_info("asker", &[]),
+            ExecuteMsg::CancelAsk {
+                id: "1".into(),
+                market: None,
+            },
+        )
+        .unwrap();
+
+        // the cancelled ask records its history and can no longer change
+        let ask_order = get_ask_storage_read(&deps.storage)
+            .load("1".as_bytes())
+            .unwrap();
+        assert_eq!(
+            ask_order.status_updates,
+            vec![
+                StatusUpdate {
+                    reason: None,
+                    status: OrderStatus::Open,
+                    time: Timestamp::from_seconds(1_000),
+                },
+                StatusUpdate {
+                    reason: None,
+                    status: OrderStatus::Cancelled,
+                    time: Timestamp::from_seconds(2_000),
+                },
+            ]
+        );
+        let cancel_response = execute(
+            deps.as_mut(),
+            env_at(2_000),
+            mock_info("asker", &[]),
+            ExecuteMsg::CancelAsk {
+                id: "1".into(),
+                market: None,
+            },
+        );
+        match cancel_response {
+            Err(ContractError::OrderClosed { status }) => assert_eq!(status, "cancelled"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // only the admin sets the retention period
+        let retention_response = execute(
+            deps.as_mut(),
+            env_at(2_000),
+            mock_info("asker", &[]),
+            ExecuteMsg::SetOrderRetention { seconds: 100 },
+        );
+        match retention_response {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        execute(
+            deps.as_mut(),
+            env_at(2_000),
+            mock_info("contract_admin", &[]),
+            ExecuteMsg::SetOrderRetention { seconds: 100 },
+        )
+        .unwrap();
+        assert_eq!(
+            query(deps.as_ref(), env_at(2_000), QueryMsg::GetOrderRetention {}),
+            to_binary(&100u64)
+        );
+
+        // closed orders are kept until the retention period has passed
+        execute(
+            deps.as_mut(),
+            env_at(2_100),
+            mock_info("anyone", &[]),
+            ExecuteMsg::PruneOrders { limit: None },
+        )
+        .unwrap();
+        assert!(get_ask_storage_read(&deps.storage)
+            .load("1".as_bytes())
+            .is_ok());
+
+        let prune_response = execute(
+            deps.as_mut(),
+            env_at(2_101),
+            mock_info("anyone", &[]),
+            ExecuteMsg::PruneOrders { limit: None },
+        )
+        .unwrap();
+        assert_eq!(
+            prune_response.attributes,
+            vec![
+                attr("action", "prune_orders"),
+                attr("block_height", "12345"),
+                attr("block_time", "2101000000000"),
+                attr("pruned", "1"),
+            ]
+        );
+        assert!(get_ask_storage_read(&deps.storage)
+            .load("1".as_bytes())
+            .is_err());
+        // the pruned ask's client order id no longer resolves
+        assert!(query(
+            deps.as_ref(),
+            env_at(2_101),
+            QueryMsg::GetAskByClientOrderId {
+                client_order_id: "client_ask_id".into(),
+                owner: "asker".into(),
+                market: None,
+            },
+        )
+        .is_err());
+        assert_eq!(
+            get_bid_storage_read(&deps.storage)
+                .load("2".as_bytes())
+                .unwrap()
+                .status,
+            OrderStatus::Open
+        );
+    }
+
+    #[test]
+    fn client_order_ids_are_unique_per_owner() {
+        let mut deps = mock_dependencies(&[]);
+        if let Err(error) = set_contract_info(
+            &mut deps.storage,
+            &ContractInfo::new(
+                Addr::unchecked("contract_admin"),
+                Some("contract_bind_name".into()),
+                "contract_name".into(),
+            ),
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+        let create_ask = |deps: DepsMut<ProvenanceQuery>, owner: &str| {
+            execute(
+                deps,
+                mock_env(),
+                mock_info(owner, &coins(100, "base_1")),
+                ExecuteMsg::CreateAsk {
+                    client_order_id: Some("client_id".into()),
+                    quote: coins(50, "quote_1"),
+                    market: None,
+                    take: None,
+                    time_in_force: None,
+                },
+            )
+        };
+
+        // the contract assigns ids in sequence and returns the order as data
+        let ask_order: AskOrder =
+            from_binary(&create_ask(deps.as_mut(), "asker").unwrap().data.unwrap()).unwrap();
+        assert_eq!(ask_order.id, "1");
+        assert_eq!(ask_order.client_order_id, Some("client_id".into()));
+
+        // resubmitting while the order is open is rejected, other owners and the bid side may use
+        // the same client order id
+        match create_ask(deps.as_mut(), "asker") {
+            Err(ContractError::ClientOrderIdExists {
+                client_order_id,
+                id,
+            }) => assert_eq!((client_order_id.as_str(), id.as_str()), ("client_id", "1")),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(create_ask(deps.as_mut(), "other_asker").is_ok());
+        assert!(execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &coins(50, "quote_1")),
+            ExecuteMsg::CreateBid {
+                base: coins(100, "base_1"),
+                client_order_id: Some("client_id".into()),
+                effective_time: None,
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+        .is_ok());
+
+        // lookups by either key return the same order
+        let by_client_order_id = |deps: Deps<ProvenanceQuery>| {
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::GetAskByClientOrderId {
+                    client_order_id: "client_id".into(),
+                    owner: "asker".into(),
+                    market: None,
+                },
+            )
+        };
+        assert_eq!(by_client_order_id(deps.as_ref()), to_binary(&ask_order));
+        assert_eq!(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetAsk {
+                    id: "1".into(),
+                    market: None
+                }
+            ),
+            to_binary(&ask_order)
+        );
+
+        // the client order id may be reused once its order has closed
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[]),
+            ExecuteMsg::CancelAsk {
+                id: "1".into(),
+                market: None,
+            },
+        )
+        .unwrap();
+        let ask_order: AskOrder =
+            from_binary(&create_ask(deps.as_mut(), "asker").unwrap().data.unwrap()).unwrap();
+        assert_eq!(ask_order.id, "4");
+        assert_eq!(by_client_order_id(deps.as_ref()), to_binary(&ask_order));
+    }
+
     #[test]
     pub fn query_with_valid_data() {
         // setup
@@ -1295,7 +3410,7 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         ) {
@@ -1305,9 +3420,14 @@ mod tests {
         // store valid ask order
         let ask_order = AskOrder {
             base: coins(200, "base_1"),
+            client_order_id: None,
             id: "ask_id".into(),
+            market: None,
             owner: Addr::unchecked("asker"),
             quote: coins(100, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
         };
 
         let mut ask_storage = get_ask_storage(&mut deps.storage);
@@ -1318,10 +3438,15 @@ mod tests {
         // store valid bid order
         let bid_order = BidOrder {
             base: coins(100, "base_1"),
+            client_order_id: None,
             effective_time: Some(Timestamp::default()),
             id: "bid_id".into(),
+            market: None,
             owner: Addr::unchecked("bidder"),
             quote: coins(100, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
         };
 
         let mut bid_storage = get_bid_storage(&mut deps.storage);
@@ -1349,6 +3474,7 @@ mod tests {
             mock_env(),
             QueryMsg::GetAsk {
                 id: ask_order.id.clone(),
+                market: None,
             },
         );
 
@@ -1360,6 +3486,7 @@ mod tests {
             mock_env(),
             QueryMsg::GetBid {
                 id: bid_order.id.clone(),
+                market: None,
             },
         );
 
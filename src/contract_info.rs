@@ -14,16 +14,21 @@ pub const CONTRACT_INFO: Item<ContractInfo> = Item::new(NAMESPACE_CONTRACT_INFO)
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ContractInfo {
     pub admin: Addr,
-    pub bind_name: String,
+    /// name bound at instantiation, if any
+    pub bind_name: Option<String>,
+    /// every name bound to the contract, including aliases bound by the admin
+    #[serde(default)]
+    pub bound_names: Vec<String>,
     pub contract_name: String,
     pub contract_type: String,
     pub contract_version: String,
 }
 
 impl ContractInfo {
-    pub fn new(admin: Addr, bind_name: String, contract_name: String) -> ContractInfo {
+    pub fn new(admin: Addr, bind_name: Option<String>, contract_name: String) -> ContractInfo {
         ContractInfo {
             admin,
+            bound_names: bind_name.iter().cloned().collect(),
             bind_name,
             contract_name,
             contract_type: CONTRACT_TYPE.into(),
@@ -60,7 +65,7 @@ mod tests {
             &mut deps.storage,
             &ContractInfo::new(
                 Addr::unchecked("contract_admin"),
-                "contract_bind_name".into(),
+                Some("contract_bind_name".into()),
                 "contract_name".into(),
             ),
         );
@@ -73,7 +78,8 @@ mod tests {
         match contract_info {
             Ok(contract_info) => {
                 assert_eq!(contract_info.admin, Addr::unchecked("contract_admin"));
-                assert_eq!(contract_info.bind_name, "contract_bind_name");
+                assert_eq!(contract_info.bind_name, Some("contract_bind_name".into()));
+                assert_eq!(contract_info.bound_names, vec!["contract_bind_name"]);
                 assert_eq!(contract_info.contract_name, "contract_name");
                 assert_eq!(contract_info.contract_type, CONTRACT_TYPE);
                 assert_eq!(contract_info.contract_version, CONTRACT_VERSION);
@@ -1,29 +1,223 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, StdError, Timestamp};
 use thiserror::Error;
 
+use crate::events::format_coins;
+use crate::msg::MatchFailure;
+
 #[derive(Error, Debug)]
 pub enum ContractError {
-    #[error("Ask Order does not match Bid Order")]
-    AskBidMismatch {},
+    #[error("Ask order {id:?} not found")]
+    AskNotFound { id: String },
+
+    #[error("Auction already exists")]
+    AuctionAlreadyExists {},
+
+    #[error("Auction bid is below the minimum of {minimum}")]
+    AuctionBidTooLow { minimum: Coin },
+
+    #[error("Auction has ended")]
+    AuctionClosed {},
+
+    #[error("Auction has not ended")]
+    AuctionOpen {},
+
+    #[error("Base mismatch: ask offers {ask:?}, bid wants {bid:?}")]
+    BaseMismatch { ask: String, bid: String },
+
+    #[error("Bid is not effective until {effective_time}")]
+    BidNotEffective { effective_time: Timestamp },
+
+    #[error("Bid order {id:?} not found")]
+    BidNotFound { id: String },
 
     #[error("Cannot send funds when canceling order")]
     CancelWithFunds {},
 
+    #[error("Client order id {client_order_id:?} is in use by open order {id:?}")]
+    ClientOrderIdExists { client_order_id: String, id: String },
+
+    #[error("No compliance address is set to hold restricted marker refunds")]
+    ComplianceAddressNotSet {},
+
+    #[error("Conditional order {id:?} not found")]
+    ConditionalOrderNotFound { id: String },
+
+    #[error("Denom {denom:?} is not traded on this venue")]
+    DenomNotAllowed { denom: String },
+
     #[error("Cannot send funds when executing match")]
     ExecuteWithFunds {},
 
+    #[error("Cannot send funds when finalizing auction")]
+    FinalizeWithFunds {},
+
+    #[error("HTLC already exists")]
+    HtlcAlreadyExists {},
+
+    #[error("HTLC is {status}")]
+    HtlcClosed { status: String },
+
+    #[error("HTLC has timed out")]
+    HtlcExpired {},
+
+    #[error("HTLC has not timed out")]
+    HtlcOpen {},
+
+    #[error("Operator balance does not cover the order's escrow")]
+    InsufficientOperatorBalance {},
+
+    #[error("Auction bid must be a single coin in the reserve price denom")]
+    InvalidAuctionBid {},
+
+    #[error("Conditional orders must trade a single base coin for a single quote coin")]
+    InvalidConditionalOrder {},
+
+    #[error("Invalid denom: {denom:?}")]
+    InvalidDenom { denom: String },
+
+    #[error("Invalid field: {field:?}")]
+    InvalidField { field: String },
+
+    #[error("Invalid id {id:?}, ids must be 1 to 64 ascii letters, digits, '.', '_' or '-'")]
+    InvalidId { id: String },
+
+    #[error("Order size is outside the trading pair's limits or not a multiple of its lot size")]
+    InvalidOrderSize {},
+
+    #[error("Preimage does not hash to the HTLC hash")]
+    InvalidPreimage {},
+
+    #[error("Order price is not a multiple of the trading pair's tick size")]
+    InvalidPrice {},
+
+    #[error("Refund exceeds escrowed funds or leaves the order empty")]
+    InvalidRefund {},
+
+    #[error("Market {id:?} already exists")]
+    MarketAlreadyExists { id: String },
+
+    #[error("Market {id:?} not found")]
+    MarketNotFound { id: String },
+
     #[error("Ask base was not sent")]
     MissingAskBase,
 
+    #[error("Auction base was not sent")]
+    MissingAuctionBase,
+
     #[error("Missing field: {field:?}")]
     MissingField { field: String },
 
     #[error("Bid quote was not sent")]
     MissingBidQuote,
 
+    #[error("Name {name:?} is already bound to the contract")]
+    NameAlreadyBound { name: String },
+
+    #[error("No balance in excess of escrowed funds")]
+    NoExcessBalance {},
+
+    #[error("No reference price for the pair, it has not traded and no price oracle is set")]
+    NoReferencePrice {},
+
+    #[error("Operator {operator:?} is not approved")]
+    OperatorNotFound { operator: String },
+
+    #[error("Operators cannot send funds, orders placed for an owner are escrowed from the operator balance")]
+    OperatorWithFunds {},
+
+    #[error("Order is {status}")]
+    OrderClosed { status: String },
+
+    #[error("Trading pair is disabled")]
+    PairDisabled {},
+
+    #[error("Orders must trade a single base coin for a single quote coin of a registered pair")]
+    PairNotRegistered {},
+
+    #[error("Trading is paused")]
+    Paused {},
+
+    #[error("Quote mismatch: ask wants {ask:?}, bid offers {bid:?}")]
+    QuoteMismatch { ask: String, bid: String },
+
+    #[error("Cannot send funds when revoking an operator")]
+    RevokeWithFunds {},
+
+    #[error("RFQ already exists")]
+    RfqAlreadyExists {},
+
+    #[error("RFQ quote base does not match the requested base")]
+    RfqBaseMismatch {},
+
+    #[error("RFQ has expired")]
+    RfqExpired {},
+
+    #[error("RFQ has not expired")]
+    RfqOpen {},
+
+    #[error("RFQ quote already exists")]
+    RfqQuoteAlreadyExists {},
+
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("Swap already exists")]
+    SwapAlreadyExists {},
+
+    #[error("Participant has already funded the swap")]
+    SwapAlreadyFunded {},
+
+    #[error("Swap is {status}")]
+    SwapClosed { status: String },
+
+    #[error("Funds sent do not match the coins the participant gives in the swap")]
+    SwapFundsMismatch {},
+
+    #[error("Legs sent do not match the legs of the swap")]
+    SwapTermsMismatch {},
+
+    #[error("Too many coins in {field:?}, at most {max} are allowed")]
+    TooManyCoins { field: String, max: usize },
+
+    #[error("At most {max} orders may be cancelled at once")]
+    TooManyOrders { max: u32 },
+
+    #[error("At most {max} quotes may be made on a request for quote")]
+    TooManyQuotes { max: usize },
+
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Coin {denom:?} in {field:?} has a zero amount")]
+    ZeroAmount { denom: String, field: String },
+}
+
+impl From<MatchFailure> for ContractError {
+    fn from(failure: MatchFailure) -> Self {
+        match failure {
+            MatchFailure::AskClosed { status } | MatchFailure::BidClosed { status } => {
+                ContractError::OrderClosed {
+                    status: status.to_string(),
+                }
+            }
+            MatchFailure::BidNotEffective { effective_time } => {
+                ContractError::BidNotEffective { effective_time }
+            }
+            MatchFailure::AskNotFound { id } => ContractError::AskNotFound { id },
+            MatchFailure::BidNotFound { id } => ContractError::BidNotFound { id },
+            MatchFailure::MatcherNotAllowed { .. } => ContractError::Unauthorized {},
+            MatchFailure::Paused {} => ContractError::Paused {},
+            MatchFailure::BaseAmountMismatch { ask, bid }
+            | MatchFailure::BaseDenomMismatch { ask, bid } => ContractError::BaseMismatch {
+                ask: format_coins(&ask),
+                bid: format_coins(&bid),
+            },
+            MatchFailure::QuoteAmountMismatch { ask, bid }
+            | MatchFailure::QuoteDenomMismatch { ask, bid } => ContractError::QuoteMismatch {
+                ask: format_coins(&ask),
+                bid: format_coins(&bid),
+            },
+        }
+    }
 }
@@ -0,0 +1,517 @@
+use cosmwasm_std::{
+    to_binary, BankMsg, Coin, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, Uint128,
+};
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+use std::collections::BTreeMap;
+
+use crate::contract_info::get_contract_info;
+use crate::error::ContractError;
+use crate::events::EventAttributes;
+use crate::msg::{EscrowDiscrepancy, SolvencyReport};
+use crate::state::ESCROW_TOTALS;
+use crate::validation::validate_denom;
+
+// add funds taken into escrow by an order, auction or quote to the ledger
+pub fn deposit_escrow(storage: &mut dyn Storage, coins: &[Coin]) -> StdResult<()> {
+    for coin in coins {
+        ESCROW_TOTALS.update(storage, &coin.denom, |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default().checked_add(coin.amount)?)
+        })?;
+    }
+    Ok(())
+}
+
+// remove funds paid out of escrow from the ledger, a release larger than the ledger total means
+// the ledger no longer matches the orders and is an error
+pub fn release_escrow(storage: &mut dyn Storage, coins: &[Coin]) -> StdResult<()> {
+    for coin in coins {
+        let total = ESCROW_TOTALS
+            .may_load(storage, &coin.denom)?
+            .unwrap_or_default()
+            .checked_sub(coin.amount)
+            .map_err(|_| {
+                StdError::generic_err(format!("escrow ledger underflow for {}", coin.denom))
+            })?;
+        if total.is_zero() {
+            ESCROW_TOTALS.remove(storage, &coin.denom);
+        } else {
+            ESCROW_TOTALS.save(storage, &coin.denom, &total)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn get_escrow_totals(storage: &dyn Storage) -> StdResult<Vec<Coin>> {
+    ESCROW_TOTALS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect()
+}
+
+// compare the escrow ledger with the contract's bank balances, admin only
+pub fn check_solvency(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if info.sender != get_contract_info(deps.storage)?.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !info.funds.is_empty() {
+        return Err(ContractError::ExecuteWithFunds {});
+    }
+
+    let balances = deps
+        .querier
+        .query_all_balances(env.contract.address.to_owned())?;
+    let report = solvency_report(&get_escrow_totals(deps.storage)?, &balances);
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("check_solvency", &env)
+                .add("solvent", report.solvent.to_string())
+                .add("discrepancies", report.discrepancies.len().to_string()),
+        )
+        .set_data(to_binary(&report)?))
+}
+
+// send a denom's balance above its escrowed total to an address, admin only
+pub fn sweep_excess(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    to: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if info.sender != get_contract_info(deps.storage)?.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !info.funds.is_empty() {
+        return Err(ContractError::ExecuteWithFunds {});
+    }
+    validate_denom(&denom)?;
+    let to = deps.api.addr_validate(&to)?;
+
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address.to_owned(), &denom)?;
+    let escrowed = ESCROW_TOTALS
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_default();
+    let excess = balance.amount.saturating_sub(escrowed);
+    if excess.is_zero() {
+        return Err(ContractError::NoExcessBalance {});
+    }
+
+    let amount = vec![Coin {
+        denom,
+        amount: excess,
+    }];
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: amount.to_owned(),
+        })
+        .add_attributes(
+            EventAttributes::new("sweep_excess", &env)
+                .add("denom", &amount[0].denom)
+                .add("to", to.as_str())
+                .add_coins("amount", &amount),
+        ))
+}
+
+// every denom whose balance differs from its ledger total, the contract is solvent while no
+// balance falls short of what it owes
+pub fn solvency_report(escrow_totals: &[Coin], balances: &[Coin]) -> SolvencyReport {
+    let mut amounts: BTreeMap<&str, (Uint128, Uint128)> = BTreeMap::new();
+    for coin in balances {
+        amounts.entry(&coin.denom).or_default().0 += coin.amount;
+    }
+    for coin in escrow_totals {
+        amounts.entry(&coin.denom).or_default().1 += coin.amount;
+    }
+
+    let discrepancies: Vec<EscrowDiscrepancy> = amounts
+        .into_iter()
+        .filter(|(_, (balance, escrowed))| balance != escrowed)
+        .map(|(denom, (balance, escrowed))| EscrowDiscrepancy {
+            balance,
+            denom: denom.into(),
+            escrowed,
+        })
+        .collect();
+
+    SolvencyReport {
+        solvent: discrepancies
+            .iter()
+            .all(|discrepancy| discrepancy.balance >= discrepancy.escrowed),
+        discrepancies,
+    }
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{attr, coin, coins, from_binary, CosmosMsg};
+    use provwasm_mocks::mock_dependencies;
+
+    use super::*;
+    use crate::contract::{execute, query};
+    use crate::msg::{ConfigMsg, ExecuteMsg, QueryMsg};
+    use crate::state::{get_ask_storage_read, get_bid_storage_read, AUCTIONS, ORDER_SEQUENCE};
+    use crate::testing::{instantiate_venue, Rng};
+
+    // an assigned order id of either side, or the next one which does not exist yet
+    fn random_order_id(rng: &mut Rng, last_id: u64) -> String {
+        (1 + rng.next(last_id + 1)).to_string()
+    }
+
+    fn random_coins(rng: &mut Rng, denom: &str) -> Vec<Coin> {
+        coins(1 + rng.next(2) as u128, denom)
+    }
+
+    fn maybe_random_coins(rng: &mut Rng, denom: &str) -> Option<Vec<Coin>> {
+        match rng.next(3) {
+            0 => None,
+            _ => Some(random_coins(rng, denom)),
+        }
+    }
+
+    // funds held by open orders and auctions, which the ledger must always equal
+    fn owed(storage: &dyn Storage) -> Vec<Coin> {
+        let mut owed: BTreeMap<String, Uint128> = BTreeMap::new();
+        let mut add = |coins: &[Coin]| {
+            for coin in coins {
+                *owed.entry(coin.denom.to_owned()).or_default() += coin.amount;
+            }
+        };
+        for item in get_ask_storage_read(storage).range(None, None, Order::Ascending) {
+            let (_, ask_order) = item.unwrap();
+            if !ask_order.status.is_closed() {
+                add(&ask_order.base);
+            }
+        }
+        for item in get_bid_storage_read(storage).range(None, None, Order::Ascending) {
+            let (_, bid_order) = item.unwrap();
+            if !bid_order.status.is_closed() {
+                add(&bid_order.quote);
+            }
+        }
+        for item in AUCTIONS.range(storage, None, None, Order::Ascending) {
+            let (_, auction) = item.unwrap();
+            add(&auction.base);
+            if let Some(high_bid) = auction.high_bid {
+                add(&[high_bid.quote]);
+            }
+        }
+        owed.into_iter()
+            .map(|(denom, amount)| Coin { denom, amount })
+            .collect()
+    }
+
+    #[test]
+    fn random_operations_keep_ledger_consistent() {
+        let client_order_ids = ["client_1", "client_2"];
+        let owners = ["owner_1", "owner_2"];
+
+        for seed in 1..=25 {
+            let mut rng = Rng(seed);
+            let mut deps = mock_dependencies(&[]);
+            instantiate_venue(deps.as_mut(), ConfigMsg::default());
+
+            // the contract's bank balance, moved by accepted funds and bank sends
+            let mut bank: BTreeMap<String, Uint128> = BTreeMap::new();
+            let mut env = mock_env();
+
+            for _ in 0..200 {
+                env.block.time = env.block.time.plus_seconds(rng.next(30));
+                let owner = rng.pick(&owners);
+                let last_id = ORDER_SEQUENCE
+                    .may_load(&deps.storage)
+                    .unwrap()
+                    .unwrap_or_default();
+                let (sender, funds, msg) = match rng.next(10) {
+                    0 => (
+                        owner,
+                        random_coins(&mut rng, "base_1"),
+                        ExecuteMsg::CreateAsk {
+                            client_order_id: match rng.next(2) {
+                                0 => None,
+                                _ => Some(rng.pick(&client_order_ids).into()),
+                            },
+                            quote: random_coins(&mut rng, "quote_1"),
+                            market: None,
+                            take: None,
+                            time_in_force: None,
+                        },
+                    ),
+                    1 => (
+                        owner,
+                        random_coins(&mut rng, "quote_1"),
+                        ExecuteMsg::CreateBid {
+                            base: random_coins(&mut rng, "base_1"),
+                            client_order_id: match rng.next(2) {
+                                0 => None,
+                                _ => Some(rng.pick(&client_order_ids).into()),
+                            },
+                            effective_time: None,
+                            market: None,
+                            take: None,
+                            time_in_force: None,
+                        },
+                    ),
+                    2 => (
+                        owner,
+                        vec![],
+                        ExecuteMsg::CancelAsk {
+                            id: random_order_id(&mut rng, last_id),
+                            market: None,
+                        },
+                    ),
+                    3 => (
+                        owner,
+                        vec![],
+                        ExecuteMsg::CancelBid {
+                            id: random_order_id(&mut rng, last_id),
+                            market: None,
+                        },
+                    ),
+                    4 => (
+                        owner,
+                        maybe_random_coins(&mut rng, "base_1").unwrap_or_default(),
+                        ExecuteMsg::UpdateAsk {
+                            id: random_order_id(&mut rng, last_id),
+                            quote: maybe_random_coins(&mut rng, "quote_1"),
+                            base_refund: maybe_random_coins(&mut rng, "base_1"),
+                            market: None,
+                        },
+                    ),
+                    5 => (
+                        owner,
+                        maybe_random_coins(&mut rng, "quote_1").unwrap_or_default(),
+                        ExecuteMsg::UpdateBid {
+                            id: random_order_id(&mut rng, last_id),
+                            base: maybe_random_coins(&mut rng, "base_1"),
+                            quote_refund: maybe_random_coins(&mut rng, "quote_1"),
+                            market: None,
+                        },
+                    ),
+                    6 | 7 => (
+                        "venue_admin",
+                        vec![],
+                        ExecuteMsg::ExecuteMatch {
+                            ask_id: random_order_id(&mut rng, last_id),
+                            bid_id: random_order_id(&mut rng, last_id),
+                            market: None,
+                        },
+                    ),
+                    8 => (
+                        owner,
+                        random_coins(&mut rng, "base_2"),
+                        ExecuteMsg::CreateAuction {
+                            id: "auction".into(),
+                            end_time: env.block.time.plus_seconds(1 + rng.next(60)),
+                            min_increment: Uint128::new(1),
+                            reserve_price: coin(1, "quote_1"),
+                            time_extension: None,
+                        },
+                    ),
+                    _ => match rng.next(2) {
+                        0 => (
+                            owner,
+                            random_coins(&mut rng, "quote_1"),
+                            ExecuteMsg::BidAuction {
+                                id: "auction".into(),
+                            },
+                        ),
+                        _ => (
+                            owner,
+                            vec![],
+                            ExecuteMsg::FinalizeAuction {
+                                id: "auction".into(),
+                            },
+                        ),
+                    },
+                };
+
+                // failed transactions are reverted by the chain, so restore storage on error
+                let snapshot: Vec<(Vec<u8>, Vec<u8>)> =
+                    deps.storage.range(None, None, Order::Ascending).collect();
+                match execute(deps.as_mut(), env.clone(), mock_info(sender, &funds), msg) {
+                    Ok(response) => {
+                        for coin in &funds {
+                            *bank.entry(coin.denom.to_owned()).or_default() += coin.amount;
+                        }
+                        for message in response.messages {
+                            if let CosmosMsg::Bank(BankMsg::Send { amount, .. }) = message.msg {
+                                for coin in amount {
+                                    let balance = bank.entry(coin.denom).or_default();
+                                    *balance = balance.checked_sub(coin.amount).unwrap();
+                                }
+                            }
+                        }
+                    }
+                    Err(_) => {
+                        let keys: Vec<Vec<u8>> = deps
+                            .storage
+                            .range(None, None, Order::Ascending)
+                            .map(|(key, _)| key)
+                            .collect();
+                        for key in keys {
+                            deps.storage.remove(&key);
+                        }
+                        for (key, value) in snapshot {
+                            deps.storage.set(&key, &value);
+                        }
+                    }
+                }
+
+                let escrow_totals = get_escrow_totals(&deps.storage).unwrap();
+                let balances: Vec<Coin> = bank
+                    .iter()
+                    .filter(|(_, amount)| !amount.is_zero())
+                    .map(|(denom, amount)| coin(amount.u128(), denom))
+                    .collect();
+                assert_eq!(escrow_totals, balances, "seed {}", seed);
+                assert_eq!(escrow_totals, owed(&deps.storage), "seed {}", seed);
+            }
+
+            // the ledger reconciles with the bank balance reported to the contract
+            let balances: Vec<Coin> = get_escrow_totals(&deps.storage).unwrap();
+            deps.querier
+                .base
+                .update_balance(MOCK_CONTRACT_ADDR, balances);
+            let response = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("venue_admin", &[]),
+                ExecuteMsg::CheckSolvency {},
+            )
+            .unwrap();
+            assert_eq!(
+                from_binary::<SolvencyReport>(&response.data.unwrap()).unwrap(),
+                SolvencyReport {
+                    discrepancies: vec![],
+                    solvent: true,
+                }
+            );
+            assert_eq!(
+                query(deps.as_ref(), env.clone(), QueryMsg::GetEscrowTotals {}),
+                to_binary(&get_escrow_totals(&deps.storage).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn check_solvency_reports_discrepancies() {
+        let mut deps = mock_dependencies(&coins(100, "base_1"));
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        deposit_escrow(
+            &mut deps.storage,
+            &[coin(50, "base_1"), coin(10, "quote_1")],
+        )
+        .unwrap();
+
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[]),
+            ExecuteMsg::CheckSolvency {},
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            ExecuteMsg::CheckSolvency {},
+        )
+        .unwrap();
+
+        // excess base is a discrepancy but only the missing quote makes the contract insolvent
+        assert_eq!(
+            from_binary::<SolvencyReport>(&response.data.unwrap()).unwrap(),
+            SolvencyReport {
+                discrepancies: vec![
+                    EscrowDiscrepancy {
+                        balance: Uint128::new(100),
+                        denom: "base_1".into(),
+                        escrowed: Uint128::new(50),
+                    },
+                    EscrowDiscrepancy {
+                        balance: Uint128::zero(),
+                        denom: "quote_1".into(),
+                        escrowed: Uint128::new(10),
+                    },
+                ],
+                solvent: false,
+            }
+        );
+
+        // releasing more than the ledger holds is refused
+        match release_escrow(&mut deps.storage, &coins(11, "quote_1")) {
+            Err(StdError::GenericErr { .. }) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        release_escrow(&mut deps.storage, &coins(10, "quote_1")).unwrap();
+        assert_eq!(
+            get_escrow_totals(&deps.storage).unwrap(),
+            coins(50, "base_1")
+        );
+    }
+
+    #[test]
+    fn sweep_excess_leaves_escrowed_funds() {
+        let mut deps = mock_dependencies(&[coin(100, "base_1"), coin(10, "quote_1")]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        deposit_escrow(
+            &mut deps.storage,
+            &[coin(60, "base_1"), coin(10, "quote_1")],
+        )
+        .unwrap();
+
+        let mut sweep = |sender: &str, denom: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::SweepExcess {
+                    denom: denom.into(),
+                    to: "treasury".into(),
+                },
+            )
+        };
+
+        match sweep("asker", "base_1") {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // only the balance above the escrowed total is sent
+        let response = sweep("venue_admin", "base_1").unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "treasury".into(),
+                amount: coins(40, "base_1"),
+            })
+        );
+        assert_eq!(
+            response.attributes.last(),
+            Some(&attr("amount", "40base_1"))
+        );
+
+        // fully escrowed and unknown denoms have nothing to sweep
+        for denom in ["quote_1", "unknown"] {
+            match sweep("venue_admin", denom) {
+                Err(ContractError::NoExcessBalance {}) => {}
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+    }
+}
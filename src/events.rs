@@ -0,0 +1,272 @@
+use cosmwasm_std::{attr, Addr, Attribute, Coin, Env};
+
+use crate::state::{AskOrder, BidOrder, ConditionalOrder, OrderStatus, Side, TimeInForce, Trade};
+
+// attribute keys of the `wasm` event, see the Events section of the README before changing
+pub const ACTION_KEY: &str = "action";
+pub const ASK_ID_KEY: &str = "ask_id";
+pub const ASKER_KEY: &str = "asker";
+pub const BASE_KEY: &str = "base";
+pub const BASE_BEFORE_KEY: &str = "base_before";
+pub const BID_ID_KEY: &str = "bid_id";
+pub const BIDDER_KEY: &str = "bidder";
+pub const BLOCK_HEIGHT_KEY: &str = "block_height";
+pub const BLOCK_TIME_KEY: &str = "block_time";
+pub const CLIENT_ORDER_ID_KEY: &str = "client_order_id";
+pub const EXPIRES_KEY: &str = "expires";
+pub const FEES_KEY: &str = "fees";
+pub const HTLC_ID_KEY: &str = "htlc_id";
+pub const MARKET_KEY: &str = "market";
+pub const OPERATOR_KEY: &str = "operator";
+pub const ORDER_ID_KEY: &str = "order_id";
+pub const OWNER_KEY: &str = "owner";
+pub const QUOTE_KEY: &str = "quote";
+pub const QUOTE_BEFORE_KEY: &str = "quote_before";
+pub const RFQ_ID_KEY: &str = "rfq_id";
+pub const SIDE_KEY: &str = "side";
+pub const STATUS_KEY: &str = "status";
+pub const SWAP_ID_KEY: &str = "swap_id";
+pub const TAKE_KEY: &str = "take";
+pub const TIME_IN_FORCE_KEY: &str = "time_in_force";
+pub const TRADE_ID_KEY: &str = "trade_id";
+pub const TRIGGER_CONDITION_KEY: &str = "trigger_condition";
+pub const TRIGGER_PRICE_KEY: &str = "trigger_price";
+
+// builds the attributes of an action's `wasm` event in a fixed key order
+pub struct EventAttributes(Vec<Attribute>);
+
+impl EventAttributes {
+    pub fn new(action: &str, env: &Env) -> EventAttributes {
+        EventAttributes(vec![
+            attr(ACTION_KEY, action),
+            attr(BLOCK_HEIGHT_KEY, env.block.height.to_string()),
+            attr(BLOCK_TIME_KEY, env.block.time.nanos().to_string()),
+        ])
+    }
+
+    pub fn add(mut self, key: &str, value: impl Into<String>) -> EventAttributes {
+        self.0.push(attr(key, value));
+        self
+    }
+
+    pub fn add_coins(self, key: &str, coins: &[Coin]) -> EventAttributes {
+        self.add(key, format_coins(coins))
+    }
+
+    pub fn order(
+        self,
+        side: Side,
+        order_id: &str,
+        owner: &str,
+        base: &[Coin],
+        quote: &[Coin],
+    ) -> EventAttributes {
+        self.add(ORDER_ID_KEY, order_id)
+            .add(SIDE_KEY, side.to_string())
+            .add(OWNER_KEY, owner)
+            .add_coins(BASE_KEY, base)
+            .add_coins(QUOTE_KEY, quote)
+    }
+
+    // the client order id is only added when the owner supplied one
+    pub fn client_order_id(self, client_order_id: Option<&str>) -> EventAttributes {
+        match client_order_id {
+            Some(client_order_id) => self.add(CLIENT_ORDER_ID_KEY, client_order_id),
+            None => self,
+        }
+    }
+
+    // the market is only added for orders outside the default market
+    pub fn market(self, market: Option<&str>) -> EventAttributes {
+        match market {
+            Some(market) => self.add(MARKET_KEY, market),
+            None => self,
+        }
+    }
+
+    // the operator is only added when an approved operator acted for the owner
+    pub fn operator(self, owner: &Addr, sender: &Addr) -> EventAttributes {
+        if sender == owner {
+            self
+        } else {
+            self.add(OPERATOR_KEY, sender.as_str())
+        }
+    }
+
+    // the time in force is only added for orders that are not good till cancelled
+    pub fn time_in_force(self, time_in_force: TimeInForce) -> EventAttributes {
+        match time_in_force {
+            TimeInForce::Gtc => self,
+            TimeInForce::Gtt { expires } => self
+                .add(TIME_IN_FORCE_KEY, time_in_force.to_string())
+                .add(EXPIRES_KEY, expires.nanos().to_string()),
+            _ => self.add(TIME_IN_FORCE_KEY, time_in_force.to_string()),
+        }
+    }
+
+    // the resting order a new order took, the new order's status after the take and the trade
+    // when it filled
+    pub fn taken(self, take: &str, status: OrderStatus, trade: Option<&Trade>) -> EventAttributes {
+        let attributes = self.add(TAKE_KEY, take).add(STATUS_KEY, status.to_string());
+        match trade {
+            Some(trade) if !trade.fees.is_empty() => attributes
+                .add(TRADE_ID_KEY, trade.id.to_string())
+                .add_coins(FEES_KEY, &trade.fees),
+            Some(trade) => attributes.add(TRADE_ID_KEY, trade.id.to_string()),
+            None => attributes,
+        }
+    }
+
+    pub fn ask(self, ask_order: &AskOrder) -> EventAttributes {
+        self.order(
+            Side::Ask,
+            &ask_order.id,
+            ask_order.owner.as_str(),
+            &ask_order.base,
+            &ask_order.quote,
+        )
+        .client_order_id(ask_order.client_order_id.as_deref())
+        .market(ask_order.market.as_deref())
+        .time_in_force(ask_order.time_in_force)
+    }
+
+    pub fn bid(self, bid_order: &BidOrder) -> EventAttributes {
+        self.order(
+            Side::Bid,
+            &bid_order.id,
+            bid_order.owner.as_str(),
+            &bid_order.base,
+            &bid_order.quote,
+        )
+        .client_order_id(bid_order.client_order_id.as_deref())
+        .market(bid_order.market.as_deref())
+        .time_in_force(bid_order.time_in_force)
+    }
+
+    // a conditional order and the reference price that triggers it
+    pub fn conditional_order(self, conditional_order: &ConditionalOrder) -> EventAttributes {
+        self.order(
+            conditional_order.side,
+            &conditional_order.id,
+            conditional_order.owner.as_str(),
+            &conditional_order.base,
+            &conditional_order.quote,
+        )
+        .client_order_id(conditional_order.client_order_id.as_deref())
+        .market(conditional_order.market.as_deref())
+        .add(
+            TRIGGER_CONDITION_KEY,
+            conditional_order.trigger.condition.to_string(),
+        )
+        .add(
+            TRIGGER_PRICE_KEY,
+            conditional_order.trigger.price.to_string(),
+        )
+    }
+
+    // the settled amounts and both counterparties of a match
+    pub fn matched(self, ask_order: &AskOrder, bid_order: &BidOrder) -> EventAttributes {
+        self.add(ASK_ID_KEY, &ask_order.id)
+            .add(BID_ID_KEY, &bid_order.id)
+            .add(ASKER_KEY, ask_order.owner.as_str())
+            .add(BIDDER_KEY, bid_order.owner.as_str())
+            .add_coins(BASE_KEY, &bid_order.base)
+            .add_coins(QUOTE_KEY, &ask_order.quote)
+            .market(ask_order.market.as_deref())
+    }
+}
+
+impl IntoIterator for EventAttributes {
+    type Item = Attribute;
+    type IntoIter = std::vec::IntoIter<Attribute>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+// format a coin vector as a comma separated list of coin strings, ie. "100base_1,200base_2"
+pub fn format_coins(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coin, coins, Timestamp};
+
+    use super::*;
+    use crate::state::OrderStatus;
+
+    #[test]
+    fn event_attributes_for_orders() {
+        let mut env = mock_env();
+        env.block.height = 42;
+        env.block.time = Timestamp::from_nanos(1_000_000_001);
+
+        let ask_order = AskOrder {
+            base: vec![coin(100, "base_1"), coin(200, "base_2")],
+            client_order_id: None,
+            id: "ask_id".into(),
+            market: None,
+            owner: Addr::unchecked("asker"),
+            quote: coins(300, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
+        };
+
+        assert_eq!(
+            EventAttributes::new("create_ask", &env)
+                .ask(&ask_order)
+                .into_iter()
+                .collect::<Vec<Attribute>>(),
+            vec![
+                attr("action", "create_ask"),
+                attr("block_height", "42"),
+                attr("block_time", "1000000001"),
+                attr("order_id", "ask_id"),
+                attr("side", "ask"),
+                attr("owner", "asker"),
+                attr("base", "100base_1,200base_2"),
+                attr("quote", "300quote_1"),
+            ]
+        );
+
+        let bid_order = BidOrder {
+            base: vec![coin(200, "base_2"), coin(100, "base_1")],
+            client_order_id: None,
+            effective_time: None,
+            id: "bid_id".into(),
+            market: None,
+            owner: Addr::unchecked("bidder"),
+            quote: coins(300, "quote_1"),
+            status: OrderStatus::Open,
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
+        };
+
+        assert_eq!(
+            EventAttributes::new("execute", &env)
+                .matched(&ask_order, &bid_order)
+                .into_iter()
+                .collect::<Vec<Attribute>>(),
+            vec![
+                attr("action", "execute"),
+                attr("block_height", "42"),
+                attr("block_time", "1000000001"),
+                attr("ask_id", "ask_id"),
+                attr("bid_id", "bid_id"),
+                attr("asker", "asker"),
+                attr("bidder", "bidder"),
+                attr("base", "200base_2,100base_1"),
+                attr("quote", "300quote_1"),
+            ]
+        );
+    }
+}
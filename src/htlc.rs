@@ -0,0 +1,365 @@
+use cosmwasm_std::{
+    to_binary, BankMsg, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage, Timestamp,
+};
+use cw_storage_plus::Bound;
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+use sha2::{Digest, Sha256};
+
+use crate::config::check_allowed_denoms;
+use crate::error::ContractError;
+use crate::escrow::{deposit_escrow, release_escrow};
+use crate::events::{EventAttributes, BASE_KEY, HTLC_ID_KEY, OWNER_KEY};
+use crate::state::{Htlc, HtlcStatus, HTLCS};
+use crate::validation::{normalize_coins, validate_id};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// lock the funds sent in an ask that the counterparty may claim with the preimage of the hash until
+// the timeout, and that is refunded to the sender after
+pub fn create_htlc(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    counterparty: String,
+    hash: String,
+    timeout: Timestamp,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&id)?;
+    if HTLCS.has(deps.storage, &id) {
+        return Err(ContractError::HtlcAlreadyExists {});
+    }
+    if info.funds.is_empty() {
+        return Err(ContractError::MissingAskBase);
+    }
+    let counterparty = deps.api.addr_validate(&counterparty)?;
+    // a sha256 is 32 bytes, 64 hex digits
+    let hash = hash.to_lowercase();
+    if hex::decode(&hash).map(|bytes| bytes.len()) != Ok(32) {
+        return Err(ContractError::InvalidField {
+            field: "hash".into(),
+        });
+    }
+    if timeout <= env.block.time {
+        return Err(ContractError::InvalidField {
+            field: "timeout".into(),
+        });
+    }
+    let base = normalize_coins(info.funds, "base")?;
+    check_allowed_denoms(deps.storage, &base)?;
+
+    let htlc = Htlc {
+        base,
+        counterparty,
+        hash,
+        id,
+        owner: info.sender,
+        preimage: None,
+        status: HtlcStatus::Locked,
+        timeout,
+    };
+    HTLCS.save(deps.storage, &htlc.id, &htlc)?;
+    deposit_escrow(deps.storage, &htlc.base)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("create_htlc", &env)
+                .add(HTLC_ID_KEY, &htlc.id)
+                .add(OWNER_KEY, htlc.owner.as_str())
+                .add("counterparty", htlc.counterparty.as_str())
+                .add_coins(BASE_KEY, &htlc.base)
+                .add("hash", &htlc.hash)
+                .add("timeout", htlc.timeout.nanos().to_string()),
+        )
+        .set_data(to_binary(&htlc)?))
+}
+
+// release a locked ask to its counterparty for the preimage of its hash before the timeout. The
+// preimage is kept and emitted so the owner can claim the other leg of a cross-chain swap with it.
+pub fn claim_htlc(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    preimage: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::ExecuteWithFunds {});
+    }
+
+    let mut htlc = load_locked_htlc(deps.storage, &id)?;
+    if env.block.time >= htlc.timeout {
+        return Err(ContractError::HtlcExpired {});
+    }
+    let preimage_bytes = hex::decode(&preimage).map_err(|_| ContractError::InvalidField {
+        field: "preimage".into(),
+    })?;
+    if hex::encode(Sha256::digest(&preimage_bytes)) != htlc.hash {
+        return Err(ContractError::InvalidPreimage {});
+    }
+
+    htlc.preimage = Some(preimage.to_lowercase());
+    htlc.status = HtlcStatus::Claimed;
+    close_htlc(deps.storage, &htlc)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("claim_htlc", &env)
+                .add(HTLC_ID_KEY, &htlc.id)
+                .add("counterparty", htlc.counterparty.as_str())
+                .add_coins(BASE_KEY, &htlc.base)
+                .add("preimage", htlc.preimage.as_deref().unwrap_or_default()),
+        )
+        .add_message(BankMsg::Send {
+            to_address: htlc.counterparty.to_string(),
+            amount: htlc.base.to_owned(),
+        })
+        .set_data(to_binary(&htlc)?))
+}
+
+// return a locked ask to its owner once it has timed out unclaimed
+pub fn refund_htlc(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::CancelWithFunds {});
+    }
+
+    let mut htlc = load_locked_htlc(deps.storage, &id)?;
+    if env.block.time < htlc.timeout {
+        return Err(ContractError::HtlcOpen {});
+    }
+
+    htlc.status = HtlcStatus::Refunded;
+    close_htlc(deps.storage, &htlc)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("refund_htlc", &env)
+                .add(HTLC_ID_KEY, &htlc.id)
+                .add(OWNER_KEY, htlc.owner.as_str())
+                .add_coins(BASE_KEY, &htlc.base),
+        )
+        .add_message(BankMsg::Send {
+            to_address: htlc.owner.to_string(),
+            amount: htlc.base.to_owned(),
+        })
+        .set_data(to_binary(&htlc)?))
+}
+
+pub fn get_htlcs(
+    storage: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Htlc>> {
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    HTLCS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .map(|item| item.map(|(_, htlc)| htlc))
+        .collect()
+}
+
+// return error unless the ask is still locked
+fn load_locked_htlc(storage: &dyn Storage, id: &str) -> Result<Htlc, ContractError> {
+    validate_id(id)?;
+    let htlc = HTLCS.load(storage, id)?;
+    if htlc.status != HtlcStatus::Locked {
+        return Err(ContractError::HtlcClosed {
+            status: htlc.status.to_string(),
+        });
+    }
+    Ok(htlc)
+}
+
+// save a claimed or refunded ask and release its escrow, the caller sends the base
+fn close_htlc(storage: &mut dyn Storage, htlc: &Htlc) -> Result<(), ContractError> {
+    HTLCS.save(storage, &htlc.id, htlc)?;
+    release_escrow(storage, &htlc.base)?;
+    Ok(())
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_info;
+    use cosmwasm_std::{coins, from_binary, CosmosMsg};
+    use provwasm_mocks::mock_dependencies;
+
+    use super::*;
+    use crate::contract::{execute, query};
+    use crate::escrow::get_escrow_totals;
+    use crate::msg::{ExecuteMsg, QueryMsg};
+    use crate::testing::env_at;
+
+    const PREIMAGE: &str = "736563726574";
+
+    // lock 100 base_1 for the counterparty until 1000s
+    fn create(deps: DepsMut<ProvenanceQuery>) {
+        execute(
+            deps,
+            env_at(100),
+            mock_info("owner", &coins(100, "base_1")),
+            ExecuteMsg::CreateHtlc {
+                id: "htlc_1".into(),
+                counterparty: "counterparty".into(),
+                hash: hex::encode(Sha256::digest(&hex::decode(PREIMAGE).unwrap())),
+                timeout: Timestamp::from_seconds(1_000),
+            },
+        )
+        .unwrap();
+    }
+
+    fn claim(
+        deps: DepsMut<ProvenanceQuery>,
+        seconds: u64,
+        preimage: &str,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            env_at(seconds),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ClaimHtlc {
+                id: "htlc_1".into(),
+                preimage: preimage.into(),
+            },
+        )
+    }
+
+    fn refund(
+        deps: DepsMut<ProvenanceQuery>,
+        seconds: u64,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            env_at(seconds),
+            mock_info("anyone", &[]),
+            ExecuteMsg::RefundHtlc {
+                id: "htlc_1".into(),
+            },
+        )
+    }
+
+    #[test]
+    fn claim_with_preimage_before_timeout() {
+        let mut deps = mock_dependencies(&[]);
+        create(deps.as_mut());
+        assert_eq!(
+            get_escrow_totals(&deps.storage).unwrap(),
+            coins(100, "base_1")
+        );
+
+        match claim(deps.as_mut(), 500, "00") {
+            Err(ContractError::InvalidPreimage {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match refund(deps.as_mut(), 500) {
+            Err(ContractError::HtlcOpen {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let response = claim(deps.as_mut(), 500, PREIMAGE).unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "counterparty".into(),
+                amount: coins(100, "base_1"),
+            })
+        );
+        assert!(get_escrow_totals(&deps.storage).unwrap().is_empty());
+
+        // the revealed preimage stays queryable for the other leg of the swap
+        let htlc: Htlc = from_binary(
+            &query(
+                deps.as_ref(),
+                env_at(500),
+                QueryMsg::GetHtlc {
+                    id: "htlc_1".into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(htlc.status, HtlcStatus::Claimed);
+        assert_eq!(htlc.preimage, Some(PREIMAGE.into()));
+
+        match refund(deps.as_mut(), 2_000) {
+            Err(ContractError::HtlcClosed { status }) => assert_eq!(status, "claimed"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn late_claim_fails_and_owner_is_refunded() {
+        let mut deps = mock_dependencies(&[]);
+        create(deps.as_mut());
+
+        match claim(deps.as_mut(), 1_000, PREIMAGE) {
+            Err(ContractError::HtlcExpired {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let response = refund(deps.as_mut(), 1_000).unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(100, "base_1"),
+            })
+        );
+        assert!(get_escrow_totals(&deps.storage).unwrap().is_empty());
+
+        match claim(deps.as_mut(), 500, PREIMAGE) {
+            Err(ContractError::HtlcClosed { status }) => assert_eq!(status, "refunded"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let htlcs = get_htlcs(&deps.storage, None, None).unwrap();
+        assert_eq!(htlcs.len(), 1);
+        assert_eq!(htlcs[0].status, HtlcStatus::Refunded);
+    }
+
+    #[test]
+    fn create_htlc_with_invalid_data() {
+        let mut deps = mock_dependencies(&[]);
+        let create = |hash: &str, timeout: u64| ExecuteMsg::CreateHtlc {
+            id: "htlc_1".into(),
+            counterparty: "counterparty".into(),
+            hash: hash.into(),
+            timeout: Timestamp::from_seconds(timeout),
+        };
+        let hash = hex::encode(Sha256::digest(b"secret"));
+
+        match execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("owner", &coins(100, "base_1")),
+            create("abcd", 1_000),
+        ) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "hash"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("owner", &coins(100, "base_1")),
+            create(&hash, 100),
+        ) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "timeout"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match execute(
+            deps.as_mut(),
+            env_at(100),
+            mock_info("owner", &[]),
+            create(&hash, 1_000),
+        ) {
+            Err(ContractError::MissingAskBase) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
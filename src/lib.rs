@@ -1,5 +1,24 @@
+pub mod auction;
+pub mod cancel;
+pub mod conditional;
+pub mod config;
 pub mod contract;
 pub mod contract_info;
 pub mod error;
+pub mod escrow;
+pub mod events;
+pub mod htlc;
+pub mod markets;
 pub mod msg;
+pub mod operators;
+pub mod pairs;
+pub mod rfq;
 pub mod state;
+pub mod stats;
+pub mod swaps;
+#[cfg(test)]
+mod testing;
+pub mod time_in_force;
+pub mod trade;
+pub mod twap;
+pub mod validation;
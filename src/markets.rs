@@ -0,0 +1,453 @@
+use cosmwasm_std::{to_binary, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage};
+use cw_storage_plus::Bound;
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+
+use crate::config::{validate_addresses, validate_fees};
+use crate::contract_info::get_contract_info;
+use crate::error::ContractError;
+use crate::events::{EventAttributes, MARKET_KEY};
+use crate::msg::FeeSchedule;
+use crate::state::{Market, MARKETS};
+use crate::validation::validate_id;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// create a market with its own admin, matchers, fees and order space, admin only
+pub fn create_market(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    admin: String,
+    fees: Option<FeeSchedule>,
+    matchers: Vec<String>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if info.sender != get_contract_info(deps.storage)?.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !info.funds.is_empty() {
+        return Err(ContractError::ExecuteWithFunds {});
+    }
+    validate_id(&id)?;
+    if MARKETS.has(deps.storage, &id) {
+        return Err(ContractError::MarketAlreadyExists { id });
+    }
+
+    let market = Market {
+        admin: deps.api.addr_validate(&admin)?,
+        fees: fees
+            .map(|fees| validate_fees(deps.api, fees))
+            .transpose()?
+            .flatten(),
+        id,
+        matchers: validate_addresses(deps.api, &matchers)?,
+    };
+    MARKETS.save(deps.storage, &market.id, &market)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("create_market", &env)
+                .add(MARKET_KEY, &market.id)
+                .add("admin", market.admin.as_str()),
+        )
+        .set_data(to_binary(&market)?))
+}
+
+// update a market's admin, matchers or fees, the market admin or contract admin only
+pub fn update_market(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    admin: Option<String>,
+    fees: Option<FeeSchedule>,
+    matchers: Option<Vec<String>>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&id)?;
+    let mut market = MARKETS
+        .may_load(deps.storage, &id)?
+        .ok_or(ContractError::MarketNotFound { id })?;
+    if info.sender != market.admin && info.sender != get_contract_info(deps.storage)?.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !info.funds.is_empty() {
+        return Err(ContractError::ExecuteWithFunds {});
+    }
+
+    if let Some(admin) = admin {
+        market.admin = deps.api.addr_validate(&admin)?;
+    }
+    if let Some(fees) = fees {
+        market.fees = validate_fees(deps.api, fees)?;
+    }
+    if let Some(matchers) = matchers {
+        market.matchers = validate_addresses(deps.api, &matchers)?;
+    }
+    MARKETS.save(deps.storage, &market.id, &market)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("update_market", &env)
+                .add(MARKET_KEY, &market.id)
+                .add("admin", market.admin.as_str()),
+        )
+        .set_data(to_binary(&market)?))
+}
+
+// the market an order names, none for the default market
+pub fn load_market(
+    storage: &dyn Storage,
+    market: Option<&str>,
+) -> Result<Option<Market>, ContractError> {
+    match market {
+        Some(id) => {
+            validate_id(id)?;
+            Ok(Some(
+                MARKETS
+                    .may_load(storage, id)?
+                    .ok_or(ContractError::MarketNotFound { id: id.into() })?,
+            ))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn get_market(storage: &dyn Storage, id: &str) -> StdResult<Market> {
+    MARKETS.load(storage, id)
+}
+
+pub fn get_markets(
+    storage: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Market>> {
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    MARKETS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .map(|item| item.map(|(_, market)| market))
+        .collect()
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{attr, coin, coins, from_binary, Addr, BankMsg, CosmosMsg};
+    use provwasm_mocks::mock_dependencies;
+
+    use super::*;
+    use crate::contract::{execute, query};
+    use crate::msg::{ConfigMsg, ExecuteMsg, QueryMsg};
+    use crate::state::{get_ask_storage_read, AskOrder, MatchingMode, Trade};
+    use crate::testing::instantiate_venue;
+
+    fn create_market_msg(id: &str) -> ExecuteMsg {
+        ExecuteMsg::CreateMarket {
+            id: id.into(),
+            admin: "market_admin".into(),
+            fees: Some(FeeSchedule {
+                collector: "market_collector".into(),
+                quote_fee_bps: 100,
+            }),
+            matchers: vec!["market_matcher".into()],
+        }
+    }
+
+    fn create_ask(
+        deps: DepsMut<ProvenanceQuery>,
+        market: Option<&str>,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("asker", &coins(100, "base_1")),
+            ExecuteMsg::CreateAsk {
+                client_order_id: None,
+                quote: coins(1000, "quote_1"),
+                market: market.map(String::from),
+                take: None,
+                time_in_force: None,
+            },
+        )
+    }
+
+    fn create_bid(
+        deps: DepsMut<ProvenanceQuery>,
+        market: Option<&str>,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("bidder", &coins(1000, "quote_1")),
+            ExecuteMsg::CreateBid {
+                base: coins(100, "base_1"),
+                client_order_id: None,
+                effective_time: None,
+                market: market.map(String::from),
+                take: None,
+                time_in_force: None,
+            },
+        )
+    }
+
+    fn execute_match(
+        deps: DepsMut<ProvenanceQuery>,
+        sender: &str,
+        ask_id: &str,
+        bid_id: &str,
+        market: Option<&str>,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::ExecuteMatch {
+                ask_id: ask_id.into(),
+                bid_id: bid_id.into(),
+                market: market.map(String::from),
+            },
+        )
+    }
+
+    #[test]
+    fn create_and_update_markets() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+
+        // only the contract admin creates markets
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("market_admin", &[]),
+            create_market_msg("market_1"),
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            create_market_msg("market 1"),
+        ) {
+            Err(ContractError::InvalidId { id }) => assert_eq!(id, "market 1"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            create_market_msg("market_1"),
+        )
+        .unwrap();
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            create_market_msg("market_1"),
+        ) {
+            Err(ContractError::MarketAlreadyExists { id }) => assert_eq!(id, "market_1"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // the market admin updates its own market, others may not
+        let update_market = |matchers: Vec<String>| ExecuteMsg::UpdateMarket {
+            id: "market_1".into(),
+            admin: None,
+            fees: Some(FeeSchedule {
+                collector: "market_collector".into(),
+                quote_fee_bps: 0,
+            }),
+            matchers: Some(matchers),
+        };
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("market_matcher", &[]),
+            update_market(vec![]),
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("market_admin", &[]),
+            update_market(vec!["matcher_2".into(), "matcher_1".into()]),
+        )
+        .unwrap();
+
+        let market: Market = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetMarket {
+                    id: "market_1".into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            market,
+            Market {
+                admin: Addr::unchecked("market_admin"),
+                fees: None,
+                id: "market_1".into(),
+                matchers: vec![Addr::unchecked("matcher_1"), Addr::unchecked("matcher_2")],
+            }
+        );
+
+        let markets: Vec<Market> = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetMarkets {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(markets, vec![market]);
+    }
+
+    #[test]
+    fn markets_keep_separate_order_spaces() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            create_market_msg("market_1"),
+        )
+        .unwrap();
+
+        match create_ask(deps.as_mut(), Some("market_2")) {
+            Err(ContractError::MarketNotFound { id }) => assert_eq!(id, "market_2"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // ids are assigned from one sequence, orders are stored under (market, id)
+        create_ask(deps.as_mut(), Some("market_1")).unwrap();
+        create_bid(deps.as_mut(), None).unwrap();
+        create_bid(deps.as_mut(), Some("market_1")).unwrap();
+
+        let ask_order: AskOrder = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetAsk {
+                    id: "1".into(),
+                    market: Some("market_1".into()),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(ask_order.market, Some("market_1".into()));
+        assert!(get_ask_storage_read(&deps.storage)
+            .load("1".as_bytes())
+            .is_err());
+
+        // orders of different markets are never crossed
+        match execute_match(deps.as_mut(), "market_admin", "1", "2", Some("market_1")) {
+            Err(ContractError::BidNotFound { id }) => assert_eq!(id, "2"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match execute_match(deps.as_mut(), "venue_admin", "1", "2", None) {
+            Err(ContractError::AskNotFound { id }) => assert_eq!(id, "1"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // only the market's admin and matchers match in the market, paying the market's fees
+        match execute_match(deps.as_mut(), "venue_admin", "1", "3", Some("market_1")) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let response =
+            execute_match(deps.as_mut(), "market_matcher", "1", "3", Some("market_1")).unwrap();
+        assert!(response.attributes.contains(&attr("market", "market_1")));
+        assert_eq!(
+            response.messages[2].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "market_collector".into(),
+                amount: coins(10, "quote_1"),
+            })
+        );
+        let trade: Trade = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(trade.fees, vec![coin(10, "quote_1")]);
+        assert_eq!(trade.bid.market, Some("market_1".into()));
+
+        // the market admin may force cancel orders of its market only
+        create_ask(deps.as_mut(), None).unwrap();
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("market_admin", &[]),
+            ExecuteMsg::ForceCancelAsk {
+                id: "4".into(),
+                reason: "compliance".into(),
+                market: None,
+            },
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn default_market_id_cannot_reach_another_market() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(
+            deps.as_mut(),
+            ConfigMsg {
+                matching_mode: Some(MatchingMode::Permissionless),
+                ..Default::default()
+            },
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venue_admin", &[]),
+            create_market_msg("m"),
+        )
+        .unwrap();
+        create_ask(deps.as_mut(), Some("m")).unwrap();
+        create_bid(deps.as_mut(), None).unwrap();
+
+        // the key of ask 1 in market "m" read as a default market id is not a valid id, and an
+        // order loaded under it is not of the default market
+        let forged_id = "\u{0}\u{1}m1";
+        match execute_match(deps.as_mut(), "anyone", forged_id, "2", None) {
+            Err(ContractError::InvalidId { id }) => assert_eq!(id, forged_id),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("asker", &[]),
+            ExecuteMsg::CancelAsk {
+                id: forged_id.into(),
+                market: None,
+            },
+        ) {
+            Err(ContractError::InvalidId { id }) => assert_eq!(id, forged_id),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetAsk {
+                id: forged_id.into(),
+                market: None,
+            },
+        )
+        .is_err());
+    }
+}
@@ -1,41 +1,538 @@
-use cosmwasm_std::{Coin, Timestamp};
+use cosmwasm_std::{BankMsg, Coin, Decimal, Timestamp, Uint128};
+use provwasm_std::NameBinding;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+use crate::state::{
+    AskOrder, BidOrder, MatchingMode, OrderStatus, Pair, PriceTrigger, Side, TimeInForce,
+    TradingPair,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
-    pub bind_name: String,
+    /// defaults to the sender
+    pub admin: Option<String>,
+    /// no name is bound when not set
+    pub bind_name: Option<String>,
+    #[serde(default)]
+    pub config: ConfigMsg,
     pub contract_name: String,
+    /// defaults to restricted
+    pub name_binding: Option<NameBinding>,
+}
+
+/// venue configuration, fields left unset keep their current value, or the default at instantiation
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ConfigMsg {
+    pub allowed_denoms: Option<Vec<String>>,
+    pub compliance_address: Option<String>,
+    /// a fee of zero basis points removes fees
+    pub fees: Option<FeeSchedule>,
+    pub matchers: Option<Vec<String>>,
+    pub matching_mode: Option<MatchingMode>,
+    pub order_retention: Option<u64>,
+    pub paused: Option<bool>,
+    pub pausers: Option<Vec<String>>,
+    pub price_oracle: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeSchedule {
+    pub collector: String,
+    pub quote_fee_bps: u16,
+}
+
+/// coins one participant of a swap gives another
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapLegMsg {
+    pub coins: Vec<Coin>,
+    pub from: String,
+    pub to: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    AcceptRfqQuote {
+        rfq_id: String,
+        quote_id: String,
+    },
+    /// approve an operator to place, cancel and amend the sender's orders, funds sent are added to
+    /// the operator balance
+    ApproveOperator {
+        operator: String,
+        expires: Option<Timestamp>,
+    },
+    BidAuction {
+        id: String,
+    },
+    /// bind an additional name to the contract, admin only
+    BindName {
+        name: String,
+        name_binding: Option<NameBinding>,
+    },
+    /// cancel the sender's open orders, optionally only one side or orders trading a denom, of the
+    /// first limit open orders
+    CancelAll {
+        side: Option<Side>,
+        denom: Option<String>,
+        limit: Option<u32>,
+    },
     CancelAsk {
         id: String,
+        market: Option<String>,
     },
     CancelBid {
         id: String,
+        market: Option<String>,
     },
-    CreateAsk {
+    /// cancel a conditional order of the sender that has not triggered, refunding its escrow
+    CancelConditionalOrder {
+        id: String,
+    },
+    CancelMany {
+        ask_ids: Vec<String>,
+        bid_ids: Vec<String>,
+        market: Option<String>,
+    },
+    /// cancel a swap that has not settled, any participant may, refunding every funded leg
+    CancelSwap {
         id: String,
+    },
+    CheckSolvency {},
+    /// claim a hashed time-locked ask for its counterparty by revealing the preimage of its hash
+    /// before the timeout, anyone may
+    ClaimHtlc {
+        id: String,
+        /// hex encoded preimage whose sha256 is the lock hash
+        preimage: String,
+    },
+    /// the contract assigns the order's id, returned with the order in the response data
+    CreateAsk {
+        client_order_id: Option<String>,
         quote: Vec<Coin>,
+        /// market to trade in, the default market when not set
+        market: Option<String>,
+        /// id of a resting order of the other side to match when created
+        take: Option<String>,
+        /// defaults to good till cancelled
+        time_in_force: Option<TimeInForce>,
     },
-    CreateBid {
+    /// an approved operator creates an ask for the owner, escrowing the base from its balance
+    CreateAskFor {
+        base: Vec<Coin>,
+        client_order_id: Option<String>,
+        owner: String,
+        quote: Vec<Coin>,
+        market: Option<String>,
+        take: Option<String>,
+        time_in_force: Option<TimeInForce>,
+    },
+    CreateAuction {
         id: String,
+        end_time: Timestamp,
+        min_increment: Uint128,
+        reserve_price: Coin,
+        time_extension: Option<u64>,
+    },
+    /// the contract assigns the order's id, returned with the order in the response data
+    CreateBid {
         base: Vec<Coin>,
+        client_order_id: Option<String>,
         effective_time: Option<Timestamp>,
+        /// market to trade in, the default market when not set
+        market: Option<String>,
+        /// id of a resting order of the other side to match when created
+        take: Option<String>,
+        /// defaults to good till cancelled
+        time_in_force: Option<TimeInForce>,
+    },
+    /// an approved operator creates a bid for the owner, escrowing the quote from its balance
+    CreateBidFor {
+        base: Vec<Coin>,
+        client_order_id: Option<String>,
+        effective_time: Option<Timestamp>,
+        owner: String,
+        quote: Vec<Coin>,
+        market: Option<String>,
+        take: Option<String>,
+        time_in_force: Option<TimeInForce>,
+    },
+    /// an ask kept off the book until the pair's reference price meets the trigger
+    CreateConditionalAsk {
+        client_order_id: Option<String>,
+        quote: Vec<Coin>,
+        market: Option<String>,
+        trigger: PriceTrigger,
+    },
+    /// a bid kept off the book until the pair's reference price meets the trigger
+    CreateConditionalBid {
+        base: Vec<Coin>,
+        client_order_id: Option<String>,
+        effective_time: Option<Timestamp>,
+        market: Option<String>,
+        trigger: PriceTrigger,
+    },
+    /// lock the funds sent in an ask claimable by the counterparty with the preimage of the hex
+    /// encoded sha256 `hash` until `timeout`, and refundable to the sender after
+    CreateHtlc {
+        id: String,
+        counterparty: String,
+        hash: String,
+        timeout: Timestamp,
+    },
+    /// create a market with its own admin, matchers, fees and order space, admin only
+    CreateMarket {
+        id: String,
+        admin: String,
+        fees: Option<FeeSchedule>,
+        matchers: Vec<String>,
+    },
+    CreateRfq {
+        id: String,
+        base: Vec<Coin>,
+        expiration: Timestamp,
+    },
+    CreateRfqQuote {
+        rfq_id: String,
+        id: String,
+        quote: Vec<Coin>,
     },
     ExecuteMatch {
         ask_id: String,
         bid_id: String,
+        market: Option<String>,
+    },
+    /// expire open good-till-time orders past their expiration, refunding their escrow
+    ExpireOrders {
+        limit: Option<u32>,
+    },
+    ExpireRfq {
+        id: String,
+    },
+    FinalizeAuction {
+        id: String,
+    },
+    /// fund and sign the sender's legs of a swap, funds sent must equal the coins the sender gives.
+    /// The legs are the terms the sender signs and must match the swap's. The swap settles once
+    /// every participant has funded.
+    FundSwap {
+        id: String,
+        legs: Vec<SwapLegMsg>,
+    },
+    /// cancel any open ask, admin or the ask's market admin only, refunding restricted marker coins to
+    /// the compliance address
+    ForceCancelAsk {
+        id: String,
+        reason: String,
+        market: Option<String>,
+    },
+    /// cancel any open bid, admin or the bid's market admin only, refunding restricted marker coins to
+    /// the compliance address
+    ForceCancelBid {
+        id: String,
+        reason: String,
+        market: Option<String>,
+    },
+    /// propose a swap between several parties, the proposer must take part and funds its own legs
+    ProposeSwap {
+        id: String,
+        legs: Vec<SwapLegMsg>,
+    },
+    PruneOrders {
+        limit: Option<u32>,
+    },
+    /// refund a hashed time-locked ask to its owner once it has timed out unclaimed, anyone may
+    RefundHtlc {
+        id: String,
+    },
+    RevokeOperator {
+        operator: String,
+    },
+    SetOrderRetention {
+        seconds: u64,
+    },
+    /// the admin and pausers may pause, only the admin may unpause
+    SetPaused {
+        paused: bool,
+    },
+    SetTradingPair {
+        pair: TradingPair,
+    },
+    SweepExcess {
+        denom: String,
+        to: String,
+    },
+    /// place up to `limit` conditional orders of the pair whose trigger the reference price meets,
+    /// anyone may trigger orders
+    TriggerOrders {
+        pair: Pair,
+        limit: Option<u32>,
+    },
+    UpdateAsk {
+        id: String,
+        quote: Option<Vec<Coin>>,
+        base_refund: Option<Vec<Coin>>,
+        market: Option<String>,
+    },
+    UpdateBid {
+        id: String,
+        base: Option<Vec<Coin>>,
+        quote_refund: Option<Vec<Coin>>,
+        market: Option<String>,
+    },
+    UpdateConfig {
+        admin: Option<String>,
+        config: ConfigMsg,
+    },
+    /// update a market, fields left unset keep their current value, market or contract admin only
+    UpdateMarket {
+        id: String,
+        admin: Option<String>,
+        /// a fee of zero basis points removes fees
+        fees: Option<FeeSchedule>,
+        matchers: Option<Vec<String>>,
     },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    GetAsk { id: String },
-    GetBid { id: String },
+    AllPairStats {
+        /// (base denom, quote denom) of the last pair seen
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    GetAsk {
+        id: String,
+        market: Option<String>,
+    },
+    GetAskByClientOrderId {
+        client_order_id: String,
+        owner: String,
+        market: Option<String>,
+    },
+    GetAuction {
+        id: String,
+    },
+    GetBid {
+        id: String,
+        market: Option<String>,
+    },
+    GetBidByClientOrderId {
+        client_order_id: String,
+        owner: String,
+        market: Option<String>,
+    },
+    GetComplianceAddress {},
+    GetConditionalOrder {
+        id: String,
+    },
+    /// conditional orders of a pair that have not triggered
+    GetConditionalOrders {
+        pair: Pair,
+        /// id of the last conditional order seen
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetConfig {},
     GetContractInfo {},
+    GetEscrowTotals {},
+    GetHtlc {
+        id: String,
+    },
+    GetHtlcs {
+        /// id of the last hashed time-locked ask seen
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetMarket {
+        id: String,
+    },
+    GetMarkets {
+        /// id of the last market seen
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetOperatorApprovals {
+        owner: String,
+        /// operator of the last approval seen
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetOrderRetention {},
+    GetRfq {
+        id: String,
+    },
+    GetRfqQuotes {
+        rfq_id: String,
+    },
+    GetSwap {
+        id: String,
+    },
+    GetTrade {
+        id: u64,
+    },
+    GetTradingPair {
+        base_denom: String,
+        quote_denom: String,
+    },
+    GetTradingPairs {
+        /// (base denom, quote denom) of the last pair seen
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    PairStats {
+        base_denom: String,
+        quote_denom: String,
+    },
+    SimulateMatch {
+        ask_id: String,
+        bid_id: String,
+        market: Option<String>,
+        /// the address that would execute the match
+        matcher: String,
+    },
+    TradesByDenom {
+        denom: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    TradesByOwner {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    Twap {
+        pair: Pair,
+        window_seconds: u64,
+    },
+}
+
+// why an ask and bid can not be matched
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchFailure {
+    AskClosed {
+        status: OrderStatus,
+    },
+    AskNotFound {
+        id: String,
+    },
+    BaseAmountMismatch {
+        ask: Vec<Coin>,
+        bid: Vec<Coin>,
+    },
+    BaseDenomMismatch {
+        ask: Vec<Coin>,
+        bid: Vec<Coin>,
+    },
+    BidClosed {
+        status: OrderStatus,
+    },
+    BidNotEffective {
+        effective_time: Timestamp,
+    },
+    BidNotFound {
+        id: String,
+    },
+    /// the matcher may not execute matches in the market
+    MatcherNotAllowed {
+        matcher: String,
+    },
+    Paused {},
+    QuoteAmountMismatch {
+        ask: Vec<Coin>,
+        bid: Vec<Coin>,
+    },
+    QuoteDenomMismatch {
+        ask: Vec<Coin>,
+        bid: Vec<Coin>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PairStatsResponse {
+    pub base_denom: String,
+    pub base_volume: Uint128,
+    /// base traded within the last 24 hours, counted in whole hours
+    pub base_volume_24h: Uint128,
+    pub high: Decimal,
+    /// quote paid per unit of base in the last trade
+    pub last_price: Decimal,
+    pub last_trade_time: Timestamp,
+    pub low: Decimal,
+    pub quote_denom: String,
+    pub quote_volume: Uint128,
+    /// quote traded within the last 24 hours, counted in whole hours
+    pub quote_volume_24h: Uint128,
+    pub trade_count: u64,
+    /// volume weighted average price of all trades
+    pub vwap: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TwapResponse {
+    pub end_time: Timestamp,
+    /// time weighted average of quote paid per unit of base
+    pub price: Decimal,
+    pub start_time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CancelResponse {
+    pub ask_ids: Vec<String>,
+    pub bid_ids: Vec<String>,
+    /// refunded coins, sent to the owner in a single bank send
+    pub refund: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowDiscrepancy {
+    pub balance: Uint128,
+    pub denom: String,
+    pub escrowed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateMatchResponse {
+    pub executable: bool,
+    /// the first reason the match would fail
+    pub failure: Option<MatchFailure>,
+    pub fees: Vec<Coin>,
+    /// the messages `ExecuteMatch` would send
+    pub messages: Vec<BankMsg>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SolvencyReport {
+    pub discrepancies: Vec<EscrowDiscrepancy>,
+    /// false when the balance of any denom is below its escrowed total
+    pub solvent: bool,
+}
+
+// query sent to the price oracle contract for the reference price of a pair
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleQueryMsg {
+    Price {
+        base_denom: String,
+        quote_denom: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OraclePriceResponse {
+    /// quote per unit of base
+    pub price: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TriggerOrdersResponse {
+    /// orders placed from triggered conditional asks and bids
+    pub asks: Vec<AskOrder>,
+    pub bids: Vec<BidOrder>,
+    /// ids of triggered conditional orders that could no longer be placed and were refunded
+    pub dropped: Vec<String>,
+    pub reference_price: Decimal,
 }
@@ -0,0 +1,508 @@
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Coin, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage,
+    Timestamp,
+};
+use cw_storage_plus::Bound;
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+
+use crate::contract::{add_coins, open_ask, open_bid, sorted_coins};
+use crate::error::ContractError;
+use crate::escrow::{deposit_escrow, release_escrow};
+use crate::events::{EventAttributes, OPERATOR_KEY, OWNER_KEY};
+use crate::state::{OperatorApproval, TimeInForce, OPERATOR_APPROVALS};
+use crate::time_in_force::{check_time_in_force, created_ask, created_bid};
+use crate::validation::normalize_coins;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// approve an operator to place, cancel and amend the sender's orders until `expires`. Funds sent
+// are added to the operator balance that orders placed by the operator are escrowed from, and
+// approving an existing operator again replaces its expiration and tops up its balance.
+pub fn approve_operator(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    expires: Option<Timestamp>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    if operator == info.sender {
+        return Err(ContractError::InvalidField {
+            field: "operator".into(),
+        });
+    }
+    if matches!(expires, Some(expires) if expires <= env.block.time) {
+        return Err(ContractError::InvalidField {
+            field: "expires".into(),
+        });
+    }
+    let funds = normalize_coins(info.funds, "funds")?;
+
+    let key = (info.sender.as_str(), operator.as_str());
+    let balance = match OPERATOR_APPROVALS.may_load(deps.storage, key)? {
+        Some(approval) => add_coins(&approval.balance, &funds),
+        None => funds.to_owned(),
+    };
+    let approval = OperatorApproval {
+        balance: sorted_coins(&balance),
+        expires,
+        operator: operator.to_owned(),
+        owner: info.sender.to_owned(),
+    };
+    OPERATOR_APPROVALS.save(deps.storage, key, &approval)?;
+    deposit_escrow(deps.storage, &funds)?;
+
+    let mut attributes = EventAttributes::new("approve_operator", &env)
+        .add(OWNER_KEY, approval.owner.as_str())
+        .add(OPERATOR_KEY, approval.operator.as_str())
+        .add_coins("balance", &approval.balance);
+    if let Some(expires) = approval.expires {
+        attributes = attributes.add("expires", expires.nanos().to_string());
+    }
+
+    Ok(Response::new()
+        .add_attributes(attributes)
+        .set_data(to_binary(&approval)?))
+}
+
+// revoke an operator of the sender, refunding what is left of its balance
+pub fn revoke_operator(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::RevokeWithFunds {});
+    }
+
+    let key = (info.sender.as_str(), operator.as_str());
+    let approval = OPERATOR_APPROVALS
+        .may_load(deps.storage, key)?
+        .ok_or_else(|| ContractError::OperatorNotFound {
+            operator: operator.to_owned(),
+        })?;
+    OPERATOR_APPROVALS.remove(deps.storage, key);
+    release_escrow(deps.storage, &approval.balance)?;
+
+    let mut response = Response::new().add_attributes(
+        EventAttributes::new("revoke_operator", &env)
+            .add(OWNER_KEY, approval.owner.as_str())
+            .add(OPERATOR_KEY, approval.operator.as_str())
+            .add_coins("refund", &approval.balance),
+    );
+    if !approval.balance.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: approval.owner.to_string(),
+            amount: approval.balance,
+        });
+    }
+    Ok(response)
+}
+
+// create an ask for an owner whose approved operator is the sender, escrowing the base from the
+// operator balance
+#[allow(clippy::too_many_arguments)]
+pub fn create_ask_for(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    base: Vec<Coin>,
+    quote: Vec<Coin>,
+    client_order_id: Option<String>,
+    market: Option<String>,
+    take: Option<String>,
+    time_in_force: TimeInForce,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::OperatorWithFunds {});
+    }
+    if base.is_empty() {
+        return Err(ContractError::MissingAskBase);
+    }
+    if quote.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "quote".into(),
+        });
+    }
+    let base = normalize_coins(base, "base")?;
+    let quote = normalize_coins(quote, "quote")?;
+    check_time_in_force(&env, time_in_force, take.as_deref())?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    debit_operator_balance(deps.storage, &env, &owner, &info.sender, &base)?;
+    let ask_order = open_ask(
+        deps.storage,
+        &env,
+        owner,
+        base,
+        quote,
+        client_order_id,
+        market,
+        time_in_force,
+    )?;
+
+    created_ask(
+        deps.storage,
+        &env,
+        &info.sender,
+        ask_order,
+        take,
+        time_in_force,
+    )
+}
+
+// create a bid for an owner whose approved operator is the sender, escrowing the quote from the
+// operator balance
+#[allow(clippy::too_many_arguments)]
+pub fn create_bid_for(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    base: Vec<Coin>,
+    quote: Vec<Coin>,
+    client_order_id: Option<String>,
+    effective_time: Option<Timestamp>,
+    market: Option<String>,
+    take: Option<String>,
+    time_in_force: TimeInForce,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::OperatorWithFunds {});
+    }
+    if base.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "base".into(),
+        });
+    }
+    if quote.is_empty() {
+        return Err(ContractError::MissingBidQuote);
+    }
+    let base = normalize_coins(base, "base")?;
+    let quote = normalize_coins(quote, "quote")?;
+    check_time_in_force(&env, time_in_force, take.as_deref())?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    debit_operator_balance(deps.storage, &env, &owner, &info.sender, &quote)?;
+    let bid_order = open_bid(
+        deps.storage,
+        &env,
+        owner,
+        base,
+        quote,
+        client_order_id,
+        effective_time,
+        market,
+        time_in_force,
+    )?;
+
+    created_bid(
+        deps.storage,
+        &env,
+        &info.sender,
+        bid_order,
+        take,
+        time_in_force,
+    )
+}
+
+// return error unless the sender is the owner or an operator the owner has approved
+pub fn check_owner_or_operator(
+    storage: &dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    if sender == owner {
+        return Ok(());
+    }
+    load_approval(storage, env, owner, sender).map(|_| ())
+}
+
+// the owner's unexpired approval of the operator
+fn load_approval(
+    storage: &dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    operator: &Addr,
+) -> Result<OperatorApproval, ContractError> {
+    match OPERATOR_APPROVALS.may_load(storage, (owner.as_str(), operator.as_str()))? {
+        Some(approval) if approval.is_active(env.block.time) => Ok(approval),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+// move coins from an operator balance to an order of the owner, the escrow ledger is credited
+// again when the order is opened
+fn debit_operator_balance(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    operator: &Addr,
+    coins: &[Coin],
+) -> Result<(), ContractError> {
+    let mut approval = load_approval(storage, env, owner, operator)?;
+    for coin in coins {
+        match approval.balance.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(existing) if existing.amount >= coin.amount => existing.amount -= coin.amount,
+            _ => return Err(ContractError::InsufficientOperatorBalance {}),
+        }
+    }
+    approval.balance.retain(|c| !c.amount.is_zero());
+    OPERATOR_APPROVALS.save(storage, (owner.as_str(), operator.as_str()), &approval)?;
+    release_escrow(storage, coins)?;
+    Ok(())
+}
+
+pub fn get_operator_approvals(
+    storage: &dyn Storage,
+    owner: &str,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<OperatorApproval>> {
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    OPERATOR_APPROVALS
+        .prefix(owner)
+        .range(storage, start, None, Order::Ascending)
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .map(|item| item.map(|(_, approval)| approval))
+        .collect()
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_info;
+    use cosmwasm_std::{coin, coins, from_binary, CosmosMsg};
+    use provwasm_mocks::mock_dependencies;
+
+    use super::*;
+    use crate::contract::{execute, query};
+    use crate::msg::{ConfigMsg, ExecuteMsg, QueryMsg};
+    use crate::state::{get_ask_storage_read, AskOrder, OrderStatus};
+    use crate::testing::{env_at, instantiate_venue};
+
+    fn setup(deps: DepsMut<ProvenanceQuery>) {
+        instantiate_venue(deps, ConfigMsg::default());
+    }
+
+    fn create_ask_for(
+        deps: DepsMut<ProvenanceQuery>,
+        env: Env,
+        sender: &str,
+        base: Vec<Coin>,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            env,
+            mock_info(sender, &[]),
+            ExecuteMsg::CreateAskFor {
+                base,
+                client_order_id: None,
+                owner: "owner".into(),
+                quote: coins(10, "quote_1"),
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+    }
+
+    #[test]
+    fn operator_places_and_cancels_orders_for_owner() {
+        let mut deps = mock_dependencies(&[]);
+        setup(deps.as_mut());
+        execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("owner", &[coin(100, "base_1"), coin(50, "quote_1")]),
+            ExecuteMsg::ApproveOperator {
+                operator: "desk".into(),
+                expires: None,
+            },
+        )
+        .unwrap();
+
+        match create_ask_for(
+            deps.as_mut(),
+            env_at(1_000),
+            "stranger",
+            coins(40, "base_1"),
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match create_ask_for(deps.as_mut(), env_at(1_000), "desk", coins(101, "base_1")) {
+            Err(ContractError::InsufficientOperatorBalance {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let response =
+            create_ask_for(deps.as_mut(), env_at(1_000), "desk", coins(40, "base_1")).unwrap();
+        let ask_order: AskOrder = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(ask_order.owner, Addr::unchecked("owner"));
+        assert_eq!(ask_order.base, coins(40, "base_1"));
+        assert!(response
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "operator" && attr.value == "desk"));
+
+        // operators may amend, but not fund, the owner's orders
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("desk", &coins(10, "base_1")),
+            ExecuteMsg::UpdateAsk {
+                id: ask_order.id.to_owned(),
+                quote: None,
+                base_refund: None,
+                market: None,
+            },
+        ) {
+            Err(ContractError::OperatorWithFunds {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("stranger", &[]),
+            ExecuteMsg::CancelAsk {
+                id: ask_order.id.to_owned(),
+                market: None,
+            },
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // refunds go to the owner
+        let response = execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("desk", &[]),
+            ExecuteMsg::CancelAsk {
+                id: ask_order.id.to_owned(),
+                market: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(40, "base_1"),
+            })
+        );
+        assert_eq!(
+            get_ask_storage_read(&deps.storage)
+                .load(ask_order.id.as_bytes())
+                .unwrap()
+                .status,
+            OrderStatus::Cancelled
+        );
+
+        let approvals: Vec<OperatorApproval> = from_binary(
+            &query(
+                deps.as_ref(),
+                env_at(1_000),
+                QueryMsg::GetOperatorApprovals {
+                    owner: "owner".into(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            approvals,
+            vec![OperatorApproval {
+                balance: vec![coin(60, "base_1"), coin(50, "quote_1")],
+                expires: None,
+                operator: Addr::unchecked("desk"),
+                owner: Addr::unchecked("owner"),
+            }]
+        );
+
+        // revoking refunds the rest of the balance
+        let response = execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("owner", &[]),
+            ExecuteMsg::RevokeOperator {
+                operator: "desk".into(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: vec![coin(60, "base_1"), coin(50, "quote_1")],
+            })
+        );
+        assert_eq!(
+            query(deps.as_ref(), env_at(1_000), QueryMsg::GetEscrowTotals {}),
+            to_binary(&Vec::<Coin>::new())
+        );
+        match create_ask_for(deps.as_mut(), env_at(1_000), "desk", coins(10, "base_1")) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("owner", &[]),
+            ExecuteMsg::RevokeOperator {
+                operator: "desk".into(),
+            },
+        ) {
+            Err(ContractError::OperatorNotFound { operator }) => assert_eq!(operator, "desk"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn operator_approval_expires() {
+        let mut deps = mock_dependencies(&[]);
+        setup(deps.as_mut());
+        let mut approve = |expires: u64| {
+            execute(
+                deps.as_mut(),
+                env_at(1_000),
+                mock_info("owner", &coins(100, "base_1")),
+                ExecuteMsg::ApproveOperator {
+                    operator: "desk".into(),
+                    expires: Some(Timestamp::from_seconds(expires)),
+                },
+            )
+        };
+        match approve(1_000) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "expires"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        approve(2_000).unwrap();
+
+        create_ask_for(deps.as_mut(), env_at(1_999), "desk", coins(10, "base_1")).unwrap();
+        match create_ask_for(deps.as_mut(), env_at(2_000), "desk", coins(10, "base_1")) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match execute(
+            deps.as_mut(),
+            env_at(2_000),
+            mock_info("desk", &[]),
+            ExecuteMsg::CancelAsk {
+                id: "1".into(),
+                market: None,
+            },
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use cosmwasm_std::{
+    Coin, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint256,
+};
+use cw_storage_plus::Bound;
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+
+use crate::contract_info::get_contract_info;
+use crate::error::ContractError;
+use crate::events::EventAttributes;
+use crate::state::{TradingPair, TRADING_PAIRS};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+const DECIMAL_FRACTIONAL: u128 = 1_000_000_000_000_000_000;
+
+// register or replace a trading pair, admin only
+pub fn set_trading_pair(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    pair: TradingPair,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if info.sender != get_contract_info(deps.storage)?.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !info.funds.is_empty() {
+        return Err(ContractError::ExecuteWithFunds {});
+    }
+    if pair.base_denom.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "base_denom".into(),
+        });
+    }
+    if pair.quote_denom.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "quote_denom".into(),
+        });
+    }
+    if pair.lot_size.is_zero() {
+        return Err(ContractError::InvalidField {
+            field: "lot_size".into(),
+        });
+    }
+    if pair.tick_size.is_zero() {
+        return Err(ContractError::InvalidField {
+            field: "tick_size".into(),
+        });
+    }
+    if let Some(max_order_size) = pair.max_order_size {
+        if max_order_size < pair.min_order_size {
+            return Err(ContractError::InvalidField {
+                field: "max_order_size".into(),
+            });
+        }
+    }
+
+    TRADING_PAIRS.save(deps.storage, (&pair.base_denom, &pair.quote_denom), &pair)?;
+
+    Ok(Response::new().add_attributes(
+        EventAttributes::new("set_trading_pair", &env)
+            .add("base_denom", &pair.base_denom)
+            .add("quote_denom", &pair.quote_denom)
+            .add("enabled", pair.enabled.to_string()),
+    ))
+}
+
+// once any pair is registered, orders must trade one coin of an enabled pair within its limits
+pub fn validate_order_pair(
+    storage: &dyn Storage,
+    base: &[Coin],
+    quote: &[Coin],
+) -> Result<(), ContractError> {
+    if TRADING_PAIRS
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_none()
+    {
+        return Ok(());
+    }
+
+    let (base, quote) = match (base, quote) {
+        ([base], [quote]) => (base, quote),
+        _ => return Err(ContractError::PairNotRegistered {}),
+    };
+    let pair = TRADING_PAIRS
+        .may_load(storage, (&base.denom, &quote.denom))?
+        .ok_or(ContractError::PairNotRegistered {})?;
+
+    if !pair.enabled {
+        return Err(ContractError::PairDisabled {});
+    }
+    if base.amount.is_zero()
+        || base.amount < pair.min_order_size
+        || matches!(pair.max_order_size, Some(max) if base.amount > max)
+        || base.amount.u128() % pair.lot_size.u128() != 0
+    {
+        return Err(ContractError::InvalidOrderSize {});
+    }
+
+    // the price, quote per unit of base, must be a whole number of ticks:
+    // quote / base = n * tick  <=>  quote * 10^18 = n * tick_atomics * base
+    let scaled_quote = Uint256::from(quote.amount) * Uint256::from(DECIMAL_FRACTIONAL);
+    let scaled_tick = Uint256::from(pair.tick_size.atomics()) * Uint256::from(base.amount);
+    if !scaled_quote
+        .checked_rem(scaled_tick)
+        .map_err(StdError::from)?
+        .is_zero()
+    {
+        return Err(ContractError::InvalidPrice {});
+    }
+
+    Ok(())
+}
+
+pub fn get_trading_pair(
+    storage: &dyn Storage,
+    base_denom: &str,
+    quote_denom: &str,
+) -> StdResult<TradingPair> {
+    TRADING_PAIRS.load(storage, (base_denom, quote_denom))
+}
+
+pub fn get_trading_pairs(
+    storage: &dyn Storage,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<TradingPair>> {
+    let start = start_after.as_ref().map(|(base_denom, quote_denom)| {
+        Bound::exclusive((base_denom.as_str(), quote_denom.as_str()))
+    });
+
+    TRADING_PAIRS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .map(|item| item.map(|(_, pair)| pair))
+        .collect()
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{coin, coins, to_binary, Decimal, Uint128};
+    use provwasm_mocks::mock_dependencies;
+
+    use super::*;
+    use crate::contract::{execute, query};
+    use crate::msg::{ConfigMsg, ExecuteMsg, QueryMsg};
+    use crate::testing::instantiate_venue;
+
+    // price increment of a tick, ie. `tick_size(5, 2)` is 0.05
+    fn tick_size(units: u64, decimal_places: u32) -> Decimal {
+        Decimal::from_ratio(units, 10u64.pow(decimal_places))
+    }
+
+    fn trading_pair(quote_denom: &str) -> TradingPair {
+        TradingPair {
+            base_denom: "base_1".into(),
+            enabled: true,
+            lot_size: Uint128::new(10),
+            max_order_size: Some(Uint128::new(1000)),
+            min_order_size: Uint128::new(20),
+            quote_denom: quote_denom.into(),
+            tick_size: tick_size(5, 2),
+        }
+    }
+
+    fn create_ask(
+        deps: DepsMut<ProvenanceQuery>,
+        base: Vec<Coin>,
+        quote: Vec<Coin>,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("asker", &base),
+            ExecuteMsg::CreateAsk {
+                client_order_id: None,
+                quote,
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        )
+    }
+
+    #[test]
+    fn set_trading_pair_with_invalid_data() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+
+        let mut set_pair = |sender: &str, pair: TradingPair| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::SetTradingPair { pair },
+            )
+        };
+
+        match set_pair("asker", trading_pair("quote_1")) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match set_pair(
+            "venue_admin",
+            TradingPair {
+                lot_size: Uint128::zero(),
+                ..trading_pair("quote_1")
+            },
+        ) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "lot_size"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match set_pair(
+            "venue_admin",
+            TradingPair {
+                tick_size: Decimal::zero(),
+                ..trading_pair("quote_1")
+            },
+        ) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "tick_size"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match set_pair(
+            "venue_admin",
+            TradingPair {
+                max_order_size: Some(Uint128::new(10)),
+                ..trading_pair("quote_1")
+            },
+        ) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "max_order_size"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn orders_are_validated_against_registered_pairs() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+
+        // any pair is accepted until the first pair is registered
+        assert!(create_ask(
+            deps.as_mut(),
+            vec![coin(1, "base_1"), coin(1, "base_2")],
+            coins(1, "quote_9")
+        )
+        .is_ok());
+
+        for pair in [
+            trading_pair("quote_1"),
+            TradingPair {
+                enabled: false,
+                ..trading_pair("quote_2")
+            },
+        ] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("venue_admin", &[]),
+                ExecuteMsg::SetTradingPair { pair },
+            )
+            .unwrap();
+        }
+
+        // 100 base_1 at 0.35 quote_1 each
+        assert!(create_ask(deps.as_mut(), coins(100, "base_1"), coins(35, "quote_1")).is_ok());
+
+        for (base, quote, expected) in [
+            (
+                coins(100, "base_1"),
+                coins(35, "quote_9"),
+                "PairNotRegistered",
+            ),
+            (
+                vec![coin(100, "base_1"), coin(100, "base_2")],
+                coins(35, "quote_1"),
+                "PairNotRegistered",
+            ),
+            (coins(100, "base_1"), coins(35, "quote_2"), "PairDisabled"),
+            (
+                coins(10, "base_1"),
+                coins(10, "quote_1"),
+                "InvalidOrderSize",
+            ),
+            (
+                coins(1010, "base_1"),
+                coins(101, "quote_1"),
+                "InvalidOrderSize",
+            ),
+            (
+                coins(105, "base_1"),
+                coins(21, "quote_1"),
+                "InvalidOrderSize",
+            ),
+            // 0.36 is not a multiple of the 0.05 tick
+            (coins(100, "base_1"), coins(36, "quote_1"), "InvalidPrice"),
+        ] {
+            match create_ask(deps.as_mut(), base, quote) {
+                Err(error) => assert!(
+                    format!("{:?}", error).starts_with(expected),
+                    "unexpected error: {:?}",
+                    error
+                ),
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+
+        // bids are checked with the quote they pay for the base they want
+        let create_bid_response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bidder", &coins(36, "quote_1")),
+            ExecuteMsg::CreateBid {
+                base: coins(100, "base_1"),
+                client_order_id: None,
+                effective_time: None,
+                market: None,
+                take: None,
+                time_in_force: None,
+            },
+        );
+        match create_bid_response {
+            Err(ContractError::InvalidPrice {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        assert_eq!(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetTradingPairs {
+                    start_after: Some(("base_1".into(), "quote_1".into())),
+                    limit: None,
+                },
+            ),
+            to_binary(&vec![TradingPair {
+                enabled: false,
+                ..trading_pair("quote_2")
+            }])
+        );
+    }
+}
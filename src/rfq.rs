@@ -0,0 +1,664 @@
+use cosmwasm_std::{
+    to_binary, BankMsg, Coin, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage,
+    Timestamp,
+};
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+
+use crate::config::check_allowed_denoms;
+use crate::contract::{is_executable, sorted_coins};
+use crate::error::ContractError;
+use crate::escrow::{deposit_escrow, release_escrow};
+use crate::events::{
+    format_coins, EventAttributes, BASE_KEY, ORDER_ID_KEY, OWNER_KEY, RFQ_ID_KEY, SIDE_KEY,
+};
+use crate::state::{AskOrder, BidOrder, OrderStatus, Rfq, Side, TimeInForce, RFQS, RFQ_QUOTES};
+use crate::validation::{normalize_coins, validate_id};
+
+// most quotes a single request for quote may collect, accepting and expiring refund all of them
+const MAX_QUOTES: usize = 20;
+
+// create request for quote entrypoint
+pub fn create_rfq(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    base: Vec<Coin>,
+    expiration: Timestamp,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&id)?;
+    let base = normalize_coins(base, "base")?;
+    if base.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "base".into(),
+        });
+    }
+    if expiration <= env.block.time {
+        return Err(ContractError::InvalidField {
+            field: "expiration".into(),
+        });
+    }
+    if RFQS.has(deps.storage, &id) {
+        return Err(ContractError::RfqAlreadyExists {});
+    }
+    check_allowed_denoms(deps.storage, &base)?;
+
+    let rfq = Rfq {
+        base,
+        expiration,
+        id,
+        owner: info.sender,
+    };
+
+    RFQS.save(deps.storage, &rfq.id, &rfq)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("create_rfq", &env)
+                .add(ORDER_ID_KEY, &rfq.id)
+                .add(SIDE_KEY, Side::Bid.to_string())
+                .add(OWNER_KEY, rfq.owner.as_str())
+                .add_coins(BASE_KEY, &rfq.base),
+        )
+        .set_data(to_binary(&rfq)?))
+}
+
+// dealer firm quote entrypoint, escrowing the requested base
+pub fn create_rfq_quote(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    rfq_id: String,
+    id: String,
+    quote: Vec<Coin>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&rfq_id)?;
+    validate_id(&id)?;
+    let quote = normalize_coins(quote, "quote")?;
+    if quote.is_empty() {
+        return Err(ContractError::MissingField {
+            field: "quote".into(),
+        });
+    }
+    let base = normalize_coins(info.funds, "base")?;
+    if base.is_empty() {
+        return Err(ContractError::MissingAskBase);
+    }
+
+    let rfq = RFQS.load(deps.storage, &rfq_id)?;
+
+    if env.block.time >= rfq.expiration {
+        return Err(ContractError::RfqExpired {});
+    }
+    if info.sender == rfq.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if sorted_coins(&base) != sorted_coins(&rfq.base) {
+        return Err(ContractError::RfqBaseMismatch {});
+    }
+    check_allowed_denoms(deps.storage, &quote)?;
+    if RFQ_QUOTES.has(deps.storage, (&rfq_id, &id)) {
+        return Err(ContractError::RfqQuoteAlreadyExists {});
+    }
+    if RFQ_QUOTES
+        .prefix(&rfq_id)
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .count()
+        >= MAX_QUOTES
+    {
+        return Err(ContractError::TooManyQuotes { max: MAX_QUOTES });
+    }
+
+    let rfq_quote = AskOrder {
+        base,
+        client_order_id: None,
+        id,
+        market: None,
+        owner: info.sender,
+        quote,
+        status: OrderStatus::Open,
+        status_updates: vec![],
+        time_in_force: TimeInForce::Gtc,
+    };
+
+    RFQ_QUOTES.save(deps.storage, (&rfq_id, &rfq_quote.id), &rfq_quote)?;
+    deposit_escrow(deps.storage, &rfq_quote.base)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("create_rfq_quote", &env)
+                .add(RFQ_ID_KEY, &rfq_id)
+                .ask(&rfq_quote),
+        )
+        .set_data(to_binary(&rfq_quote)?))
+}
+
+// requester accepts one quote by paying its quote, all other quotes are refunded
+pub fn accept_rfq_quote(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    rfq_id: String,
+    quote_id: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&rfq_id)?;
+    validate_id(&quote_id)?;
+    let quote = normalize_coins(info.funds, "quote")?;
+    if quote.is_empty() {
+        return Err(ContractError::MissingBidQuote);
+    }
+
+    let rfq = RFQS.load(deps.storage, &rfq_id)?;
+
+    if info.sender != rfq.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if env.block.time >= rfq.expiration {
+        return Err(ContractError::RfqExpired {});
+    }
+
+    let accepted_quote = RFQ_QUOTES.load(deps.storage, (&rfq_id, &quote_id))?;
+
+    // the requester's payment is treated as a bid against the accepted quote
+    let bid_order = BidOrder {
+        base: rfq.base,
+        client_order_id: None,
+        effective_time: None,
+        id: rfq.id,
+        market: None,
+        owner: info.sender,
+        quote,
+        status: OrderStatus::Open,
+        status_updates: vec![],
+        time_in_force: TimeInForce::Gtc,
+    };
+    // the quote's base was checked against the requested base when it was made, so only the
+    // payment can differ
+    if !is_executable(&accepted_quote, &bid_order) {
+        return Err(ContractError::QuoteMismatch {
+            ask: format_coins(&accepted_quote.quote),
+            bid: format_coins(&bid_order.quote),
+        });
+    }
+
+    let attributes = EventAttributes::new("accept_rfq_quote", &env)
+        .add(RFQ_ID_KEY, &rfq_id)
+        .matched(&accepted_quote, &bid_order);
+
+    // 'send quote to dealer' and 'send base to requester' messages
+    let mut messages = vec![
+        BankMsg::Send {
+            to_address: accepted_quote.owner.to_string(),
+            amount: accepted_quote.quote,
+        },
+        BankMsg::Send {
+            to_address: bid_order.owner.to_string(),
+            amount: accepted_quote.base,
+        },
+    ];
+
+    // 'send base back to dealer' messages for every quote not accepted
+    messages.extend(
+        remove_rfq(deps.storage, &rfq_id)?
+            .into_iter()
+            .filter(|rfq_quote| rfq_quote.id != quote_id)
+            .map(refund_rfq_quote),
+    );
+
+    Ok(Response::new()
+        .add_attributes(attributes)
+        .add_messages(messages))
+}
+
+// refund every quote on a request for quote whose window has closed
+pub fn expire_rfq(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&id)?;
+    // return error if funds sent
+    if !info.funds.is_empty() {
+        return Err(ContractError::CancelWithFunds {});
+    }
+
+    let rfq = RFQS.load(deps.storage, &id)?;
+
+    if env.block.time < rfq.expiration {
+        return Err(ContractError::RfqOpen {});
+    }
+
+    let messages: Vec<BankMsg> = remove_rfq(deps.storage, &id)?
+        .into_iter()
+        .map(refund_rfq_quote)
+        .collect();
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("expire_rfq", &env)
+                .add(RFQ_ID_KEY, &rfq.id)
+                .add(OWNER_KEY, rfq.owner.as_str())
+                .add_coins(BASE_KEY, &rfq.base),
+        )
+        .add_messages(messages))
+}
+
+pub fn get_rfq_quotes(storage: &dyn Storage, rfq_id: &str) -> StdResult<Vec<AskOrder>> {
+    RFQ_QUOTES
+        .prefix(rfq_id)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, rfq_quote)| rfq_quote))
+        .collect()
+}
+
+// remove a request for quote and its quotes from storage, releasing and returning the removed
+// quotes
+fn remove_rfq(storage: &mut dyn Storage, rfq_id: &str) -> StdResult<Vec<AskOrder>> {
+    let rfq_quotes = get_rfq_quotes(storage, rfq_id)?;
+    for rfq_quote in &rfq_quotes {
+        RFQ_QUOTES.remove(storage, (rfq_id, &rfq_quote.id));
+        release_escrow(storage, &rfq_quote.base)?;
+    }
+    RFQS.remove(storage, rfq_id);
+    Ok(rfq_quotes)
+}
+
+// 'send base back to dealer' message
+fn refund_rfq_quote(rfq_quote: AskOrder) -> BankMsg {
+    BankMsg::Send {
+        to_address: rfq_quote.owner.to_string(),
+        amount: rfq_quote.base,
+    }
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_info;
+    use cosmwasm_std::{attr, coin, coins, from_binary, Addr, CosmosMsg};
+    use provwasm_mocks::mock_dependencies;
+
+    use crate::contract::{execute, query};
+    use crate::msg::{ConfigMsg, ExecuteMsg, QueryMsg};
+
+    use super::*;
+    use crate::testing::{env_at, instantiate_venue};
+
+    // create an rfq for 10 base_1 expiring at 1000s with two dealer quotes
+    fn setup_rfq(deps: DepsMut<ProvenanceQuery>) {
+        let mut deps = deps;
+        if let Err(error) = execute(
+            deps.branch(),
+            env_at(100),
+            mock_info("requester", &[]),
+            ExecuteMsg::CreateRfq {
+                id: "rfq_id".into(),
+                base: coins(10, "base_1"),
+                expiration: Timestamp::from_seconds(1_000),
+            },
+        ) {
+            panic!("unexpected error: {:?}", error)
+        }
+        for (dealer, price) in [("dealer_1", 100), ("dealer_2", 95)] {
+            if let Err(error) = execute(
+                deps.branch(),
+                env_at(200),
+                mock_info(dealer, &coins(10, "base_1")),
+                ExecuteMsg::CreateRfqQuote {
+                    rfq_id: "rfq_id".into(),
+                    id: format!("{}_quote", dealer),
+                    quote: coins(price, "quote_1"),
+                },
+            ) {
+                panic!("unexpected error: {:?}", error)
+            }
+        }
+    }
+
+    #[test]
+    fn create_rfq_quote_with_valid_data() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        setup_rfq(deps.as_mut());
+
+        // verify rfq stored
+        let query_response = query(
+            deps.as_ref(),
+            env_at(200),
+            QueryMsg::GetRfq {
+                id: "rfq_id".into(),
+            },
+        );
+        assert_eq!(
+            from_binary::<Rfq>(&query_response.unwrap()).unwrap(),
+            Rfq {
+                base: coins(10, "base_1"),
+                expiration: Timestamp::from_seconds(1_000),
+                id: "rfq_id".into(),
+                owner: Addr::unchecked("requester"),
+            }
+        );
+
+        // verify quotes stored
+        let query_response = query(
+            deps.as_ref(),
+            env_at(200),
+            QueryMsg::GetRfqQuotes {
+                rfq_id: "rfq_id".into(),
+            },
+        );
+        assert_eq!(
+            from_binary::<Vec<AskOrder>>(&query_response.unwrap()).unwrap(),
+            vec![
+                AskOrder {
+                    base: coins(10, "base_1"),
+                    client_order_id: None,
+                    id: "dealer_1_quote".into(),
+                    market: None,
+                    owner: Addr::unchecked("dealer_1"),
+                    quote: coins(100, "quote_1"),
+                    status: OrderStatus::Open,
+                    status_updates: vec![],
+                    time_in_force: TimeInForce::Gtc,
+                },
+                AskOrder {
+                    base: coins(10, "base_1"),
+                    client_order_id: None,
+                    id: "dealer_2_quote".into(),
+                    market: None,
+                    owner: Addr::unchecked("dealer_2"),
+                    quote: coins(95, "quote_1"),
+                    status: OrderStatus::Open,
+                    status_updates: vec![],
+                    time_in_force: TimeInForce::Gtc,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn create_rfq_quote_with_invalid_data() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        setup_rfq(deps.as_mut());
+
+        // quote escrowing a different base returns ContractError::RfqBaseMismatch
+        match execute(
+            deps.as_mut(),
+            env_at(300),
+            mock_info("dealer_3", &coins(9, "base_1")),
+            ExecuteMsg::CreateRfqQuote {
+                rfq_id: "rfq_id".into(),
+                id: "dealer_3_quote".into(),
+                quote: coins(90, "quote_1"),
+            },
+        ) {
+            Err(ContractError::RfqBaseMismatch {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // quote reusing an id returns ContractError::RfqQuoteAlreadyExists
+        match execute(
+            deps.as_mut(),
+            env_at(300),
+            mock_info("dealer_3", &coins(10, "base_1")),
+            ExecuteMsg::CreateRfqQuote {
+                rfq_id: "rfq_id".into(),
+                id: "dealer_1_quote".into(),
+                quote: coins(90, "quote_1"),
+            },
+        ) {
+            Err(ContractError::RfqQuoteAlreadyExists {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // quote after expiration returns ContractError::RfqExpired
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("dealer_3", &coins(10, "base_1")),
+            ExecuteMsg::CreateRfqQuote {
+                rfq_id: "rfq_id".into(),
+                id: "dealer_3_quote".into(),
+                quote: coins(90, "quote_1"),
+            },
+        ) {
+            Err(ContractError::RfqExpired {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // quote ids are validated and zero quote coins rejected, they could not be paid out
+        for (id, quote) in [
+            ("dealer 3", coins(90, "quote_1")),
+            (
+                "dealer_3_quote",
+                vec![coin(90, "quote_1"), coin(0, "quote_2")],
+            ),
+        ] {
+            match execute(
+                deps.as_mut(),
+                env_at(300),
+                mock_info("dealer_3", &coins(10, "base_1")),
+                ExecuteMsg::CreateRfqQuote {
+                    rfq_id: "rfq_id".into(),
+                    id: id.into(),
+                    quote,
+                },
+            ) {
+                Err(ContractError::InvalidId { id }) => assert_eq!(id, "dealer 3"),
+                Err(ContractError::ZeroAmount { denom, field }) => {
+                    assert_eq!((denom.as_str(), field.as_str()), ("quote_2", "quote"))
+                }
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+
+        // duplicate quote coins are merged into one
+        let response = execute(
+            deps.as_mut(),
+            env_at(300),
+            mock_info("dealer_3", &coins(10, "base_1")),
+            ExecuteMsg::CreateRfqQuote {
+                rfq_id: "rfq_id".into(),
+                id: "dealer_3_quote".into(),
+                quote: vec![coin(45, "quote_1"), coin(45, "quote_1")],
+            },
+        )
+        .unwrap();
+        let rfq_quote: AskOrder = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(rfq_quote.quote, coins(90, "quote_1"));
+    }
+
+    #[test]
+    fn create_rfq_quote_over_limit() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        setup_rfq(deps.as_mut());
+
+        let create_quote = |deps: DepsMut<ProvenanceQuery>, dealer: String| {
+            execute(
+                deps,
+                env_at(300),
+                mock_info(&dealer, &coins(10, "base_1")),
+                ExecuteMsg::CreateRfqQuote {
+                    rfq_id: "rfq_id".into(),
+                    id: format!("{}_quote", dealer),
+                    quote: coins(90, "quote_1"),
+                },
+            )
+        };
+
+        // fill the rfq up to the limit alongside the two setup quotes
+        for dealer in 3..=MAX_QUOTES {
+            if let Err(error) = create_quote(deps.as_mut(), format!("dealer_{}", dealer)) {
+                panic!("unexpected error: {:?}", error)
+            }
+        }
+
+        // one more quote returns ContractError::TooManyQuotes
+        match create_quote(deps.as_mut(), "dealer_overflow".into()) {
+            Err(ContractError::TooManyQuotes { max }) => assert_eq!(max, MAX_QUOTES),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert_eq!(
+            get_rfq_quotes(&deps.storage, "rfq_id").unwrap().len(),
+            MAX_QUOTES
+        );
+    }
+
+    #[test]
+    fn accept_rfq_quote_refunds_other_quotes() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        setup_rfq(deps.as_mut());
+
+        let accept_msg = ExecuteMsg::AcceptRfqQuote {
+            rfq_id: "rfq_id".into(),
+            quote_id: "dealer_2_quote".into(),
+        };
+
+        // accept by someone other than the requester returns ContractError::Unauthorized
+        match execute(
+            deps.as_mut(),
+            env_at(300),
+            mock_info("dealer_1", &coins(95, "quote_1")),
+            accept_msg.clone(),
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // accept paying the wrong amount returns ContractError::QuoteMismatch
+        match execute(
+            deps.as_mut(),
+            env_at(300),
+            mock_info("requester", &coins(90, "quote_1")),
+            accept_msg.clone(),
+        ) {
+            Err(ContractError::QuoteMismatch { ask, bid }) => {
+                assert_eq!(ask, "95quote_1");
+                assert_eq!(bid, "90quote_1");
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // accept settles the chosen quote and refunds the other dealer
+        match execute(
+            deps.as_mut(),
+            env_at(300),
+            mock_info("requester", &coins(95, "quote_1")),
+            accept_msg,
+        ) {
+            Ok(response) => {
+                assert_eq!(
+                    response.attributes,
+                    vec![
+                        attr("action", "accept_rfq_quote"),
+                        attr("block_height", "12345"),
+                        attr("block_time", "300000000000"),
+                        attr("rfq_id", "rfq_id"),
+                        attr("ask_id", "dealer_2_quote"),
+                        attr("bid_id", "rfq_id"),
+                        attr("asker", "dealer_2"),
+                        attr("bidder", "requester"),
+                        attr("base", "10base_1"),
+                        attr("quote", "95quote_1"),
+                    ]
+                );
+                assert_eq!(response.messages.len(), 3);
+                assert_eq!(
+                    response.messages[0].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "dealer_2".into(),
+                        amount: coins(95, "quote_1"),
+                    })
+                );
+                assert_eq!(
+                    response.messages[1].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "requester".into(),
+                        amount: coins(10, "base_1"),
+                    })
+                );
+                assert_eq!(
+                    response.messages[2].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "dealer_1".into(),
+                        amount: coins(10, "base_1"),
+                    })
+                );
+            }
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+
+        // verify rfq and quotes removed from storage
+        assert!(!RFQS.has(&deps.storage, "rfq_id"));
+        assert_eq!(get_rfq_quotes(&deps.storage, "rfq_id").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn expire_rfq_refunds_all_quotes() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        setup_rfq(deps.as_mut());
+
+        let expire_msg = ExecuteMsg::ExpireRfq {
+            id: "rfq_id".into(),
+        };
+
+        // expire before the window closes returns ContractError::RfqOpen
+        match execute(
+            deps.as_mut(),
+            env_at(999),
+            mock_info("anyone", &[]),
+            expire_msg.clone(),
+        ) {
+            Err(ContractError::RfqOpen {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // accept after the window closes returns ContractError::RfqExpired
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("requester", &[coin(100, "quote_1")]),
+            ExecuteMsg::AcceptRfqQuote {
+                rfq_id: "rfq_id".into(),
+                quote_id: "dealer_1_quote".into(),
+            },
+        ) {
+            Err(ContractError::RfqExpired {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // anyone may expire the rfq once the window closes
+        match execute(
+            deps.as_mut(),
+            env_at(1_000),
+            mock_info("anyone", &[]),
+            expire_msg,
+        ) {
+            Ok(response) => {
+                assert_eq!(response.attributes[0], attr("action", "expire_rfq"));
+                assert_eq!(response.messages.len(), 2);
+                assert_eq!(
+                    response.messages[0].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "dealer_1".into(),
+                        amount: coins(10, "base_1"),
+                    })
+                );
+                assert_eq!(
+                    response.messages[1].msg,
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "dealer_2".into(),
+                        amount: coins(10, "base_1"),
+                    })
+                );
+            }
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+        assert!(!RFQS.has(&deps.storage, "rfq_id"));
+    }
+}
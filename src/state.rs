@@ -1,39 +1,708 @@
-use cosmwasm_std::{Addr, Coin, Storage, Timestamp};
+use cosmwasm_std::{
+    Addr, Coin, Decimal, Empty, Order, StdError, StdResult, Storage, Timestamp, Uint128, Uint256,
+};
 use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket};
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std::fmt;
 
 pub static NAMESPACE_ORDER_ASK: &[u8] = b"ask";
 pub static NAMESPACE_ORDER_BID: &[u8] = b"bid";
 
+pub const AUCTIONS: Map<&str, Auction> = Map::new("auction");
+// canonical id of an owner's order by client order id, keyed by (side, owner, client order id)
+pub const CLIENT_ORDER_IDS: Map<(&str, &str, &str), String> = Map::new("client_order_id");
+// conditional orders waiting for their trigger, keyed by id
+pub const CONDITIONAL_ORDERS: Map<&str, ConditionalOrder> = Map::new("conditional_order");
+// market of closed ask and bid orders keyed by (close time in nanos, side, id), oldest first for
+// pruning
+pub const CLOSED_ORDERS: Map<(u64, &str, &str), Option<String>> = Map::new("closed_order");
+// total of each denom held in escrow for open orders, auctions and quotes
+pub const ESCROW_TOTALS: Map<&str, Uint128> = Map::new("escrow_total");
+pub const CONFIG: Item<Config> = Item::new("config");
+// hashed time-locked asks keyed by id, kept after they are claimed or refunded
+pub const HTLCS: Map<&str, Htlc> = Map::new("htlc");
+pub const DEFAULT_ORDER_RETENTION: u64 = 7 * 24 * 60 * 60;
+// markets besides the default market, keyed by market id
+pub const MARKETS: Map<&str, Market> = Map::new("market");
+// last canonical id assigned to an ask or bid order
+pub const ORDER_SEQUENCE: Item<u64> = Item::new("order_sequence");
+// market of open good-till-time ask and bid orders keyed by (expiration in nanos, side, id),
+// soonest first for expiring
+pub const ORDER_EXPIRATIONS: Map<(u64, &str, &str), Option<String>> = Map::new("order_expiration");
+// operators approved by an owner, keyed by (owner, operator)
+pub const OPERATOR_APPROVALS: Map<(&str, &str), OperatorApproval> = Map::new("operator_approval");
+// market of open ask and bid orders keyed by (owner, side, id)
+pub const OWNER_ORDERS: Map<(&str, &str, &str), Option<String>> = Map::new("owner_order");
+// conditional orders of a pair keyed by (base denom, quote denom, id)
+pub const PAIR_CONDITIONAL_ORDERS: Map<(&str, &str, &str), Empty> =
+    Map::new("pair_conditional_order");
+// conditional orders of a pair by trigger, keyed by ((base denom, quote denom), trigger condition,
+// (trigger price key, id)) so triggering ranges over only the orders the reference price meets
+pub const PAIR_CONDITIONAL_TRIGGERS: Map<((&str, &str), &str, PriceIdKey), Empty> =
+    Map::new("pair_conditional_trigger");
+// statistics of trades settling a single base coin for a single quote coin, keyed by
+// (base denom, quote denom)
+pub const PAIR_STATS: Map<(&str, &str), PairStats> = Map::new("pair_stats");
+// traded volume of a pair per hour, keyed by (base denom, quote denom, hours since the epoch)
+pub const PAIR_VOLUMES: Map<(&str, &str, u64), PairVolume> = Map::new("pair_volume");
+pub const RFQS: Map<&str, Rfq> = Map::new("rfq");
+// dealer quotes escrow base exactly like an ask, keyed by (rfq id, quote id)
+pub const RFQ_QUOTES: Map<(&str, &str), AskOrder> = Map::new("rfq_quote");
+// multi-party swaps waiting for every participant to fund, keyed by id
+pub const SWAPS: Map<&str, Swap> = Map::new("swap");
+pub const TRADE_SEQUENCE: Item<u64> = Item::new("trade_sequence");
+// admin registered trading pairs keyed by (base denom, quote denom)
+pub const TRADING_PAIRS: Map<(&str, &str), TradingPair> = Map::new("trading_pair");
+// next slot of each pair's price observation ring buffer, keyed by (base denom, quote denom)
+pub const TWAP_CURSORS: Map<(&str, &str), u32> = Map::new("twap_cursor");
+// price observations keyed by (base denom, quote denom, ring buffer slot)
+pub const TWAP_OBSERVATIONS: Map<(&str, &str, u32), PriceObservation> =
+    Map::new("twap_observation");
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct AskOrder {
     pub base: Vec<Coin>,
+    /// owner supplied id, unique among the owner's open asks
+    #[serde(default)]
+    pub client_order_id: Option<String>,
     pub id: String,
+    /// market the order trades in, the default market when not set
+    #[serde(default)]
+    pub market: Option<String>,
     pub owner: Addr,
     pub quote: Vec<Coin>,
+    #[serde(default)]
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub status_updates: Vec<StatusUpdate>,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+}
+
+impl AskOrder {
+    // storage key of the order within its market's order space
+    pub fn key(&self) -> Vec<u8> {
+        order_key(self.market.as_deref(), &self.id)
+    }
+
+    // the status at a time, an open order past its good-till-time expiration is expired
+    pub fn status_at(&self, time: Timestamp) -> OrderStatus {
+        if !self.status.is_closed() && self.time_in_force.is_expired(time) {
+            OrderStatus::Expired
+        } else {
+            self.status
+        }
+    }
+
+    pub fn update_status(&mut self, status: OrderStatus, time: Timestamp, reason: Option<String>) {
+        self.status = status;
+        self.status_updates.push(StatusUpdate {
+            reason,
+            status,
+            time,
+        });
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Auction {
+    pub base: Vec<Coin>,
+    pub end_time: Timestamp,
+    pub high_bid: Option<AuctionBid>,
+    pub id: String,
+    pub min_increment: Uint128,
+    pub owner: Addr,
+    pub reserve_price: Coin,
+    /// seconds a bid placed close to `end_time` pushes the end out to, if set
+    pub time_extension: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionBid {
+    pub bidder: Addr,
+    pub quote: Coin,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BidOrder {
     pub base: Vec<Coin>,
+    /// owner supplied id, unique among the owner's open bids
+    #[serde(default)]
+    pub client_order_id: Option<String>,
     pub effective_time: Option<Timestamp>,
     pub id: String,
+    /// market the order trades in, the default market when not set
+    #[serde(default)]
+    pub market: Option<String>,
+    pub owner: Addr,
+    pub quote: Vec<Coin>,
+    #[serde(default)]
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub status_updates: Vec<StatusUpdate>,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+}
+
+// an ask or bid held in escrow but kept off the book until the pair's reference price reaches the
+// trigger price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConditionalOrder {
+    pub base: Vec<Coin>,
+    /// client order id of the order placed once triggered
+    pub client_order_id: Option<String>,
+    pub effective_time: Option<Timestamp>,
+    pub id: String,
+    pub market: Option<String>,
+    pub owner: Addr,
+    pub quote: Vec<Coin>,
+    pub side: Side,
+    pub trigger: PriceTrigger,
+}
+
+impl ConditionalOrder {
+    // the coins held in escrow, the base of an ask or the quote of a bid
+    pub fn escrow(&self) -> &[Coin] {
+        match self.side {
+            Side::Ask => &self.base,
+            Side::Bid => &self.quote,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceTrigger {
+    pub condition: TriggerCondition,
+    /// quote per unit of base
+    pub price: Decimal,
+}
+
+// (price key, id), orders of the same price sort by id
+pub type PriceIdKey<'a> = (Vec<u8>, &'a str);
+
+// a price as a storage key, big endian atomics sort in the order of the prices
+pub fn price_key(price: Decimal) -> Vec<u8> {
+    price.atomics().u128().to_be_bytes().to_vec()
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerCondition {
+    AtOrAbove,
+    AtOrBelow,
+}
+
+impl fmt::Display for TriggerCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TriggerCondition::AtOrAbove => write!(f, "at_or_above"),
+            TriggerCondition::AtOrBelow => write!(f, "at_or_below"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Ask,
+    Bid,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Side::Ask => write!(f, "ask"),
+            Side::Bid => write!(f, "bid"),
+        }
+    }
+}
+
+impl BidOrder {
+    // storage key of the order within its market's order space
+    pub fn key(&self) -> Vec<u8> {
+        order_key(self.market.as_deref(), &self.id)
+    }
+
+    // the status at a time, an open order past its good-till-time expiration is expired
+    pub fn status_at(&self, time: Timestamp) -> OrderStatus {
+        if !self.status.is_closed() && self.time_in_force.is_expired(time) {
+            OrderStatus::Expired
+        } else {
+            self.status
+        }
+    }
+
+    pub fn update_status(&mut self, status: OrderStatus, time: Timestamp, reason: Option<String>) {
+        self.status = status;
+        self.status_updates.push(StatusUpdate {
+            reason,
+            status,
+            time,
+        });
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    #[default]
+    Open,
+    Filled,
+    Cancelled,
+    Expired,
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderStatus::Open => write!(f, "open"),
+            OrderStatus::Filled => write!(f, "filled"),
+            OrderStatus::Cancelled => write!(f, "cancelled"),
+            OrderStatus::Expired => write!(f, "expired"),
+        }
+    }
+}
+
+impl OrderStatus {
+    // closed orders hold no escrow and are only kept until pruned
+    pub fn is_closed(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Expired
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeInForce {
+    /// good till cancelled
+    #[default]
+    Gtc,
+    /// immediate or cancel, the order takes a resting order when created or expires
+    Ioc,
+    /// fill or kill, the order takes a resting order when created or is not created
+    Fok,
+    /// good till time, the order cannot be matched from `expires` on
+    Gtt { expires: Timestamp },
+}
+
+impl TimeInForce {
+    pub fn is_expired(&self, time: Timestamp) -> bool {
+        matches!(self, TimeInForce::Gtt { expires } if *expires <= time)
+    }
+}
+
+impl fmt::Display for TimeInForce {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeInForce::Gtc => write!(f, "gtc"),
+            TimeInForce::Ioc => write!(f, "ioc"),
+            TimeInForce::Fok => write!(f, "fok"),
+            TimeInForce::Gtt { .. } => write!(f, "gtt"),
+        }
+    }
+}
+
+// an ask of escrowed base released to the counterparty for the preimage of its hash before the
+// timeout, and to the owner after
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Htlc {
+    pub base: Vec<Coin>,
+    pub counterparty: Addr,
+    /// hex encoded sha256 of the preimage
+    pub hash: String,
+    pub id: String,
+    pub owner: Addr,
+    /// hex encoded preimage, revealed by the claim
+    pub preimage: Option<String>,
+    pub status: HtlcStatus,
+    pub timeout: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HtlcStatus {
+    Locked,
+    Claimed,
+    Refunded,
+}
+
+impl fmt::Display for HtlcStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HtlcStatus::Locked => write!(f, "locked"),
+            HtlcStatus::Claimed => write!(f, "claimed"),
+            HtlcStatus::Refunded => write!(f, "refunded"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatusUpdate {
+    /// why an admin force cancelled the order
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub status: OrderStatus,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// denoms that orders, auctions and quotes may trade, any denom when empty
+    pub allowed_denoms: Vec<String>,
+    /// holder of restricted marker coins refunded by a force cancel
+    pub compliance_address: Option<Addr>,
+    pub fees: Option<Fees>,
+    /// addresses besides the admin that may execute matches in restricted matching mode
+    pub matchers: Vec<Addr>,
+    pub matching_mode: MatchingMode,
+    /// seconds a closed order is kept before it may be pruned
+    pub order_retention: u64,
+    /// while paused orders, auctions and quotes may be cancelled but not created, amended or settled
+    pub paused: bool,
+    /// addresses besides the admin that may pause the contract
+    pub pausers: Vec<Addr>,
+    /// contract queried for the reference price of conditional orders, the last trade price of
+    /// the pair when not set
+    #[serde(default)]
+    pub price_oracle: Option<Addr>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            allowed_denoms: vec![],
+            compliance_address: None,
+            fees: None,
+            matchers: vec![],
+            matching_mode: MatchingMode::default(),
+            order_retention: DEFAULT_ORDER_RETENTION,
+            paused: false,
+            pausers: vec![],
+            price_oracle: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Fees {
+    pub collector: Addr,
+    /// basis points of the quote of a matched ask paid to the collector instead of the asker
+    pub quote_fee_bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchingMode {
+    /// anyone may execute matches
+    Permissionless,
+    /// only the admin and matchers may execute matches
+    #[default]
+    Restricted,
+}
+
+// an isolated market with its own admin, matchers, fees and order space
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Market {
+    /// may update the market and force cancel its orders
+    pub admin: Addr,
+    pub fees: Option<Fees>,
+    pub id: String,
+    /// addresses besides the market admin that may execute matches in the market
+    pub matchers: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorApproval {
+    /// held for orders the operator places for the owner, refunded when the operator is revoked
+    pub balance: Vec<Coin>,
+    /// the approval lapses at this time, never when not set
+    pub expires: Option<Timestamp>,
+    pub operator: Addr,
     pub owner: Addr,
+}
+
+impl OperatorApproval {
+    pub fn is_active(&self, time: Timestamp) -> bool {
+        match self.expires {
+            Some(expires) => time < expires,
+            None => true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Pair {
+    pub base_denom: String,
+    pub quote_denom: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PairStats {
+    pub base_denom: String,
+    pub base_volume: Uint128,
+    pub high: Decimal,
+    pub last_price: Decimal,
+    pub last_trade_time: Timestamp,
+    pub low: Decimal,
+    pub quote_denom: String,
+    pub quote_volume: Uint128,
+    pub trade_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PairVolume {
+    pub base_volume: Uint128,
+    pub quote_volume: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceObservation {
+    /// sum of price atomics times seconds each price was in effect, up to `time`
+    pub cumulative_price: Uint256,
+    /// price in effect from `time` until the next observation
+    pub price: Decimal,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Rfq {
+    pub base: Vec<Coin>,
+    pub expiration: Timestamp,
+    pub id: String,
+    pub owner: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Swap {
+    /// participants that have funded their legs, in the order they funded
+    pub funded: Vec<Addr>,
+    pub id: String,
+    pub legs: Vec<SwapLeg>,
+    pub proposer: Addr,
+    pub status: SwapStatus,
+}
+
+impl Swap {
+    // every address that gives or receives in a leg, in order of first appearance
+    pub fn participants(&self) -> Vec<Addr> {
+        let mut participants: Vec<Addr> = vec![];
+        for leg in &self.legs {
+            for address in [&leg.from, &leg.to] {
+                if !participants.contains(address) {
+                    participants.push(address.to_owned());
+                }
+            }
+        }
+        participants
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapLeg {
+    pub coins: Vec<Coin>,
+    pub from: Addr,
+    pub to: Addr,
+}
+
+/// settled and cancelled swaps are kept so their ids are never reused
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapStatus {
+    Open,
+    Settled,
+    Cancelled,
+}
+
+impl fmt::Display for SwapStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SwapStatus::Open => write!(f, "open"),
+            SwapStatus::Settled => write!(f, "settled"),
+            SwapStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Trade {
+    pub ask: AskOrder,
+    /// base sent to the bidder
+    pub base: Vec<Coin>,
+    pub bid: BidOrder,
+    pub block_height: u64,
+    pub block_time: Timestamp,
+    /// part of the quote sent to the fee collector
+    pub fees: Vec<Coin>,
+    pub id: u64,
+    pub matcher: Addr,
+    /// quote paid by the bidder, the asker receives it less fees
     pub quote: Vec<Coin>,
 }
 
-pub fn get_ask_storage(storage: &mut dyn Storage) -> Bucket<AskOrder> {
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradingPair {
+    pub base_denom: String,
+    pub enabled: bool,
+    /// base amounts must be a multiple of the lot size
+    pub lot_size: Uint128,
+    /// largest base amount of an order
+    pub max_order_size: Option<Uint128>,
+    /// smallest base amount of an order
+    pub min_order_size: Uint128,
+    pub quote_denom: String,
+    /// prices, quote per unit of base, must be a multiple of the tick size
+    pub tick_size: Decimal,
+}
+
+// a secondary index storing a trade under each of several index values, since a trade belongs to
+// both counterparties and to every denom it settled
+pub struct TradeIndex<'a> {
+    index: fn(&Trade) -> Vec<String>,
+    idx_map: Map<'a, (String, u64), Empty>,
+}
+
+impl<'a> TradeIndex<'a> {
+    pub const fn new(index: fn(&Trade) -> Vec<String>, idx_namespace: &'a str) -> Self {
+        TradeIndex {
+            index,
+            idx_map: Map::new(idx_namespace),
+        }
+    }
+
+    pub fn trade_ids(
+        &self,
+        storage: &dyn Storage,
+        value: &str,
+        start_after: Option<u64>,
+        limit: usize,
+    ) -> StdResult<Vec<u64>> {
+        self.idx_map
+            .prefix(value.to_string())
+            .keys(
+                storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect()
+    }
+
+    fn index_values(&self, trade: &Trade) -> Vec<String> {
+        let mut values = (self.index)(trade);
+        values.sort();
+        values.dedup();
+        values
+    }
+}
+
+fn trade_id(pk: &[u8]) -> StdResult<u64> {
+    pk.try_into()
+        .map(u64::from_be_bytes)
+        .map_err(|_| StdError::generic_err("invalid trade id"))
+}
+
+impl<'a> Index<Trade> for TradeIndex<'a> {
+    fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &Trade) -> StdResult<()> {
+        let id = trade_id(pk)?;
+        for value in self.index_values(data) {
+            self.idx_map.save(store, (value, id), &Empty {})?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, store: &mut dyn Storage, pk: &[u8], old_data: &Trade) -> StdResult<()> {
+        let id = trade_id(pk)?;
+        for value in self.index_values(old_data) {
+            self.idx_map.remove(store, (value, id));
+        }
+        Ok(())
+    }
+}
+
+pub struct TradeIndexes<'a> {
+    pub denom: TradeIndex<'a>,
+    pub owner: TradeIndex<'a>,
+}
+
+impl<'a> IndexList<Trade> for TradeIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Trade>> + '_> {
+        let indexes: Vec<&dyn Index<Trade>> = vec![&self.denom, &self.owner];
+        Box::new(indexes.into_iter())
+    }
+}
+
+pub fn trades<'a>() -> IndexedMap<'a, u64, Trade, TradeIndexes<'a>> {
+    IndexedMap::new(
+        "trade",
+        TradeIndexes {
+            denom: TradeIndex::new(
+                |trade| {
+                    trade
+                        .base
+                        .iter()
+                        .chain(trade.quote.iter())
+                        .map(|coin| coin.denom.to_owned())
+                        .collect()
+                },
+                "trade__denom",
+            ),
+            owner: TradeIndex::new(
+                |trade| vec![trade.ask.owner.to_string(), trade.bid.owner.to_string()],
+                "trade__owner",
+            ),
+        },
+    )
+}
+
+// orders are keyed by (market, id), orders of the default market by id alone
+pub fn order_key(market: Option<&str>, id: &str) -> Vec<u8> {
+    match market {
+        Some(market) => [
+            (market.len() as u16).to_be_bytes().as_slice(),
+            market.as_bytes(),
+            id.as_bytes(),
+        ]
+        .concat(),
+        None => id.as_bytes().to_vec(),
+    }
+}
+
+pub fn get_ask_storage(storage: &mut dyn Storage) -> Bucket<'_, AskOrder> {
     bucket(storage, NAMESPACE_ORDER_ASK)
 }
 
-pub fn get_ask_storage_read(storage: &dyn Storage) -> ReadonlyBucket<AskOrder> {
+pub fn get_ask_storage_read(storage: &dyn Storage) -> ReadonlyBucket<'_, AskOrder> {
     bucket_read(storage, NAMESPACE_ORDER_ASK)
 }
-pub fn get_bid_storage(storage: &mut dyn Storage) -> Bucket<BidOrder> {
+pub fn get_bid_storage(storage: &mut dyn Storage) -> Bucket<'_, BidOrder> {
     bucket(storage, NAMESPACE_ORDER_BID)
 }
 
-pub fn get_bid_storage_read(storage: &dyn Storage) -> ReadonlyBucket<BidOrder> {
+pub fn get_bid_storage_read(storage: &dyn Storage) -> ReadonlyBucket<'_, BidOrder> {
     bucket_read(storage, NAMESPACE_ORDER_BID)
 }
+
+// load an ask of a market. Default market asks are keyed by the raw id, so the ask's own market is
+// checked to keep a crafted id from reaching an ask of another market.
+pub fn load_ask(storage: &dyn Storage, market: Option<&str>, id: &str) -> StdResult<AskOrder> {
+    let ask_order = get_ask_storage_read(storage).load(&order_key(market, id))?;
+    if ask_order.market.as_deref() != market {
+        return Err(StdError::not_found("AskOrder"));
+    }
+    Ok(ask_order)
+}
+
+// load a bid of a market, checking the bid's own market like load_ask
+pub fn load_bid(storage: &dyn Storage, market: Option<&str>, id: &str) -> StdResult<BidOrder> {
+    let bid_order = get_bid_storage_read(storage).load(&order_key(market, id))?;
+    if bid_order.market.as_deref() != market {
+        return Err(StdError::not_found("BidOrder"));
+    }
+    Ok(bid_order)
+}
@@ -0,0 +1,309 @@
+use cosmwasm_std::{
+    Coin, Decimal, Env, Order, StdError, StdResult, Storage, Timestamp, Uint128, Uint256,
+};
+use cw_storage_plus::Bound;
+use std::convert::TryFrom;
+
+use crate::msg::PairStatsResponse;
+use crate::state::{PairStats, PairVolume, Trade, PAIR_STATS, PAIR_VOLUMES};
+
+// decimal places of `Decimal::atomics`
+pub(crate) const DECIMAL_PLACES: u32 = 18;
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+const SECONDS_PER_HOUR: u64 = 60 * 60;
+// hourly volumes summed into the rolling 24h volume, including the current hour
+const ROLLING_HOURS: u64 = 24;
+
+// fold a trade into the statistics of its pair, trades of several base or quote coins have no
+// single price and are skipped, as are trades priced beyond what a decimal can hold
+pub fn update_pair_stats(storage: &mut dyn Storage, trade: &Trade) -> StdResult<()> {
+    let (base, quote, price) = match pair_amounts(trade) {
+        Some((base, quote)) => match checked_price(quote.amount, base.amount) {
+            Some(price) => (base, quote, price),
+            None => return Ok(()),
+        },
+        None => return Ok(()),
+    };
+    let pair = (base.denom.as_str(), quote.denom.as_str());
+
+    let stats = match PAIR_STATS.may_load(storage, pair)? {
+        Some(stats) => PairStats {
+            base_volume: stats.base_volume.checked_add(base.amount)?,
+            high: stats.high.max(price),
+            last_price: price,
+            last_trade_time: trade.block_time,
+            low: stats.low.min(price),
+            quote_volume: stats.quote_volume.checked_add(quote.amount)?,
+            trade_count: stats.trade_count + 1,
+            ..stats
+        },
+        None => PairStats {
+            base_denom: base.denom.to_owned(),
+            base_volume: base.amount,
+            high: price,
+            last_price: price,
+            last_trade_time: trade.block_time,
+            low: price,
+            quote_denom: quote.denom.to_owned(),
+            quote_volume: quote.amount,
+            trade_count: 1,
+        },
+    };
+    PAIR_STATS.save(storage, pair, &stats)?;
+
+    let hour = hours(trade.block_time);
+    PAIR_VOLUMES.update(storage, (pair.0, pair.1, hour), |volume| -> StdResult<_> {
+        let volume = volume.unwrap_or_default();
+        Ok(PairVolume {
+            base_volume: volume.base_volume.checked_add(base.amount)?,
+            quote_volume: volume.quote_volume.checked_add(quote.amount)?,
+        })
+    })?;
+
+    // drop hourly volumes that have left the rolling window
+    let expired_hours = PAIR_VOLUMES
+        .prefix(pair)
+        .keys(
+            storage,
+            None,
+            Some(Bound::exclusive(window_start(hour))),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<u64>>>()?;
+    for expired_hour in expired_hours {
+        PAIR_VOLUMES.remove(storage, (pair.0, pair.1, expired_hour));
+    }
+
+    Ok(())
+}
+
+pub fn get_pair_stats(
+    storage: &dyn Storage,
+    env: &Env,
+    base_denom: &str,
+    quote_denom: &str,
+) -> StdResult<PairStatsResponse> {
+    let stats = PAIR_STATS.load(storage, (base_denom, quote_denom))?;
+    to_response(storage, env, stats)
+}
+
+pub fn get_all_pair_stats(
+    storage: &dyn Storage,
+    env: &Env,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PairStatsResponse>> {
+    let start = start_after.as_ref().map(|(base_denom, quote_denom)| {
+        Bound::exclusive((base_denom.as_str(), quote_denom.as_str()))
+    });
+
+    PAIR_STATS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .map(|item| item.and_then(|(_, stats)| to_response(storage, env, stats)))
+        .collect()
+}
+
+fn to_response(storage: &dyn Storage, env: &Env, stats: PairStats) -> StdResult<PairStatsResponse> {
+    let mut base_volume_24h = Uint128::zero();
+    let mut quote_volume_24h = Uint128::zero();
+    for item in PAIR_VOLUMES
+        .prefix((&stats.base_denom, &stats.quote_denom))
+        .range(
+            storage,
+            Some(Bound::inclusive(window_start(hours(env.block.time)))),
+            None,
+            Order::Ascending,
+        )
+    {
+        let (_, volume) = item?;
+        base_volume_24h = base_volume_24h.checked_add(volume.base_volume)?;
+        quote_volume_24h = quote_volume_24h.checked_add(volume.quote_volume)?;
+    }
+
+    Ok(PairStatsResponse {
+        vwap: checked_price(stats.quote_volume, stats.base_volume)
+            .ok_or_else(|| StdError::generic_err("vwap is out of the decimal range"))?,
+        base_denom: stats.base_denom,
+        base_volume: stats.base_volume,
+        base_volume_24h,
+        high: stats.high,
+        last_price: stats.last_price,
+        last_trade_time: stats.last_trade_time,
+        low: stats.low,
+        quote_denom: stats.quote_denom,
+        quote_volume: stats.quote_volume,
+        quote_volume_24h,
+        trade_count: stats.trade_count,
+    })
+}
+
+// the base and quote coin of a trade settling a single coin for a single coin
+pub(crate) fn pair_amounts(trade: &Trade) -> Option<(&Coin, &Coin)> {
+    match (trade.base.as_slice(), trade.quote.as_slice()) {
+        ([base], [quote]) if !base.amount.is_zero() => Some((base, quote)),
+        _ => None,
+    }
+}
+
+// quote per unit of base, or none when the base is zero or the price exceeds the decimal range.
+// `Decimal::from_ratio` panics on the overflow instead.
+pub(crate) fn checked_price(quote: Uint128, base: Uint128) -> Option<Decimal> {
+    let atomics = Uint256::from(quote)
+        .checked_mul(Uint256::from(Decimal::one().atomics()))
+        .ok()?
+        .checked_div(Uint256::from(base))
+        .ok()?;
+    Decimal::from_atomics(Uint128::try_from(atomics).ok()?, DECIMAL_PLACES).ok()
+}
+
+fn hours(time: Timestamp) -> u64 {
+    time.seconds() / SECONDS_PER_HOUR
+}
+
+fn window_start(hour: u64) -> u64 {
+    hour.saturating_sub(ROLLING_HOURS - 1)
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{coin, coins};
+
+    use super::*;
+    use crate::testing::{env_at, trade};
+
+    #[test]
+    fn update_pair_stats_aggregates_trades() {
+        let mut storage = MockStorage::new();
+        let day = ROLLING_HOURS * SECONDS_PER_HOUR;
+
+        for (time, base, quote) in [(0, 10, 20), (day, 10, 40), (day + 60, 20, 30)] {
+            update_pair_stats(
+                &mut storage,
+                &trade(time, coins(base, "base_1"), coins(quote, "quote_1")),
+            )
+            .unwrap();
+        }
+
+        // multi coin trades have no single price
+        update_pair_stats(
+            &mut storage,
+            &trade(
+                day,
+                vec![coin(10, "base_1"), coin(10, "base_2")],
+                coins(20, "quote_1"),
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            get_pair_stats(&storage, &env_at(day + 120), "base_1", "quote_1").unwrap(),
+            PairStatsResponse {
+                base_denom: "base_1".into(),
+                base_volume: Uint128::new(40),
+                base_volume_24h: Uint128::new(30),
+                high: Decimal::from_ratio(4u128, 1u128),
+                last_price: Decimal::from_ratio(3u128, 2u128),
+                last_trade_time: Timestamp::from_seconds(day + 60),
+                low: Decimal::from_ratio(3u128, 2u128),
+                quote_denom: "quote_1".into(),
+                quote_volume: Uint128::new(90),
+                quote_volume_24h: Uint128::new(70),
+                trade_count: 3,
+                vwap: Decimal::from_ratio(9u128, 4u128),
+            }
+        );
+
+        // the rolling volume empties once the window has passed
+        let stats = get_all_pair_stats(&storage, &env_at(2 * day + 60), None, None).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].base_volume_24h, Uint128::zero());
+        assert_eq!(stats[0].base_volume, Uint128::new(40));
+
+        // the hour that left the window was pruned when the later trades were recorded
+        assert_eq!(
+            PAIR_VOLUMES
+                .prefix(("base_1", "quote_1"))
+                .keys(&storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<u64>>>()
+                .unwrap(),
+            vec![ROLLING_HOURS]
+        );
+    }
+
+    #[test]
+    fn update_pair_stats_skips_prices_out_of_range() {
+        let mut storage = MockStorage::new();
+
+        // a unit of a 0 decimal marker for 10^21 of an 18 decimal denom is too large a price
+        update_pair_stats(
+            &mut storage,
+            &trade(0, coins(1, "marker"), coins(10u128.pow(21), "nhash")),
+        )
+        .unwrap();
+        assert!(get_pair_stats(&storage, &env_at(60), "marker", "nhash").is_err());
+
+        // a price of 10^20 still fits and is recorded
+        update_pair_stats(
+            &mut storage,
+            &trade(0, coins(1, "marker"), coins(10u128.pow(20), "nhash")),
+        )
+        .unwrap();
+        let stats = get_pair_stats(&storage, &env_at(60), "marker", "nhash").unwrap();
+        assert_eq!(
+            stats.last_price,
+            Decimal::from_atomics(10u128.pow(20), 0).unwrap()
+        );
+        assert_eq!(stats.vwap, stats.last_price);
+        assert_eq!(stats.trade_count, 1);
+
+        assert_eq!(checked_price(Uint128::MAX, Uint128::new(1)), None);
+        assert_eq!(checked_price(Uint128::new(1), Uint128::zero()), None);
+    }
+
+    #[test]
+    fn get_all_pair_stats_paginates() {
+        let mut storage = MockStorage::new();
+        for (base_denom, quote_denom) in [
+            ("base_1", "quote_1"),
+            ("base_1", "quote_2"),
+            ("base_2", "quote_1"),
+        ] {
+            update_pair_stats(
+                &mut storage,
+                &trade(0, coins(10, base_denom), coins(10, quote_denom)),
+            )
+            .unwrap();
+        }
+
+        let pairs = |stats: Vec<PairStatsResponse>| {
+            stats
+                .into_iter()
+                .map(|stats| (stats.base_denom, stats.quote_denom))
+                .collect::<Vec<(String, String)>>()
+        };
+
+        assert_eq!(
+            pairs(get_all_pair_stats(&storage, &env_at(0), None, Some(2)).unwrap()),
+            vec![
+                ("base_1".to_string(), "quote_1".to_string()),
+                ("base_1".to_string(), "quote_2".to_string()),
+            ]
+        );
+        assert_eq!(
+            pairs(
+                get_all_pair_stats(
+                    &storage,
+                    &env_at(0),
+                    Some(("base_1".into(), "quote_2".into())),
+                    None
+                )
+                .unwrap()
+            ),
+            vec![("base_2".to_string(), "quote_1".to_string())]
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use cosmwasm_std::{
+    to_binary, Addr, Api, BankMsg, Coin, DepsMut, Env, MessageInfo, Response, Storage,
+};
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+
+use crate::config::check_allowed_denoms;
+use crate::contract::{add_coins, sorted_coins};
+use crate::error::ContractError;
+use crate::escrow::{deposit_escrow, release_escrow};
+use crate::events::{EventAttributes, SWAP_ID_KEY};
+use crate::msg::SwapLegMsg;
+use crate::state::{Swap, SwapLeg, SwapStatus, SWAPS};
+use crate::validation::{normalize_coins, validate_id};
+
+// most legs a single swap may have
+const MAX_LEGS: usize = 10;
+
+// propose a swap of several legs between any number of parties. The proposer must take part, and
+// proposing funds and signs its own legs.
+pub fn propose_swap(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    legs: Vec<SwapLegMsg>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    validate_id(&id)?;
+    // closed swaps are kept, so an id is never reused
+    if SWAPS.has(deps.storage, &id) {
+        return Err(ContractError::SwapAlreadyExists {});
+    }
+    if legs.is_empty() || legs.len() > MAX_LEGS {
+        return Err(ContractError::InvalidField {
+            field: "legs".into(),
+        });
+    }
+
+    let swap_legs = swap_legs(deps.api, legs)?;
+    for leg in &swap_legs {
+        check_allowed_denoms(deps.storage, &leg.coins)?;
+    }
+    let swap = Swap {
+        funded: vec![],
+        id,
+        legs: swap_legs,
+        proposer: info.sender.to_owned(),
+        status: SwapStatus::Open,
+    };
+    let funds = normalize_coins(info.funds, "funds")?;
+    let (swap, settlement) = fund(deps.storage, swap, &info.sender, &funds)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("propose_swap", &env)
+                .add(SWAP_ID_KEY, &swap.id)
+                .add("proposer", swap.proposer.as_str())
+                .add("legs", swap.legs.len().to_string())
+                .add_coins("funds", &funds)
+                .add("settled", settlement.is_some().to_string()),
+        )
+        .add_messages(settlement.unwrap_or_default())
+        .set_data(to_binary(&swap)?))
+}
+
+// fund and sign the sender's legs of a swap, settling it once every participant has funded. The
+// legs sent are the terms the sender signs, so funding fails unless they are the swap's legs.
+pub fn fund_swap(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    legs: Vec<SwapLegMsg>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let swap = load_open_swap(deps.storage, &id)?;
+    if swap_legs(deps.api, legs)? != swap.legs {
+        return Err(ContractError::SwapTermsMismatch {});
+    }
+    let funds = normalize_coins(info.funds, "funds")?;
+    let (swap, settlement) = fund(deps.storage, swap, &info.sender, &funds)?;
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("fund_swap", &env)
+                .add(SWAP_ID_KEY, &swap.id)
+                .add("participant", info.sender.as_str())
+                .add_coins("funds", &funds)
+                .add("settled", settlement.is_some().to_string()),
+        )
+        .add_messages(settlement.unwrap_or_default())
+        .set_data(to_binary(&swap)?))
+}
+
+// cancel a swap that has not settled, refunding every participant that funded it
+pub fn cancel_swap(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::CancelWithFunds {});
+    }
+
+    let mut swap = load_open_swap(deps.storage, &id)?;
+    if !swap.participants().contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    swap.status = SwapStatus::Cancelled;
+    SWAPS.save(deps.storage, &id, &swap)?;
+
+    // 'send funded coins back to participant' messages
+    let mut messages = vec![];
+    for participant in &swap.funded {
+        let refund = contribution(&swap, participant);
+        release_escrow(deps.storage, &refund)?;
+        if !refund.is_empty() {
+            messages.push(BankMsg::Send {
+                to_address: participant.to_string(),
+                amount: refund,
+            });
+        }
+    }
+
+    Ok(Response::new()
+        .add_attributes(
+            EventAttributes::new("cancel_swap", &env)
+                .add(SWAP_ID_KEY, &swap.id)
+                .add("participant", info.sender.as_str())
+                .add("refunded", swap.funded.len().to_string()),
+        )
+        .add_messages(messages)
+        .set_data(to_binary(&swap)?))
+}
+
+// escrow a participant's contribution to a swap and mark it funded, then settle the swap when every
+// participant has funded, returning the settlement messages. Participants that only receive fund
+// with no coins, which signs the swap.
+fn fund(
+    storage: &mut dyn Storage,
+    mut swap: Swap,
+    sender: &Addr,
+    funds: &[Coin],
+) -> Result<(Swap, Option<Vec<BankMsg>>), ContractError> {
+    let participants = swap.participants();
+    if !participants.contains(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if swap.funded.contains(sender) {
+        return Err(ContractError::SwapAlreadyFunded {});
+    }
+    if sorted_coins(funds) != contribution(&swap, sender) {
+        return Err(ContractError::SwapFundsMismatch {});
+    }
+    deposit_escrow(storage, funds)?;
+    swap.funded.push(sender.to_owned());
+
+    if swap.funded.len() < participants.len() {
+        SWAPS.save(storage, &swap.id, &swap)?;
+        return Ok((swap, None));
+    }
+
+    // 'send received coins to participant' messages
+    swap.status = SwapStatus::Settled;
+    SWAPS.save(storage, &swap.id, &swap)?;
+    let mut messages = vec![];
+    for participant in &participants {
+        release_escrow(storage, &contribution(&swap, participant))?;
+        let receipt = swap
+            .legs
+            .iter()
+            .filter(|leg| &leg.to == participant)
+            .fold(vec![], |receipt, leg| add_coins(&receipt, &leg.coins));
+        if !receipt.is_empty() {
+            messages.push(BankMsg::Send {
+                to_address: participant.to_string(),
+                amount: sorted_coins(&receipt),
+            });
+        }
+    }
+    Ok((swap, Some(messages)))
+}
+
+// validate the legs of a swap, each between two different addresses with coins to give
+fn swap_legs(api: &dyn Api, legs: Vec<SwapLegMsg>) -> Result<Vec<SwapLeg>, ContractError> {
+    let mut swap_legs = vec![];
+    for leg in legs {
+        let from = api.addr_validate(&leg.from)?;
+        let to = api.addr_validate(&leg.to)?;
+        if from == to || leg.coins.is_empty() {
+            return Err(ContractError::InvalidField {
+                field: "legs".into(),
+            });
+        }
+        let coins = normalize_coins(leg.coins, "coins")?;
+        swap_legs.push(SwapLeg { coins, from, to });
+    }
+    Ok(swap_legs)
+}
+
+// return error unless the swap is still open
+fn load_open_swap(storage: &dyn Storage, id: &str) -> Result<Swap, ContractError> {
+    validate_id(id)?;
+    let swap = SWAPS.load(storage, id)?;
+    if swap.status != SwapStatus::Open {
+        return Err(ContractError::SwapClosed {
+            status: swap.status.to_string(),
+        });
+    }
+    Ok(swap)
+}
+
+// the coins a participant gives across its legs of a swap, merged by denom
+fn contribution(swap: &Swap, participant: &Addr) -> Vec<Coin> {
+    sorted_coins(
+        &swap
+            .legs
+            .iter()
+            .filter(|leg| &leg.from == participant)
+            .fold(vec![], |contribution, leg| {
+                add_coins(&contribution, &leg.coins)
+            }),
+    )
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{attr, coin, coins, from_binary, CosmosMsg, Deps};
+    use provwasm_mocks::mock_dependencies;
+
+    use super::*;
+    use crate::contract::{execute, query};
+    use crate::escrow::get_escrow_totals;
+    use crate::msg::{ExecuteMsg, QueryMsg};
+
+    fn leg(from: &str, to: &str, coins: Vec<Coin>) -> SwapLegMsg {
+        SwapLegMsg {
+            coins,
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    // party a gives 10 base_1 to b, b gives 20 base_2 to c and c gives 30 base_3 to a
+    fn ring_legs() -> Vec<SwapLegMsg> {
+        vec![
+            leg("party_a", "party_b", coins(10, "base_1")),
+            leg("party_b", "party_c", coins(20, "base_2")),
+            leg("party_c", "party_a", coins(30, "base_3")),
+        ]
+    }
+
+    fn propose_ring(
+        deps: DepsMut<ProvenanceQuery>,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("party_a", &coins(10, "base_1")),
+            ExecuteMsg::ProposeSwap {
+                id: "ring".into(),
+                legs: ring_legs(),
+            },
+        )
+    }
+
+    fn fund(
+        deps: DepsMut<ProvenanceQuery>,
+        sender: &str,
+        funds: &[Coin],
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info(sender, funds),
+            ExecuteMsg::FundSwap {
+                id: "ring".into(),
+                legs: ring_legs(),
+            },
+        )
+    }
+
+    fn load_ring(deps: Deps<ProvenanceQuery>) -> Swap {
+        from_binary(&query(deps, mock_env(), QueryMsg::GetSwap { id: "ring".into() }).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn swap_settles_once_every_participant_funds() {
+        let mut deps = mock_dependencies(&[]);
+        let response = propose_ring(deps.as_mut()).unwrap();
+        assert!(response.attributes.contains(&attr("settled", "false")));
+        match propose_ring(deps.as_mut()) {
+            Err(ContractError::SwapAlreadyExists {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        match fund(deps.as_mut(), "party_d", &[]) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match fund(deps.as_mut(), "party_a", &coins(10, "base_1")) {
+            Err(ContractError::SwapAlreadyFunded {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match fund(deps.as_mut(), "party_b", &coins(10, "base_2")) {
+            Err(ContractError::SwapFundsMismatch {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        fund(deps.as_mut(), "party_b", &coins(20, "base_2")).unwrap();
+        let swap = load_ring(deps.as_ref());
+        assert_eq!(swap.status, SwapStatus::Open);
+        assert_eq!(
+            swap.funded,
+            vec![Addr::unchecked("party_a"), Addr::unchecked("party_b")]
+        );
+        assert_eq!(
+            get_escrow_totals(&deps.storage).unwrap(),
+            vec![coin(10, "base_1"), coin(20, "base_2")]
+        );
+
+        // the last participant's funds settle every leg at once
+        let response = fund(deps.as_mut(), "party_c", &coins(30, "base_3")).unwrap();
+        assert!(response.attributes.contains(&attr("settled", "true")));
+        assert_eq!(
+            response
+                .messages
+                .into_iter()
+                .map(|message| message.msg)
+                .collect::<Vec<CosmosMsg<ProvenanceMsg>>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "party_a".into(),
+                    amount: coins(30, "base_3"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "party_b".into(),
+                    amount: coins(10, "base_1"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "party_c".into(),
+                    amount: coins(20, "base_2"),
+                }),
+            ]
+        );
+        assert!(get_escrow_totals(&deps.storage).unwrap().is_empty());
+
+        // the settled swap is kept, so its id cannot be proposed again
+        assert_eq!(load_ring(deps.as_ref()).status, SwapStatus::Settled);
+        match propose_ring(deps.as_mut()) {
+            Err(ContractError::SwapAlreadyExists {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match fund(deps.as_mut(), "party_c", &[]) {
+            Err(ContractError::SwapClosed { status }) => assert_eq!(status, "settled"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn fund_swap_with_other_terms() {
+        let mut deps = mock_dependencies(&[]);
+        propose_ring(deps.as_mut()).unwrap();
+
+        // b signs only the terms it was shown, here c giving it 30 base_3 rather than a
+        let mut legs = ring_legs();
+        legs[2] = leg("party_c", "party_b", coins(30, "base_3"));
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("party_b", &coins(20, "base_2")),
+            ExecuteMsg::FundSwap {
+                id: "ring".into(),
+                legs,
+            },
+        ) {
+            Err(ContractError::SwapTermsMismatch {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert_eq!(
+            load_ring(deps.as_ref()).funded,
+            vec![Addr::unchecked("party_a")]
+        );
+    }
+
+    #[test]
+    fn any_participant_cancels_and_refunds_funded_legs() {
+        let mut deps = mock_dependencies(&[]);
+        propose_ring(deps.as_mut()).unwrap();
+        fund(deps.as_mut(), "party_b", &coins(20, "base_2")).unwrap();
+
+        let cancel = ExecuteMsg::CancelSwap { id: "ring".into() };
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("party_d", &[]),
+            cancel.to_owned(),
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let response =
+            execute(deps.as_mut(), mock_env(), mock_info("party_c", &[]), cancel).unwrap();
+        assert!(response.attributes.contains(&attr("refunded", "2")));
+        assert_eq!(response.messages.len(), 2);
+        assert_eq!(
+            response.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "party_b".into(),
+                amount: coins(20, "base_2"),
+            })
+        );
+        assert!(get_escrow_totals(&deps.storage).unwrap().is_empty());
+
+        // the cancelled swap is kept, so its id cannot be proposed again with other legs
+        assert_eq!(load_ring(deps.as_ref()).status, SwapStatus::Cancelled);
+        match propose_ring(deps.as_mut()) {
+            Err(ContractError::SwapAlreadyExists {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match fund(deps.as_mut(), "party_a", &coins(10, "base_1")) {
+            Err(ContractError::SwapClosed { status }) => assert_eq!(status, "cancelled"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn propose_swap_with_invalid_legs() {
+        let mut deps = mock_dependencies(&[]);
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("party_a", &[]),
+            ExecuteMsg::ProposeSwap {
+                id: "self".into(),
+                legs: vec![SwapLegMsg {
+                    coins: coins(10, "base_1"),
+                    from: "party_a".into(),
+                    to: "party_a".into(),
+                }],
+            },
+        ) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "legs"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        // the proposer must take part in the swap
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("party_a", &[]),
+            ExecuteMsg::ProposeSwap {
+                id: "others".into(),
+                legs: vec![SwapLegMsg {
+                    coins: coins(10, "base_1"),
+                    from: "party_b".into(),
+                    to: "party_c".into(),
+                }],
+            },
+        ) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
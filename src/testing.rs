@@ -0,0 +1,83 @@
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{Addr, Coin, DepsMut, Env, Timestamp};
+use provwasm_std::ProvenanceQuery;
+
+use crate::contract::instantiate;
+use crate::msg::{ConfigMsg, InstantiateMsg};
+use crate::state::{AskOrder, BidOrder, TimeInForce, Trade};
+
+// fixtures shared by the unit tests of each module
+
+// small deterministic xorshift generator so failures replay from their seed
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+
+    pub fn pick<'a>(&mut self, values: &[&'a str]) -> &'a str {
+        values[self.next(values.len() as u64) as usize]
+    }
+}
+
+// the mock env with the block time at the given seconds
+pub fn env_at(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(seconds);
+    env
+}
+
+// instantiate the contract with the config, administered by venue_admin
+pub fn instantiate_venue(deps: DepsMut<ProvenanceQuery>, config: ConfigMsg) {
+    instantiate(
+        deps,
+        mock_env(),
+        mock_info("venue_admin", &[]),
+        InstantiateMsg {
+            config,
+            contract_name: "contract_name".into(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+}
+
+// a trade of the base for the quote between asker and bidder at the time in seconds
+pub fn trade(time: u64, base: Vec<Coin>, quote: Vec<Coin>) -> Trade {
+    Trade {
+        ask: AskOrder {
+            base: base.to_owned(),
+            client_order_id: None,
+            id: "ask_id".into(),
+            market: None,
+            owner: Addr::unchecked("asker"),
+            quote: quote.to_owned(),
+            status: Default::default(),
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
+        },
+        base: base.to_owned(),
+        bid: BidOrder {
+            base,
+            client_order_id: None,
+            effective_time: None,
+            id: "bid_id".into(),
+            market: None,
+            owner: Addr::unchecked("bidder"),
+            quote: quote.to_owned(),
+            status: Default::default(),
+            status_updates: vec![],
+            time_in_force: TimeInForce::Gtc,
+        },
+        block_height: 12345,
+        block_time: Timestamp::from_seconds(time),
+        fees: vec![],
+        id: 1,
+        matcher: Addr::unchecked("matcher"),
+        quote,
+    }
+}
@@ -0,0 +1,587 @@
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Coin, DepsMut, Env, Order, Response, StdResult, Storage, Timestamp,
+};
+use cw_storage_plus::Bound;
+use provwasm_std::{ProvenanceMsg, ProvenanceQuery};
+
+use crate::config::check_matcher;
+use crate::contract::{load_match, save_closed_order, settle_match};
+use crate::error::ContractError;
+use crate::escrow::release_escrow;
+use crate::events::EventAttributes;
+use crate::markets::load_market;
+use crate::state::{
+    get_ask_storage, get_ask_storage_read, get_bid_storage, get_bid_storage_read, order_key,
+    AskOrder, BidOrder, OrderStatus, Side, TimeInForce, Trade, ORDER_EXPIRATIONS,
+};
+use crate::validation::validate_id;
+
+// most orders expired by a single sweep
+const EXPIRE_LIMIT: u32 = 30;
+
+// what came of a new order taking a resting order
+struct Taken {
+    messages: Vec<BankMsg>,
+    /// the trade when the new order filled
+    trade: Option<Trade>,
+}
+
+// return error if a new order cannot have the time in force. Immediate or cancel and fill or kill
+// orders only trade by taking a resting order, and a good-till-time order must expire later.
+pub fn check_time_in_force(
+    env: &Env,
+    time_in_force: TimeInForce,
+    take: Option<&str>,
+) -> Result<(), ContractError> {
+    if let Some(take) = take {
+        validate_id(take)?;
+    }
+    match time_in_force {
+        TimeInForce::Ioc | TimeInForce::Fok if take.is_none() => Err(ContractError::MissingField {
+            field: "take".into(),
+        }),
+        TimeInForce::Gtt { expires } if expires <= env.block.time => {
+            Err(ContractError::InvalidField {
+                field: "time_in_force".into(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+// respond to a new ask, which first takes the resting bid it names
+pub fn created_ask(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    ask_order: AskOrder,
+    take: Option<String>,
+    time_in_force: TimeInForce,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let (ask_order, attributes, messages) = match take {
+        Some(take) => {
+            let taken = take_order(
+                storage,
+                env,
+                sender,
+                Side::Ask,
+                &ask_order.id,
+                ask_order.market.as_deref(),
+                &take,
+                time_in_force,
+            )?;
+            let ask_order = get_ask_storage_read(storage).load(&ask_order.key())?;
+            let attributes = EventAttributes::new("create_ask", env)
+                .ask(&ask_order)
+                .operator(&ask_order.owner, sender)
+                .taken(&take, ask_order.status, taken.trade.as_ref());
+            (ask_order, attributes, taken.messages)
+        }
+        None => {
+            let attributes = EventAttributes::new("create_ask", env)
+                .ask(&ask_order)
+                .operator(&ask_order.owner, sender);
+            (ask_order, attributes, vec![])
+        }
+    };
+
+    Ok(Response::new()
+        .add_attributes(attributes)
+        .add_messages(messages)
+        .set_data(to_binary(&ask_order)?))
+}
+
+// respond to a new bid, which first takes the resting ask it names
+pub fn created_bid(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    bid_order: BidOrder,
+    take: Option<String>,
+    time_in_force: TimeInForce,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let (bid_order, attributes, messages) = match take {
+        Some(take) => {
+            let taken = take_order(
+                storage,
+                env,
+                sender,
+                Side::Bid,
+                &bid_order.id,
+                bid_order.market.as_deref(),
+                &take,
+                time_in_force,
+            )?;
+            let bid_order = get_bid_storage_read(storage).load(&bid_order.key())?;
+            let attributes = EventAttributes::new("create_bid", env)
+                .bid(&bid_order)
+                .operator(&bid_order.owner, sender)
+                .taken(&take, bid_order.status, taken.trade.as_ref());
+            (bid_order, attributes, taken.messages)
+        }
+        None => {
+            let attributes = EventAttributes::new("create_bid", env)
+                .bid(&bid_order)
+                .operator(&bid_order.owner, sender);
+            (bid_order, attributes, vec![])
+        }
+    };
+
+    Ok(Response::new()
+        .add_attributes(attributes)
+        .add_messages(messages)
+        .set_data(to_binary(&bid_order)?))
+}
+
+// match a new order with the resting order of the other side it takes, which is a match and only
+// allowed to those who may match in the market. When the orders cannot be matched a good-till
+// order stays on the book, an immediate-or-cancel order expires and its escrow is refunded, and a
+// fill-or-kill order reverts with the reason.
+#[allow(clippy::too_many_arguments)]
+fn take_order(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    side: Side,
+    id: &str,
+    market: Option<&str>,
+    take: &str,
+    time_in_force: TimeInForce,
+) -> Result<Taken, ContractError> {
+    let market_info = load_market(storage, market)?;
+    check_matcher(storage, market_info.as_ref(), sender)?;
+
+    let (ask_id, bid_id) = match side {
+        Side::Ask => (id, take),
+        Side::Bid => (take, id),
+    };
+    match load_match(storage, env, market, ask_id, bid_id) {
+        Ok((ask_order, bid_order)) => {
+            let (messages, trade) = settle_match(
+                storage,
+                env,
+                sender.to_owned(),
+                market_info.as_ref(),
+                ask_order,
+                bid_order,
+            )?;
+            Ok(Taken {
+                messages,
+                trade: Some(trade),
+            })
+        }
+        Err(failure) => match time_in_force {
+            TimeInForce::Fok => Err(failure.into()),
+            TimeInForce::Ioc => Ok(Taken {
+                messages: expire_order(storage, side, &order_key(market, id), env.block.time)?
+                    .iter()
+                    .map(Expired::refund)
+                    .collect(),
+                trade: None,
+            }),
+            TimeInForce::Gtc | TimeInForce::Gtt { .. } => Ok(Taken {
+                messages: vec![],
+                trade: None,
+            }),
+        },
+    }
+}
+
+// expire open good-till-time orders whose expiration has passed, soonest first, refunding their
+// escrow to the owners
+pub fn expire_orders(
+    deps: DepsMut<ProvenanceQuery>,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let limit = limit.unwrap_or(EXPIRE_LIMIT).min(EXPIRE_LIMIT) as usize;
+    let expirations = ORDER_EXPIRATIONS
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::exclusive((env.block.time.nanos() + 1, "", ""))),
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<((u64, String, String), Option<String>)>>>()?;
+
+    let mut expired = vec![];
+    for ((expires, side, id), market) in &expirations {
+        ORDER_EXPIRATIONS.remove(deps.storage, (*expires, side, id));
+        let side = if side == &Side::Ask.to_string() {
+            Side::Ask
+        } else {
+            Side::Bid
+        };
+        // orders filled or cancelled before expiring are left alone
+        expired.extend(expire_order(
+            deps.storage,
+            side,
+            &order_key(market.as_deref(), id),
+            env.block.time,
+        )?);
+    }
+
+    // the count, then the attributes of each expired order followed by its refund
+    let mut attributes =
+        EventAttributes::new("expire_orders", &env).add("expired", expired.len().to_string());
+    for order in &expired {
+        attributes = match order {
+            Expired::Ask(ask_order) => attributes.ask(ask_order),
+            Expired::Bid(bid_order) => attributes.bid(bid_order),
+        }
+        .add_coins("refund", order.escrow());
+    }
+
+    Ok(Response::new()
+        .add_attributes(attributes)
+        .add_messages(expired.iter().map(Expired::refund)))
+}
+
+// an order closed by expiring
+enum Expired {
+    Ask(AskOrder),
+    Bid(BidOrder),
+}
+
+impl Expired {
+    // the escrow released, the base of an ask or the quote of a bid
+    fn escrow(&self) -> &[Coin] {
+        match self {
+            Expired::Ask(ask_order) => &ask_order.base,
+            Expired::Bid(bid_order) => &bid_order.quote,
+        }
+    }
+
+    // 'send escrow back to owner' message
+    fn refund(&self) -> BankMsg {
+        let owner = match self {
+            Expired::Ask(ask_order) => &ask_order.owner,
+            Expired::Bid(bid_order) => &bid_order.owner,
+        };
+        BankMsg::Send {
+            to_address: owner.to_string(),
+            amount: self.escrow().to_vec(),
+        }
+    }
+}
+
+// mark an open order expired and release its escrow, the caller refunds the owner. Closed orders
+// are not expired again.
+fn expire_order(
+    storage: &mut dyn Storage,
+    side: Side,
+    key: &[u8],
+    time: Timestamp,
+) -> Result<Option<Expired>, ContractError> {
+    let expired = match side {
+        Side::Ask => {
+            let mut ask_storage = get_ask_storage(storage);
+            let mut ask_order = match ask_storage.may_load(key)? {
+                Some(ask_order) if !ask_order.status.is_closed() => ask_order,
+                _ => return Ok(None),
+            };
+            ask_order.update_status(OrderStatus::Expired, time, None);
+            ask_storage.save(key, &ask_order)?;
+            save_closed_order(
+                storage,
+                side,
+                &ask_order.owner,
+                &ask_order.id,
+                ask_order.market.as_deref(),
+                time,
+            )?;
+            Expired::Ask(ask_order)
+        }
+        Side::Bid => {
+            let mut bid_storage = get_bid_storage(storage);
+            let mut bid_order = match bid_storage.may_load(key)? {
+                Some(bid_order) if !bid_order.status.is_closed() => bid_order,
+                _ => return Ok(None),
+            };
+            bid_order.update_status(OrderStatus::Expired, time, None);
+            bid_storage.save(key, &bid_order)?;
+            save_closed_order(
+                storage,
+                side,
+                &bid_order.owner,
+                &bid_order.id,
+                bid_order.market.as_deref(),
+                time,
+            )?;
+            Expired::Bid(bid_order)
+        }
+    };
+    release_escrow(storage, expired.escrow())?;
+    Ok(Some(expired))
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{attr, coins, from_binary, Coin, CosmosMsg};
+    use provwasm_mocks::mock_dependencies;
+
+    use super::*;
+    use crate::contract::execute;
+    use crate::escrow::get_escrow_totals;
+    use crate::msg::{ConfigMsg, ExecuteMsg};
+    use crate::state::MatchingMode;
+    use crate::testing::instantiate_venue;
+
+    fn create_bid(
+        deps: DepsMut<ProvenanceQuery>,
+        env: Env,
+        quote: Vec<Coin>,
+        time_in_force: Option<TimeInForce>,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            env,
+            mock_info("bidder", &quote),
+            ExecuteMsg::CreateBid {
+                base: coins(100, "base_1"),
+                client_order_id: None,
+                effective_time: None,
+                market: None,
+                take: None,
+                time_in_force,
+            },
+        )
+    }
+
+    fn create_ask(
+        deps: DepsMut<ProvenanceQuery>,
+        sender: &str,
+        take: Option<&str>,
+        time_in_force: Option<TimeInForce>,
+    ) -> Result<Response<ProvenanceMsg>, ContractError> {
+        execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &coins(100, "base_1")),
+            ExecuteMsg::CreateAsk {
+                client_order_id: None,
+                quote: coins(100, "quote_1"),
+                market: None,
+                take: take.map(String::from),
+                time_in_force,
+            },
+        )
+    }
+
+    #[test]
+    fn take_falls_back_by_time_in_force() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(
+            deps.as_mut(),
+            ConfigMsg {
+                matching_mode: Some(MatchingMode::Permissionless),
+                ..Default::default()
+            },
+        );
+        // resting bids "1" at the ask's quote and "2" at a lower quote
+        create_bid(deps.as_mut(), mock_env(), coins(100, "quote_1"), None).unwrap();
+        create_bid(deps.as_mut(), mock_env(), coins(90, "quote_1"), None).unwrap();
+
+        // immediate or cancel and fill or kill orders must take a resting order
+        match create_ask(deps.as_mut(), "asker", None, Some(TimeInForce::Ioc)) {
+            Err(ContractError::MissingField { field }) => assert_eq!(field, "take"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // an immediate or cancel ask that cannot match expires and is refunded
+        let response =
+            create_ask(deps.as_mut(), "asker", Some("2"), Some(TimeInForce::Ioc)).unwrap();
+        let ask_order: AskOrder = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(ask_order.status, OrderStatus::Expired);
+        assert!(response.attributes.contains(&attr("status", "expired")));
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "asker".into(),
+                amount: coins(100, "base_1"),
+            })
+        );
+
+        // a good till cancelled ask that cannot match rests on the book
+        let response = create_ask(deps.as_mut(), "asker", Some("2"), None).unwrap();
+        let ask_order: AskOrder = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(ask_order.status, OrderStatus::Open);
+        assert!(response.messages.is_empty());
+
+        // a fill or kill ask that matches fills at once
+        let response =
+            create_ask(deps.as_mut(), "asker", Some("1"), Some(TimeInForce::Fok)).unwrap();
+        let ask_order: AskOrder = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(ask_order.status, OrderStatus::Filled);
+        assert!(response.attributes.contains(&attr("take", "1")));
+        assert!(response.attributes.contains(&attr("trade_id", "1")));
+        assert_eq!(response.messages.len(), 2);
+        assert_eq!(
+            get_escrow_totals(&deps.storage).unwrap(),
+            vec![Coin::new(100, "base_1"), Coin::new(90, "quote_1")]
+        );
+
+        // a fill or kill ask that cannot match reverts with the reason
+        match create_ask(deps.as_mut(), "asker", Some("2"), Some(TimeInForce::Fok)) {
+            Err(ContractError::QuoteMismatch { .. }) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn taking_requires_matching_rights() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(
+            deps.as_mut(),
+            ConfigMsg {
+                matching_mode: Some(MatchingMode::Restricted),
+                ..Default::default()
+            },
+        );
+        create_bid(deps.as_mut(), mock_env(), coins(100, "quote_1"), None).unwrap();
+
+        match create_ask(deps.as_mut(), "asker", Some("1"), Some(TimeInForce::Fok)) {
+            Err(ContractError::Unauthorized {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let response = create_ask(
+            deps.as_mut(),
+            "venue_admin",
+            Some("1"),
+            Some(TimeInForce::Fok),
+        )
+        .unwrap();
+        let ask_order: AskOrder = from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(ask_order.status, OrderStatus::Filled);
+    }
+
+    #[test]
+    fn good_till_time_orders_expire() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(
+            deps.as_mut(),
+            ConfigMsg {
+                matching_mode: Some(MatchingMode::Restricted),
+                ..Default::default()
+            },
+        );
+        let expires = mock_env().block.time.plus_seconds(60);
+        let mut later = mock_env();
+        later.block.time = expires;
+
+        match create_bid(
+            deps.as_mut(),
+            later.to_owned(),
+            coins(100, "quote_1"),
+            Some(TimeInForce::Gtt { expires }),
+        ) {
+            Err(ContractError::InvalidField { field }) => assert_eq!(field, "time_in_force"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        create_bid(
+            deps.as_mut(),
+            mock_env(),
+            coins(100, "quote_1"),
+            Some(TimeInForce::Gtt { expires }),
+        )
+        .unwrap();
+        create_ask(deps.as_mut(), "asker", None, None).unwrap();
+
+        // once expired the bid cannot be matched, even before it is swept
+        match execute(
+            deps.as_mut(),
+            later.to_owned(),
+            mock_info("venue_admin", &[]),
+            ExecuteMsg::ExecuteMatch {
+                ask_id: "2".into(),
+                bid_id: "1".into(),
+                market: None,
+            },
+        ) {
+            Err(ContractError::OrderClosed { status }) => assert_eq!(status, "expired"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // nor cancelled, the sweep records it expired rather than cancelled
+        for msg in [
+            ExecuteMsg::CancelBid {
+                id: "1".into(),
+                market: None,
+            },
+            ExecuteMsg::CancelMany {
+                ask_ids: vec![],
+                bid_ids: vec!["1".into()],
+                market: None,
+            },
+        ] {
+            match execute(
+                deps.as_mut(),
+                later.to_owned(),
+                mock_info("bidder", &[]),
+                msg,
+            ) {
+                Err(ContractError::OrderClosed { status }) => assert_eq!(status, "expired"),
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+        let response = execute(
+            deps.as_mut(),
+            later.to_owned(),
+            mock_info("bidder", &[]),
+            ExecuteMsg::CancelAll {
+                side: None,
+                denom: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        assert!(response.attributes.contains(&attr("cancelled", "0")));
+
+        // anyone may sweep expired orders, which refunds them
+        let expire = ExecuteMsg::ExpireOrders { limit: None };
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            expire.to_owned(),
+        )
+        .unwrap();
+        assert!(response.attributes.contains(&attr("expired", "0")));
+        let response = execute(
+            deps.as_mut(),
+            later.to_owned(),
+            mock_info("anyone", &[]),
+            expire.to_owned(),
+        )
+        .unwrap();
+        assert!(response.attributes.contains(&attr("expired", "1")));
+        for expected in [
+            attr("order_id", "1"),
+            attr("side", "bid"),
+            attr("owner", "bidder"),
+            attr("quote", "100quote_1"),
+            attr("refund", "100quote_1"),
+        ] {
+            assert!(response.attributes.contains(&expected), "{:?}", expected);
+        }
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "bidder".into(),
+                amount: coins(100, "quote_1"),
+            })
+        );
+        let bid_order = get_bid_storage_read(&deps.storage).load(b"1").unwrap();
+        assert_eq!(bid_order.status, OrderStatus::Expired);
+        assert_eq!(
+            get_escrow_totals(&deps.storage).unwrap(),
+            coins(100, "base_1")
+        );
+
+        let response = execute(deps.as_mut(), later, mock_info("anyone", &[]), expire).unwrap();
+        assert!(response.attributes.contains(&attr("expired", "0")));
+    }
+}
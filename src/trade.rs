@@ -0,0 +1,221 @@
+use cosmwasm_std::{Addr, Coin, Env, StdResult, Storage};
+
+use crate::state::{trades, AskOrder, BidOrder, Trade, TRADE_SEQUENCE};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// store a settlement receipt for a matched ask and bid under the next trade id
+pub fn record_trade(
+    storage: &mut dyn Storage,
+    env: &Env,
+    matcher: Addr,
+    ask_order: AskOrder,
+    bid_order: BidOrder,
+    fees: Vec<Coin>,
+) -> StdResult<Trade> {
+    let id = TRADE_SEQUENCE.may_load(storage)?.unwrap_or_default() + 1;
+    TRADE_SEQUENCE.save(storage, &id)?;
+
+    let trade = Trade {
+        base: bid_order.base.to_owned(),
+        quote: ask_order.quote.to_owned(),
+        ask: ask_order,
+        bid: bid_order,
+        block_height: env.block.height,
+        block_time: env.block.time,
+        fees,
+        id,
+        matcher,
+    };
+
+    trades().save(storage, id, &trade)?;
+
+    Ok(trade)
+}
+
+pub fn get_trade(storage: &dyn Storage, id: u64) -> StdResult<Trade> {
+    trades().load(storage, id)
+}
+
+pub fn get_trades_by_owner(
+    storage: &dyn Storage,
+    owner: &str,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Trade>> {
+    let trade_ids = trades()
+        .idx
+        .owner
+        .trade_ids(storage, owner, start_after, page_size(limit))?;
+    load_trades(storage, trade_ids)
+}
+
+pub fn get_trades_by_denom(
+    storage: &dyn Storage,
+    denom: &str,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Trade>> {
+    let trade_ids = trades()
+        .idx
+        .denom
+        .trade_ids(storage, denom, start_after, page_size(limit))?;
+    load_trades(storage, trade_ids)
+}
+
+fn page_size(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+fn load_trades(storage: &dyn Storage, trade_ids: Vec<u64>) -> StdResult<Vec<Trade>> {
+    trade_ids
+        .into_iter()
+        .map(|id| get_trade(storage, id))
+        .collect()
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+    use cosmwasm_std::{coin, coins, Timestamp};
+
+    use super::*;
+    use crate::state::{OrderStatus, TimeInForce};
+
+    fn matched_orders(
+        id: &str,
+        asker: &str,
+        bidder: &str,
+        quote_denom: &str,
+    ) -> (AskOrder, BidOrder) {
+        (
+            AskOrder {
+                base: coins(100, "base_1"),
+                client_order_id: None,
+                id: format!("{}_ask", id),
+                market: None,
+                owner: Addr::unchecked(asker),
+                quote: coins(10, quote_denom),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
+            },
+            BidOrder {
+                base: coins(100, "base_1"),
+                client_order_id: None,
+                effective_time: Some(Timestamp::default()),
+                id: format!("{}_bid", id),
+                market: None,
+                owner: Addr::unchecked(bidder),
+                quote: coins(10, quote_denom),
+                status: OrderStatus::Open,
+                status_updates: vec![],
+                time_in_force: TimeInForce::Gtc,
+            },
+        )
+    }
+
+    #[test]
+    fn record_trade_assigns_sequential_ids() {
+        let mut storage = MockStorage::new();
+
+        let (ask_order, bid_order) = matched_orders("first", "asker", "bidder", "quote_1");
+        let trade = record_trade(
+            &mut storage,
+            &mock_env(),
+            Addr::unchecked("matcher"),
+            ask_order.clone(),
+            bid_order.clone(),
+            vec![],
+        )
+        .unwrap();
+
+        assert_eq!(
+            trade,
+            Trade {
+                ask: ask_order,
+                base: coins(100, "base_1"),
+                bid: bid_order,
+                block_height: 12345,
+                block_time: mock_env().block.time,
+                fees: vec![],
+                id: 1,
+                matcher: Addr::unchecked("matcher"),
+                quote: vec![coin(10, "quote_1")],
+            }
+        );
+        assert_eq!(get_trade(&storage, 1).unwrap(), trade);
+
+        let (ask_order, bid_order) = matched_orders("second", "asker", "other", "quote_2");
+        let trade = record_trade(
+            &mut storage,
+            &mock_env(),
+            Addr::unchecked("matcher"),
+            ask_order,
+            bid_order,
+            vec![coin(1, "quote_2")],
+        )
+        .unwrap();
+        assert_eq!(trade.id, 2);
+        assert_eq!(trade.fees, vec![coin(1, "quote_2")]);
+    }
+
+    #[test]
+    fn get_trades_by_owner_and_denom_paginates() {
+        let mut storage = MockStorage::new();
+        for (id, bidder, quote_denom) in [
+            ("first", "bidder_1", "quote_1"),
+            ("second", "bidder_2", "quote_2"),
+            ("third", "bidder_1", "quote_2"),
+        ] {
+            let (ask_order, bid_order) = matched_orders(id, "asker", bidder, quote_denom);
+            record_trade(
+                &mut storage,
+                &mock_env(),
+                Addr::unchecked("matcher"),
+                ask_order,
+                bid_order,
+                vec![],
+            )
+            .unwrap();
+        }
+
+        let trade_ids = |trades: Vec<Trade>| trades.iter().map(|t| t.id).collect::<Vec<u64>>();
+
+        // both counterparties index the trade
+        assert_eq!(
+            trade_ids(get_trades_by_owner(&storage, "asker", None, None).unwrap()),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            trade_ids(get_trades_by_owner(&storage, "bidder_1", None, None).unwrap()),
+            vec![1, 3]
+        );
+
+        // pages continue after the last trade id seen
+        assert_eq!(
+            trade_ids(get_trades_by_owner(&storage, "asker", None, Some(2)).unwrap()),
+            vec![1, 2]
+        );
+        assert_eq!(
+            trade_ids(get_trades_by_owner(&storage, "asker", Some(2), Some(2)).unwrap()),
+            vec![3]
+        );
+
+        // base and quote denoms both index the trade
+        assert_eq!(
+            trade_ids(get_trades_by_denom(&storage, "base_1", None, None).unwrap()),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            trade_ids(get_trades_by_denom(&storage, "quote_2", None, None).unwrap()),
+            vec![2, 3]
+        );
+        assert_eq!(
+            get_trades_by_denom(&storage, "unknown", None, None).unwrap(),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use cosmwasm_std::{
+    Decimal, Env, Order, StdError, StdResult, Storage, Timestamp, Uint128, Uint256,
+};
+use std::convert::TryFrom;
+
+use crate::msg::TwapResponse;
+use crate::state::{Pair, PriceObservation, Trade, TWAP_CURSORS, TWAP_OBSERVATIONS};
+use crate::stats::{checked_price, pair_amounts, DECIMAL_PLACES};
+
+// observations kept per pair, the oldest is overwritten once the ring buffer is full
+const TWAP_CAPACITY: u32 = 100;
+
+// record the price of a settled trade, trades within the same second share one observation and
+// the last price of that second is used from then on. Trades priced beyond what a decimal can
+// hold are not observed.
+pub fn record_price_observation(storage: &mut dyn Storage, trade: &Trade) -> StdResult<()> {
+    let (base, quote, price) = match pair_amounts(trade) {
+        Some((base, quote)) => match checked_price(quote.amount, base.amount) {
+            Some(price) => (base, quote, price),
+            None => return Ok(()),
+        },
+        None => return Ok(()),
+    };
+    let pair = (base.denom.as_str(), quote.denom.as_str());
+    let time = Timestamp::from_seconds(trade.block_time.seconds());
+
+    let next = TWAP_CURSORS.may_load(storage, pair)?;
+    let latest = match next {
+        Some(next) => {
+            let slot = (next + TWAP_CAPACITY - 1) % TWAP_CAPACITY;
+            Some((
+                slot,
+                TWAP_OBSERVATIONS.load(storage, (pair.0, pair.1, slot))?,
+            ))
+        }
+        None => None,
+    };
+
+    let (slot, cumulative_price) = match latest {
+        Some((slot, latest)) if latest.time == time => (slot, latest.cumulative_price),
+        Some((_, latest)) => (
+            next.unwrap_or_default(),
+            cumulative_price_at(&latest, time.seconds())?,
+        ),
+        None => (0, Uint256::zero()),
+    };
+
+    TWAP_OBSERVATIONS.save(
+        storage,
+        (pair.0, pair.1, slot),
+        &PriceObservation {
+            cumulative_price,
+            price,
+            time,
+        },
+    )?;
+    TWAP_CURSORS.save(storage, pair, &((slot + 1) % TWAP_CAPACITY))
+}
+
+// time weighted average price over the window ending at the current block, the window must lie
+// within the retained observations
+pub fn get_twap(
+    storage: &dyn Storage,
+    env: &Env,
+    pair: &Pair,
+    window_seconds: u64,
+) -> StdResult<TwapResponse> {
+    if window_seconds == 0 {
+        return Err(StdError::generic_err(
+            "window_seconds must be greater than zero",
+        ));
+    }
+    let end = env.block.time.seconds();
+    let start = end
+        .checked_sub(window_seconds)
+        .ok_or_else(|| StdError::generic_err("window starts before the epoch"))?;
+
+    let mut observations = TWAP_OBSERVATIONS
+        .prefix((&pair.base_denom, &pair.quote_denom))
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, observation)| observation))
+        .collect::<StdResult<Vec<PriceObservation>>>()?;
+    observations.sort_by_key(|observation| observation.time);
+
+    let cumulative_price = |time: u64| match observations
+        .iter()
+        .rev()
+        .find(|observation| observation.time.seconds() <= time)
+    {
+        Some(observation) => cumulative_price_at(observation, time),
+        None => Err(StdError::generic_err(
+            "not enough price observations for window",
+        )),
+    };
+
+    let price_seconds = cumulative_price(end)?.checked_sub(cumulative_price(start)?)?;
+    let price_atomics = Uint128::try_from(price_seconds / Uint256::from(window_seconds))
+        .map_err(|error| StdError::generic_err(error.to_string()))?;
+
+    Ok(TwapResponse {
+        end_time: Timestamp::from_seconds(end),
+        price: Decimal::from_atomics(price_atomics, DECIMAL_PLACES)
+            .map_err(|error| StdError::generic_err(error.to_string()))?,
+        start_time: Timestamp::from_seconds(start),
+    })
+}
+
+// extend an observation's cumulative price to a later time at the observed price
+fn cumulative_price_at(observation: &PriceObservation, time: u64) -> StdResult<Uint256> {
+    let elapsed = time - observation.time.seconds();
+    let price_seconds =
+        Uint256::from(observation.price.atomics()).checked_mul(Uint256::from(elapsed))?;
+    Ok(observation.cumulative_price.checked_add(price_seconds)?)
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::coins;
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+    use crate::testing::{env_at, trade};
+
+    fn twap_at(storage: &dyn Storage, seconds: u64, window_seconds: u64) -> StdResult<Decimal> {
+        let env = env_at(seconds);
+        let pair = Pair {
+            base_denom: "base_1".into(),
+            quote_denom: "quote_1".into(),
+        };
+        get_twap(storage, &env, &pair, window_seconds).map(|twap| twap.price)
+    }
+
+    #[test]
+    fn twap_weights_prices_by_time() {
+        let mut storage = MockStorage::new();
+        record_price_observation(
+            &mut storage,
+            &trade(100, coins(10, "base_1"), coins(20, "quote_1")),
+        )
+        .unwrap();
+        // the last trade within a second sets the price for that second
+        record_price_observation(
+            &mut storage,
+            &trade(200, coins(10, "base_1"), coins(10, "quote_1")),
+        )
+        .unwrap();
+        record_price_observation(
+            &mut storage,
+            &trade(200, coins(10, "base_1"), coins(40, "quote_1")),
+        )
+        .unwrap();
+
+        // a window within a single price
+        assert_eq!(twap_at(&storage, 300, 100), Ok(Decimal::percent(400)));
+        // a window starting exactly at the first observation
+        assert_eq!(twap_at(&storage, 300, 200), Ok(Decimal::percent(300)));
+        // a window starting between observations
+        assert_eq!(
+            twap_at(&storage, 300, 150),
+            Ok(Decimal::from_ratio(500u128, 150u128))
+        );
+        // a window starting before the first observation
+        match twap_at(&storage, 300, 201) {
+            Err(StdError::GenericErr { .. }) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match twap_at(&storage, 300, 0) {
+            Err(StdError::GenericErr { .. }) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn twap_with_sparse_trades() {
+        let mut storage = MockStorage::new();
+        record_price_observation(
+            &mut storage,
+            &trade(100, coins(10, "base_1"), coins(20, "quote_1")),
+        )
+        .unwrap();
+
+        // the last price holds until the next trade
+        assert_eq!(
+            twap_at(&storage, 100_000, 50_000),
+            Ok(Decimal::percent(200))
+        );
+
+        record_price_observation(
+            &mut storage,
+            &trade(100_000, coins(10, "base_1"), coins(40, "quote_1")),
+        )
+        .unwrap();
+        assert_eq!(
+            twap_at(&storage, 100_000, 50_000),
+            Ok(Decimal::percent(200))
+        );
+        assert_eq!(
+            twap_at(&storage, 150_000, 100_000),
+            Ok(Decimal::percent(300))
+        );
+    }
+
+    #[test]
+    fn twap_ring_buffer_overwrites_oldest_observation() {
+        let mut storage = MockStorage::new();
+        for time in 1..=(TWAP_CAPACITY as u64 + 1) {
+            record_price_observation(
+                &mut storage,
+                &trade(time, coins(10, "base_1"), coins(20, "quote_1")),
+            )
+            .unwrap();
+        }
+
+        // the observation at one second was overwritten
+        match twap_at(&storage, 200, 199) {
+            Err(StdError::GenericErr { .. }) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert_eq!(twap_at(&storage, 200, 198), Ok(Decimal::percent(200)));
+    }
+
+    #[test]
+    fn twap_skips_prices_out_of_range() {
+        let mut storage = MockStorage::new();
+        record_price_observation(
+            &mut storage,
+            &trade(100, coins(10, "base_1"), coins(20, "quote_1")),
+        )
+        .unwrap();
+        // 10^21 quote for a single base overflows a decimal and is not observed
+        record_price_observation(
+            &mut storage,
+            &trade(200, coins(1, "base_1"), coins(10u128.pow(21), "quote_1")),
+        )
+        .unwrap();
+
+        assert_eq!(twap_at(&storage, 300, 200), Ok(Decimal::percent(200)));
+    }
+}
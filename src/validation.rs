@@ -0,0 +1,345 @@
+use cosmwasm_std::{Coin, StdError};
+
+use crate::error::ContractError;
+
+// longest accepted client order id
+pub const MAX_ID_LENGTH: usize = 64;
+// most coins accepted in a single coin vector
+pub const MAX_COINS: usize = 10;
+// longest accepted force cancel reason
+pub const MAX_REASON_LENGTH: usize = 256;
+
+// client order ids are 1 to MAX_ID_LENGTH ascii letters, digits, '.', '_' or '-'
+pub fn validate_id(id: &str) -> Result<(), ContractError> {
+    if id.is_empty()
+        || id.len() > MAX_ID_LENGTH
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err(ContractError::InvalidId { id: id.into() });
+    }
+    Ok(())
+}
+
+// orders are named by an id within an optional market, both plain ids
+pub fn validate_order_id(id: &str, market: Option<&str>) -> Result<(), ContractError> {
+    validate_id(id)?;
+    if let Some(market) = market {
+        validate_id(market)?;
+    }
+    Ok(())
+}
+
+// force cancel reasons are required and kept in the order's status history
+pub fn validate_reason(reason: &str) -> Result<(), ContractError> {
+    if reason.trim().is_empty() {
+        return Err(ContractError::MissingField {
+            field: "reason".into(),
+        });
+    }
+    if reason.len() > MAX_REASON_LENGTH {
+        return Err(ContractError::InvalidField {
+            field: "reason".into(),
+        });
+    }
+    Ok(())
+}
+
+// denoms must match the cosmos sdk denom regex `[a-zA-Z][a-zA-Z0-9/:._-]{2,127}`
+pub fn validate_denom(denom: &str) -> Result<(), ContractError> {
+    let mut chars = denom.chars();
+    let valid = (3..=128).contains(&denom.len())
+        && matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | ':' | '.' | '_' | '-'));
+    if !valid {
+        return Err(ContractError::InvalidDenom {
+            denom: denom.into(),
+        });
+    }
+    Ok(())
+}
+
+// validate a coin vector and merge coins of the same denom, keeping the order in which denoms
+// first appear
+pub fn normalize_coins(coins: Vec<Coin>, field: &str) -> Result<Vec<Coin>, ContractError> {
+    if coins.len() > MAX_COINS {
+        return Err(ContractError::TooManyCoins {
+            field: field.into(),
+            max: MAX_COINS,
+        });
+    }
+
+    let mut normalized: Vec<Coin> = Vec::with_capacity(coins.len());
+    for coin in coins {
+        validate_denom(&coin.denom)?;
+        if coin.amount.is_zero() {
+            return Err(ContractError::ZeroAmount {
+                denom: coin.denom,
+                field: field.into(),
+            });
+        }
+        match normalized.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(existing) => {
+                existing.amount = existing
+                    .amount
+                    .checked_add(coin.amount)
+                    .map_err(StdError::from)?
+            }
+            None => normalized.push(coin),
+        }
+    }
+    Ok(normalized)
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{coin, coins, from_binary, Uint128};
+    use provwasm_mocks::mock_dependencies;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::*;
+    use crate::contract::execute;
+    use crate::msg::{ConfigMsg, ExecuteMsg};
+    use crate::state::{AskOrder, BidOrder};
+    use crate::testing::{instantiate_venue, Rng};
+
+    #[test]
+    fn normalize_coins_merges_and_rejects() {
+        assert_eq!(
+            normalize_coins(
+                vec![coin(1, "quote_1"), coin(2, "base_1"), coin(3, "quote_1")],
+                "quote"
+            )
+            .unwrap(),
+            vec![coin(4, "quote_1"), coin(2, "base_1")]
+        );
+
+        match normalize_coins(vec![coin(1, "quote_1"), coin(0, "base_1")], "quote") {
+            Err(ContractError::ZeroAmount { denom, field }) => {
+                assert_eq!((denom.as_str(), field.as_str()), ("base_1", "quote"))
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        for denom in ["", "ab", "1abc", "bad denom", "quöte", &"a".repeat(129)] {
+            match normalize_coins(coins(1, denom), "quote") {
+                Err(ContractError::InvalidDenom { denom: invalid }) => assert_eq!(invalid, denom),
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+        for denom in [
+            "abc",
+            "nhash",
+            "ibc/27394FB092D2ECCD",
+            "a:b.c_d-e",
+            &"a".repeat(128),
+        ] {
+            assert!(normalize_coins(coins(1, denom), "quote").is_ok());
+        }
+        match normalize_coins((0..=MAX_COINS).map(|_| coin(1, "base_1")).collect(), "base") {
+            Err(ContractError::TooManyCoins { field, max }) => {
+                assert_eq!((field.as_str(), max), ("base", MAX_COINS))
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn validate_id_limits_length_and_charset() {
+        for id in ["ask_id", "ab-12.CD", &"a".repeat(MAX_ID_LENGTH)] {
+            assert!(validate_id(id).is_ok(), "{:?}", id);
+        }
+        for id in [
+            "",
+            "ask id",
+            "ask/id",
+            "äsk",
+            &"a".repeat(MAX_ID_LENGTH + 1),
+        ] {
+            match validate_id(id) {
+                Err(ContractError::InvalidId { id: invalid }) => assert_eq!(invalid, id),
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+    }
+
+    // ids of every length around the limit, mostly valid
+    fn random_id(rng: &mut Rng) -> String {
+        let chars = ["a", "Z", "0", "_", "-", ".", " ", "/", "é"];
+        let length = rng.next(MAX_ID_LENGTH as u64 + 3);
+        (0..length)
+            .map(|_| match rng.next(20) {
+                0 => rng.pick(&chars),
+                _ => rng.pick(&chars[..3]),
+            })
+            .collect()
+    }
+
+    // coin vectors around the length limit, with duplicate, invalid and zero coins
+    fn random_coins(rng: &mut Rng) -> Vec<Coin> {
+        let denoms = ["base_1", "quote_1", "ibc/ABC", "nhash", "", "1abc", "x y"];
+        let length = match rng.next(10) {
+            0 => MAX_COINS as u64 + 1 + rng.next(3),
+            _ => rng.next(4),
+        };
+        (0..length)
+            .map(|_| {
+                let denom = match rng.next(10) {
+                    0 => rng.pick(&denoms),
+                    _ => rng.pick(&denoms[..4]),
+                };
+                let amount = match rng.next(10) {
+                    0 => 0,
+                    _ => 1 + rng.next(100) as u128,
+                };
+                coin(amount, denom)
+            })
+            .collect()
+    }
+
+    fn is_valid(coins: &[Coin]) -> bool {
+        !coins.is_empty()
+            && coins.len() <= MAX_COINS
+            && coins
+                .iter()
+                .all(|c| !c.amount.is_zero() && validate_denom(&c.denom).is_ok())
+    }
+
+    fn totals(coins: &[Coin]) -> BTreeMap<String, Uint128> {
+        let mut totals = BTreeMap::new();
+        for coin in coins {
+            *totals.entry(coin.denom.to_owned()).or_default() += coin.amount;
+        }
+        totals
+    }
+
+    fn assert_normalized(stored: &[Coin], sent: &[Coin]) {
+        let denoms = stored.iter().map(|c| &c.denom).collect::<BTreeSet<_>>();
+        assert_eq!(denoms.len(), stored.len(), "duplicate denoms: {:?}", stored);
+        assert_eq!(totals(stored), totals(sent));
+    }
+
+    #[test]
+    fn random_order_inputs_are_validated() {
+        for seed in 1..=25 {
+            let mut rng = Rng(seed);
+            let mut deps = mock_dependencies(&[]);
+            instantiate_venue(deps.as_mut(), ConfigMsg::default());
+            let mut ask_ids = BTreeSet::new();
+            let mut bid_ids = BTreeSet::new();
+
+            for _ in 0..200 {
+                let id = random_id(&mut rng);
+                let funds = random_coins(&mut rng);
+                let requested = random_coins(&mut rng);
+                let is_ask = rng.next(2) == 0;
+                let msg = if is_ask {
+                    ExecuteMsg::CreateAsk {
+                        client_order_id: Some(id.to_owned()),
+                        quote: requested.to_owned(),
+                        market: None,
+                        take: None,
+                        time_in_force: None,
+                    }
+                } else {
+                    ExecuteMsg::CreateBid {
+                        base: requested.to_owned(),
+                        client_order_id: Some(id.to_owned()),
+                        effective_time: None,
+                        market: None,
+                        take: None,
+                        time_in_force: None,
+                    }
+                };
+
+                let valid = validate_id(&id).is_ok() && is_valid(&funds) && is_valid(&requested);
+                let result = execute(deps.as_mut(), mock_env(), mock_info("owner", &funds), msg);
+                let ids = if is_ask { &mut ask_ids } else { &mut bid_ids };
+
+                match result {
+                    Ok(response) => {
+                        assert!(valid, "seed {}: accepted {:?} {:?}", seed, id, funds);
+                        assert!(ids.insert(id.to_owned()));
+                        let data = response.data.unwrap();
+                        let (base, quote, client_order_id) = if is_ask {
+                            let order: AskOrder = from_binary(&data).unwrap();
+                            (order.base, order.quote, order.client_order_id)
+                        } else {
+                            let order: BidOrder = from_binary(&data).unwrap();
+                            (order.base, order.quote, order.client_order_id)
+                        };
+                        assert_eq!(client_order_id, Some(id));
+                        let (sent_base, sent_quote) = if is_ask {
+                            (&funds, &requested)
+                        } else {
+                            (&requested, &funds)
+                        };
+                        assert_normalized(&base, sent_base);
+                        assert_normalized(&quote, sent_quote);
+                    }
+                    Err(ContractError::ClientOrderIdExists { .. }) => {
+                        assert!(valid && ids.contains(&id), "seed {}: {:?}", seed, id)
+                    }
+                    Err(
+                        ContractError::InvalidDenom { .. }
+                        | ContractError::InvalidId { .. }
+                        | ContractError::MissingAskBase
+                        | ContractError::MissingBidQuote
+                        | ContractError::MissingField { .. }
+                        | ContractError::TooManyCoins { .. }
+                        | ContractError::ZeroAmount { .. },
+                    ) => assert!(!valid, "seed {}: rejected {:?} {:?}", seed, id, funds),
+                    Err(error) => panic!("seed {}: unexpected error: {:?}", seed, error),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ids_naming_orders_are_validated() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_venue(deps.as_mut(), ConfigMsg::default());
+        let invalid_id = "ask id";
+        for (sender, msg) in [
+            (
+                "venue_admin",
+                ExecuteMsg::ExecuteMatch {
+                    ask_id: "1".into(),
+                    bid_id: invalid_id.into(),
+                    market: None,
+                },
+            ),
+            (
+                "asker",
+                ExecuteMsg::UpdateAsk {
+                    id: invalid_id.into(),
+                    quote: None,
+                    base_refund: None,
+                    market: None,
+                },
+            ),
+            (
+                "asker",
+                ExecuteMsg::CancelMany {
+                    ask_ids: vec![invalid_id.into()],
+                    bid_ids: vec![],
+                    market: None,
+                },
+            ),
+            (
+                "asker",
+                ExecuteMsg::CancelAsk {
+                    id: "1".into(),
+                    market: Some(invalid_id.into()),
+                },
+            ),
+        ] {
+            match execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg) {
+                Err(ContractError::InvalidId { id }) => assert_eq!(id, invalid_id),
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+    }
+}